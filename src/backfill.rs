@@ -0,0 +1,60 @@
+use anyhow::{Context, Result};
+
+use crate::{
+    clock::Clock,
+    git::{self, backfill::PastPush},
+    scoring,
+    state::State,
+    storage::{PatchIdStore, PushEntry, PushHistory},
+};
+
+/// imports past pushes of the current repo into push history.
+/// points are only awarded if asked for
+pub fn run(
+    state: &mut State,
+    history: &PushHistory,
+    patch_ids: &PatchIdStore,
+    award_points: bool,
+) -> Result<()> {
+    let cwd = std::env::current_dir().context("could not get current directory")?;
+    let repo_path = git::repo::git_work_dir(&cwd).context("not a git or jj repository")?;
+
+    let past_pushes = git::backfill::find_past_pushes(&repo_path, patch_ids)?;
+    if past_pushes.is_empty() {
+        println!("no new pushes found in this repo's reflogs");
+        return Ok(());
+    }
+
+    let tz_offset_secs = Clock::from_now().tz_offset_secs();
+    let mut commits = 0;
+    let mut points = 0;
+
+    for PastPush { timestamp, push } in &past_pushes {
+        // scored as of when the push happened, so day-based bonuses line up
+        let (earned, commit_points) = if award_points {
+            let clock = Clock::with_offset(*timestamp, tz_offset_secs);
+            let breakdown = scoring::calculate_points(push, state, history, &clock);
+            state.earn_points(breakdown.total);
+            (breakdown.total, breakdown.commit_points)
+        } else {
+            (0, Vec::new())
+        };
+
+        history.record(
+            &PushEntry::from_push(*timestamp, push, earned).with_commit_points(commit_points),
+        )?;
+        commits += push.commits().len();
+        points += earned;
+    }
+
+    println!(
+        "imported {} past pushes with {} commits",
+        past_pushes.len(),
+        commits
+    );
+    if award_points {
+        println!("earned {points} party points");
+    }
+
+    Ok(())
+}
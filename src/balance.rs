@@ -0,0 +1,403 @@
+//! Economy numbers (prices, rewards and odds) tuned from a JSON file instead of in code,
+//! so they can be changed without a rebuild. The file is the same one that
+//! `tools/economy-sim.html` loads and saves. Every section is optional and anything left
+//! out keeps its built-in value:
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "pack_accrual_rate": 25,
+//!   "packs": { "basic": 1000, "premium": 10000 },
+//!   "pack_item_points": { "common": 25, "rare": 100, "epic": 400, "legendary": 1600 },
+//!   "rarity_upgrade_chances": { "common": 0.1, "rare": 0.2, "epic": 0.2 },
+//!   "bonus_tracks": {
+//!     "commit_value": [
+//!       { "cost": 0, "points": 1 },
+//!       { "cost": 50, "points": 2 }
+//!     ]
+//!   },
+//!   "parties": { "fireworks": 8000 },
+//!   "games": { "snake": 300 },
+//!   "treasure_depths": { "visibility": [0, 150, 300] },
+//!   "respec": { "refund_percent": 50, "cooldown_hours": 24 }
+//! }
+//! ```
+//!
+//! A track's tiers replace all of its built-in tiers, so there must be as many of them,
+//! each with the same kind of reward. Costs have to go up from tier to tier.
+
+use std::{collections::HashMap, path::Path, sync::OnceLock};
+
+use anyhow::{Context, Result, bail};
+use serde::Deserialize;
+
+use crate::{
+    bonus_track::{ALL_TRACKS, Reward, Tier},
+    game::{ALL_GAMES, default_upgrade_costs},
+    pack::{ALL_PACKS, Rarity},
+    party::ALL_PARTIES,
+};
+
+/// the only version of the format so far. bumped when a change would make an older
+/// file mean something different
+const VERSION: u32 = 1;
+
+const RARITIES: [Rarity; 4] = [
+    Rarity::Common,
+    Rarity::Rare,
+    Rarity::Epic,
+    Rarity::Legendary,
+];
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BalanceFile {
+    version: u32,
+    pack_accrual_rate: Option<u64>,
+    #[serde(default)]
+    packs: HashMap<String, u64>,
+    #[serde(default)]
+    pack_item_points: HashMap<String, u64>,
+    #[serde(default)]
+    rarity_upgrade_chances: HashMap<String, f64>,
+    #[serde(default)]
+    bonus_tracks: HashMap<String, Vec<TierDef>>,
+    #[serde(default)]
+    parties: HashMap<String, u64>,
+    #[serde(default)]
+    games: HashMap<String, u64>,
+    #[serde(default)]
+    treasure_depths: HashMap<String, Vec<u64>>,
+    #[serde(default)]
+    respec: RespecDef,
+}
+
+/// selling back bonus track levels
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RespecDef {
+    refund_percent: Option<u64>,
+    cooldown_hours: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TierDef {
+    cost: u64,
+    multiplier: Option<u32>,
+    points: Option<u64>,
+}
+
+/// the overrides from the balance file. each getter takes the built-in value to fall
+/// back on, so the defaults stay next to the code that uses them
+#[derive(Debug, Default)]
+pub struct Balance {
+    pack_accrual_rate: Option<u64>,
+    packs: HashMap<String, u64>,
+    pack_item_points: HashMap<String, u64>,
+    rarity_upgrade_chances: HashMap<String, f64>,
+    bonus_tracks: HashMap<String, Vec<Tier>>,
+    parties: HashMap<String, u64>,
+    games: HashMap<String, u64>,
+    treasure_depths: HashMap<String, Vec<u64>>,
+    respec_refund_percent: Option<u64>,
+    respec_cooldown_hours: Option<u64>,
+}
+
+static BALANCE: OnceLock<Balance> = OnceLock::new();
+
+/// the loaded balance, or no overrides at all if there's no (valid) balance file
+pub fn balance() -> &'static Balance {
+    BALANCE.get_or_init(Balance::default)
+}
+
+/// reads the balance file so that `balance` returns its overrides. a missing file
+/// overrides nothing; an invalid one is an error and overrides nothing either
+pub fn load() -> Result<()> {
+    let path = crate::storage::balance_path()?;
+    let balance = read(&path)?;
+    let _ = BALANCE.set(balance);
+    Ok(())
+}
+
+fn read(path: &Path) -> Result<Balance> {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Balance::default()),
+        Err(e) => return Err(e).with_context(|| format!("could not read {}", path.display())),
+    };
+    parse(&content).with_context(|| format!("invalid {}", path.display()))
+}
+
+fn parse(content: &str) -> Result<Balance> {
+    let file: BalanceFile = serde_json::from_str(content)?;
+    if file.version != VERSION {
+        bail!("unsupported version {} (expected {VERSION})", file.version);
+    }
+
+    if file.pack_accrual_rate == Some(0) {
+        bail!("pack_accrual_rate must be at least 1");
+    }
+
+    let pack_ids: Vec<&str> = ALL_PACKS.iter().map(|p| p.id()).collect();
+    check_ids("pack", file.packs.keys(), &pack_ids)?;
+
+    let rarity_ids: Vec<&str> = RARITIES.iter().map(|r| r.id()).collect();
+    check_ids("rarity", file.pack_item_points.keys(), &rarity_ids)?;
+    // nothing upgrades past legendary
+    check_ids(
+        "upgradable rarity",
+        file.rarity_upgrade_chances.keys(),
+        &rarity_ids[..3],
+    )?;
+    for (rarity, chance) in &file.rarity_upgrade_chances {
+        if !(0.0..1.0).contains(chance) {
+            bail!("the upgrade chance for `{rarity}` should be at least 0 and below 1");
+        }
+    }
+
+    let mut bonus_tracks = HashMap::new();
+    for (id, defs) in file.bonus_tracks {
+        let Some(track) = ALL_TRACKS.iter().find(|t| t.id() == id) else {
+            bail!("unknown bonus track `{id}`");
+        };
+        let builtin = track.tiers();
+        if defs.len() != builtin.len() {
+            bail!(
+                "bonus track `{id}` should have {} tiers, not {}",
+                builtin.len(),
+                defs.len()
+            );
+        }
+
+        let mut tiers = Vec::with_capacity(defs.len());
+        for (def, builtin) in defs.iter().zip(builtin) {
+            let reward = match (builtin.reward, def.multiplier, def.points) {
+                (Reward::Multiplier(_), Some(m), None) if m >= 1 => Reward::Multiplier(m),
+                (Reward::FlatPoints(_), None, Some(p)) => Reward::FlatPoints(p),
+                (Reward::Multiplier(_), _, _) => {
+                    bail!("each tier of bonus track `{id}` needs a `multiplier` of at least 1")
+                }
+                (Reward::FlatPoints(_), _, _) => {
+                    bail!("each tier of bonus track `{id}` needs `points`")
+                }
+            };
+            tiers.push(Tier {
+                cost: def.cost,
+                reward,
+            });
+        }
+        check_increasing(&format!("bonus track `{id}`"), tiers.iter().map(|t| t.cost))?;
+        bonus_tracks.insert(id, tiers);
+    }
+
+    let party_ids: Vec<&str> = ALL_PARTIES.iter().map(|p| p.info.id).collect();
+    check_ids("party", file.parties.keys(), &party_ids)?;
+
+    let game_ids: Vec<&str> = ALL_GAMES.iter().map(|g| g.id()).collect();
+    check_ids("game", file.games.keys(), &game_ids)?;
+
+    for (id, costs) in &file.treasure_depths {
+        let Some(builtin) = default_upgrade_costs(id) else {
+            bail!("unknown treasure depths upgrade `{id}`");
+        };
+        if costs.len() != builtin.len() || costs.first() != Some(&0) {
+            bail!(
+                "treasure depths upgrade `{id}` should have {} costs, starting with 0",
+                builtin.len()
+            );
+        }
+        check_increasing(
+            &format!("treasure depths upgrade `{id}`"),
+            costs.iter().copied(),
+        )?;
+    }
+
+    if file.respec.refund_percent.is_some_and(|p| p > 100) {
+        bail!("respec refund_percent can't be more than 100");
+    }
+
+    Ok(Balance {
+        pack_accrual_rate: file.pack_accrual_rate,
+        packs: file.packs,
+        pack_item_points: file.pack_item_points,
+        rarity_upgrade_chances: file.rarity_upgrade_chances,
+        bonus_tracks,
+        parties: file.parties,
+        games: file.games,
+        treasure_depths: file.treasure_depths,
+        respec_refund_percent: file.respec.refund_percent,
+        respec_cooldown_hours: file.respec.cooldown_hours,
+    })
+}
+
+fn check_ids<'a>(what: &str, ids: impl Iterator<Item = &'a String>, known: &[&str]) -> Result<()> {
+    for id in ids {
+        if !known.contains(&id.as_str()) {
+            bail!("unknown {what} `{id}`");
+        }
+    }
+    Ok(())
+}
+
+/// each tier has to cost more than the one before it
+fn check_increasing(what: &str, costs: impl Iterator<Item = u64>) -> Result<()> {
+    let mut previous = None;
+    for cost in costs {
+        if previous.is_some_and(|p| cost <= p) {
+            bail!("{what} has a tier that costs no more than the one before it");
+        }
+        previous = Some(cost);
+    }
+    Ok(())
+}
+
+impl Balance {
+    pub fn pack_accrual_rate(&self, default: u64) -> u64 {
+        self.pack_accrual_rate.unwrap_or(default)
+    }
+
+    pub fn pack_cost(&self, id: &str, default: u64) -> u64 {
+        self.packs.get(id).copied().unwrap_or(default)
+    }
+
+    pub fn pack_item_points(&self, rarity: Rarity, default: u64) -> u64 {
+        self.pack_item_points
+            .get(rarity.id())
+            .copied()
+            .unwrap_or(default)
+    }
+
+    /// the chance that an item of this rarity is upgraded to the next one
+    pub fn rarity_upgrade_chance(&self, rarity: Rarity, default: f64) -> f64 {
+        self.rarity_upgrade_chances
+            .get(rarity.id())
+            .copied()
+            .unwrap_or(default)
+    }
+
+    /// replacement tiers for a built-in bonus track, if there are any
+    pub fn bonus_track_tiers(&self, id: &str) -> Option<&[Tier]> {
+        self.bonus_tracks.get(id).map(Vec::as_slice)
+    }
+
+    pub fn party_cost(&self, id: &str, default: u64) -> u64 {
+        self.parties.get(id).copied().unwrap_or(default)
+    }
+
+    pub fn game_cost(&self, id: &str, default: u64) -> u64 {
+        self.games.get(id).copied().unwrap_or(default)
+    }
+
+    /// replacement costs for each tier of a treasure depths upgrade, if there are any
+    pub fn treasure_depths_costs(&self, id: &str) -> Option<&[u64]> {
+        self.treasure_depths.get(id).map(Vec::as_slice)
+    }
+
+    /// how much of a tier's cost, in percent, selling it back refunds
+    pub fn respec_refund_percent(&self, default: u64) -> u64 {
+        self.respec_refund_percent.unwrap_or(default)
+    }
+
+    pub fn respec_cooldown_hours(&self, default: u64) -> u64 {
+        self.respec_cooldown_hours.unwrap_or(default)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BALANCE: &str = r#"{
+        "version": 1,
+        "pack_accrual_rate": 50,
+        "packs": { "basic": 800 },
+        "pack_item_points": { "legendary": 2000 },
+        "rarity_upgrade_chances": { "common": 0.15 },
+        "bonus_tracks": {
+            "commit_value": [
+                { "cost": 0, "points": 2 },
+                { "cost": 50, "points": 3 },
+                { "cost": 500, "points": 4 },
+                { "cost": 5000, "points": 5 },
+                { "cost": 50000, "points": 6 }
+            ]
+        },
+        "parties": { "fireworks": 8000 },
+        "games": { "snake": 300 },
+        "treasure_depths": { "visibility": [0, 150, 300] },
+        "respec": { "refund_percent": 75 }
+    }"#;
+
+    #[test]
+    fn overrides_only_what_the_file_sets() {
+        let balance = parse(BALANCE).unwrap();
+
+        assert_eq!(balance.pack_accrual_rate(25), 50);
+        assert_eq!(balance.pack_cost("basic", 1_000), 800);
+        assert_eq!(balance.pack_cost("premium", 10_000), 10_000);
+        assert_eq!(balance.pack_item_points(Rarity::Legendary, 1600), 2000);
+        assert_eq!(balance.pack_item_points(Rarity::Common, 25), 25);
+        assert_eq!(balance.rarity_upgrade_chance(Rarity::Common, 0.1), 0.15);
+        assert_eq!(balance.rarity_upgrade_chance(Rarity::Rare, 0.2), 0.2);
+        assert_eq!(balance.party_cost("fireworks", 10_000), 8000);
+        assert_eq!(balance.party_cost("quotes", 1000), 1000);
+        assert_eq!(balance.game_cost("snake", 100), 300);
+        assert_eq!(
+            balance.treasure_depths_costs("visibility"),
+            Some(&[0, 150, 300][..])
+        );
+        assert_eq!(balance.treasure_depths_costs("inventory"), None);
+        assert_eq!(balance.respec_refund_percent(50), 75);
+        assert_eq!(balance.respec_cooldown_hours(24), 24);
+
+        let tiers = balance.bonus_track_tiers("commit_value").unwrap();
+        assert_eq!(tiers[1].cost, 50);
+        assert_eq!(tiers[4].reward, Reward::FlatPoints(6));
+        assert!(balance.bonus_track_tiers("streak").is_none());
+
+        // a file with nothing but the version changes nothing
+        let empty = parse(r#"{ "version": 1 }"#).unwrap();
+        assert_eq!(empty.pack_accrual_rate(25), 25);
+        assert!(empty.bonus_track_tiers("commit_value").is_none());
+    }
+
+    #[test]
+    fn rejects_invalid_balance() {
+        let invalid = [
+            // versions from the future
+            BALANCE.replace(r#""version": 1"#, r#""version": 2"#),
+            // unknown sections and ids are typos, not no-ops
+            BALANCE.replace("pack_accrual_rate", "pack_acrual_rate"),
+            BALANCE.replace(r#""basic""#, r#""mega""#),
+            BALANCE.replace(r#""fireworks""#, r#""confetti""#),
+            BALANCE.replace(r#""snake""#, r#""slots""#),
+            BALANCE.replace(r#""visibility""#, r#""sonar""#),
+            BALANCE.replace(r#""commit_value""#, r#""commit_valu""#),
+            BALANCE.replace(r#""legendary""#, r#""mythic""#),
+            // nothing upgrades past legendary, and chances are chances
+            BALANCE.replace(r#""common": 0.15"#, r#""legendary": 0.15"#),
+            BALANCE.replace("0.15", "1.5"),
+            BALANCE.replace(r#""pack_accrual_rate": 50"#, r#""pack_accrual_rate": 0"#),
+            BALANCE.replace(r#""refund_percent": 75"#, r#""refund_percent": 150"#),
+            // tiers must cost more and more
+            BALANCE.replace(r#""cost": 500,"#, r#""cost": 50,"#),
+            BALANCE.replace("[0, 150, 300]", "[0, 300, 150]"),
+            BALANCE.replace("[0, 150, 300]", "[50, 150, 300]"),
+            // and match the built-in track's shape
+            BALANCE.replace(
+                r#"{ "cost": 50000, "points": 6 }"#,
+                r#"{ "cost": 50000, "multiplier": 6 }"#,
+            ),
+            BALANCE.replace(
+                r#",
+                { "cost": 50000, "points": 6 }"#,
+                "",
+            ),
+            BALANCE.replace("[0, 150, 300]", "[0, 150]"),
+        ];
+        for content in invalid {
+            assert!(parse(&content).is_err(), "{content}");
+        }
+    }
+}
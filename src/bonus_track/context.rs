@@ -9,3 +9,14 @@ pub struct PushContext<'a> {
     pub history: &'a PushHistory<'a>,
     pub clock: &'a Clock,
 }
+
+impl PushContext<'_> {
+    /// how many new commits each branch in the push received, in push order
+    pub fn commits_per_branch(&self) -> Vec<(&str, usize)> {
+        self.push
+            .branches()
+            .iter()
+            .map(|b| (b.name(), b.commits().len()))
+            .collect()
+    }
+}
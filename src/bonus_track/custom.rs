@@ -0,0 +1,502 @@
+//! Bonus tracks declared in a TOML file instead of in code, for house rules like a
+//! bonus for pushing to release branches. Each `[[bonus]]` has conditions under
+//! `[bonus.when]`, all of which must hold, and a list of `[[bonus.tier]]`s:
+//!
+//! ```toml
+//! [[bonus]]
+//! id = "docs_day"
+//! name = "Docs Day"
+//! description = "Points for every commit that touches the docs."
+//! per = "commit"
+//!
+//! [bonus.when]
+//! branches = ["main", "release/*"]
+//! paths = ["docs/", "*.md"]
+//! hours = [9, 17]
+//! days = ["mon", "tue", "wed", "thu", "fri"]
+//! min_lines = 5
+//!
+//! [[bonus.tier]]
+//! cost = 100
+//! points = 5
+//!
+//! [[bonus.tier]]
+//! cost = 1000
+//! points = 15
+//! ```
+//!
+//! Commits count if they're on a matching branch and touch a matching path (paths use
+//! the same globs as `party.ignore`). A track `per = "push"` (the default) applies once
+//! when at least one commit counts and the rest of the conditions hold, with the line
+//! limits on the counted commits' total. A track `per = "commit"` applies once per
+//! counted commit, with the line limits on each commit. `hours` is a local time window
+//! `[from, to)` that may wrap past midnight, and the `pushes_today` limits are on the
+//! pushes made earlier the same day.
+
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+use serde::Deserialize;
+
+use crate::git::{Commit, ignore::IgnoreRules, ignore::matches_glob};
+
+use super::{BonusTrack, PushContext, Reward, Tier};
+
+const DAY_NAMES: [&str; 7] = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CustomBonusFile {
+    #[serde(default)]
+    bonus: Vec<CustomBonusDef>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CustomBonusDef {
+    id: String,
+    name: String,
+    description: String,
+    #[serde(default)]
+    per: Per,
+    #[serde(default)]
+    when: Conditions,
+    tier: Vec<TierDef>,
+}
+
+/// what a track counts
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Per {
+    #[default]
+    Push,
+    Commit,
+}
+
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Conditions {
+    branches: Option<Vec<String>>,
+    paths: Option<Vec<String>>,
+    hours: Option<[u32; 2]>,
+    days: Option<Vec<String>>,
+    min_commits: Option<usize>,
+    max_commits: Option<usize>,
+    min_lines: Option<u64>,
+    max_lines: Option<u64>,
+    min_pushes_today: Option<u32>,
+    max_pushes_today: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TierDef {
+    cost: u64,
+    multiplier: Option<u32>,
+    points: Option<u64>,
+}
+
+/// a bonus track read from the custom bonuses file. its strings and tiers live for
+/// the rest of the process, like the built-in tracks' do
+pub struct CustomTrack {
+    id: &'static str,
+    name: &'static str,
+    description: &'static str,
+    tiers: &'static [Tier],
+    per: Per,
+    when: Conditions,
+    paths: Option<IgnoreRules>,
+
+    /// days of the week as `Clock::day_of_week` numbers them
+    days: Option<Vec<i64>>,
+}
+
+/// reads the tracks from a custom bonuses file. a missing file has no tracks
+pub fn load(path: &Path, builtin_ids: &[&str]) -> Result<Vec<CustomTrack>> {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e).with_context(|| format!("could not read {}", path.display())),
+    };
+    parse(&content, builtin_ids).with_context(|| format!("invalid {}", path.display()))
+}
+
+fn parse(content: &str, builtin_ids: &[&str]) -> Result<Vec<CustomTrack>> {
+    let file: CustomBonusFile = toml::from_str(content)?;
+
+    let mut tracks: Vec<CustomTrack> = Vec::new();
+    for def in file.bonus {
+        let track = CustomTrack::from_def(def)?;
+        if builtin_ids.contains(&track.id) || tracks.iter().any(|t| t.id == track.id) {
+            bail!("bonus id `{}` is already taken", track.id);
+        }
+        tracks.push(track);
+    }
+    Ok(tracks)
+}
+
+impl CustomTrack {
+    fn from_def(def: CustomBonusDef) -> Result<Self> {
+        let id = def.id.trim();
+        if id.is_empty()
+            || !id
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+        {
+            bail!("bonus id `{id}` should be lowercase letters, digits and underscores");
+        }
+
+        if def.tier.is_empty() {
+            bail!("bonus `{id}` needs at least one tier");
+        }
+        let mut tiers = Vec::with_capacity(def.tier.len());
+        for tier in &def.tier {
+            let reward = match (tier.multiplier, tier.points) {
+                (Some(m), None) if m >= 1 => Reward::Multiplier(m),
+                (None, Some(p)) => Reward::FlatPoints(p),
+                (Some(_), None) => bail!("bonus `{id}` has a multiplier below 1"),
+                _ => bail!("each tier of bonus `{id}` needs either `multiplier` or `points`"),
+            };
+            tiers.push(Tier {
+                cost: tier.cost,
+                reward,
+            });
+        }
+
+        let when = def.when;
+        if let Some([from, to]) = when.hours
+            && (from > 23 || to > 24 || from == to)
+        {
+            bail!("bonus `{id}` has invalid hours [{from}, {to}]");
+        }
+        let days = when
+            .days
+            .as_ref()
+            .map(|days| {
+                days.iter()
+                    .map(|day| {
+                        let day = day.trim().to_lowercase();
+                        DAY_NAMES
+                            .iter()
+                            .position(|&d| day.starts_with(d))
+                            // monday is 4 for Clock, which starts counting on a thursday
+                            .map(|i| (i as i64 + 4) % 7)
+                            .with_context(|| format!("bonus `{id}` has unknown day `{day}`"))
+                    })
+                    .collect::<Result<Vec<_>>>()
+            })
+            .transpose()?;
+        let paths = when.paths.as_ref().map(IgnoreRules::new);
+
+        Ok(Self {
+            id: leak(id),
+            name: leak(&def.name),
+            description: leak(&def.description),
+            tiers: Vec::leak(tiers),
+            per: def.per,
+            when,
+            paths,
+            days,
+        })
+    }
+
+    fn on_matching_branch(&self, branch: &str) -> bool {
+        match &self.when.branches {
+            Some(patterns) => patterns.iter().any(|p| matches_glob(p, branch)),
+            None => true,
+        }
+    }
+
+    fn touches_matching_path(&self, commit: &Commit) -> bool {
+        match &self.paths {
+            Some(paths) => commit.files().iter().any(|f| paths.is_ignored(f.path())),
+            None => true,
+        }
+    }
+
+    fn lines_in_range(&self, lines: u64) -> bool {
+        self.when.min_lines.is_none_or(|min| lines >= min)
+            && self.when.max_lines.is_none_or(|max| lines <= max)
+    }
+
+    /// whether the push happens at a time the track allows
+    fn in_time_window(&self, ctx: &PushContext) -> bool {
+        if let Some(days) = &self.days
+            && !days.contains(&ctx.clock.day_of_week())
+        {
+            return false;
+        }
+        if let Some([from, to]) = self.when.hours {
+            let hour = (ctx.clock.local_seconds_since_midnight() / 3600) as u32;
+            let in_window = if from < to {
+                hour >= from && hour < to
+            } else {
+                hour >= from || hour < to
+            };
+            if !in_window {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// whether the pushes already made today fit the track's limits
+    fn history_allows(&self, ctx: &PushContext) -> bool {
+        let (min, max) = (self.when.min_pushes_today, self.when.max_pushes_today);
+        if min.is_none() && max.is_none() {
+            return true;
+        }
+        let pushes_today = ctx
+            .history
+            .count_since(ctx.clock.today_start())
+            .unwrap_or_default();
+        min.is_none_or(|min| pushes_today >= min) && max.is_none_or(|max| pushes_today <= max)
+    }
+}
+
+impl BonusTrack for CustomTrack {
+    fn id(&self) -> &'static str {
+        self.id
+    }
+
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn description(&self) -> &'static str {
+        self.description
+    }
+
+    fn tiers(&self) -> &'static [Tier] {
+        self.tiers
+    }
+
+    fn applies(&self, ctx: &PushContext) -> u32 {
+        if !self.in_time_window(ctx) || !self.history_allows(ctx) {
+            return 0;
+        }
+
+        // without path or line conditions, only how many commits each branch got matters
+        let has_line_limits = self.when.min_lines.is_some() || self.when.max_lines.is_some();
+        let count = if self.paths.is_none() && !has_line_limits {
+            ctx.commits_per_branch()
+                .into_iter()
+                .filter(|(branch, _)| self.on_matching_branch(branch))
+                .map(|(_, n)| n)
+                .sum()
+        } else {
+            let commits: Vec<&Commit> = ctx
+                .push
+                .branches()
+                .iter()
+                .filter(|b| self.on_matching_branch(b.name()))
+                .flat_map(|b| b.commits())
+                .filter(|c| self.touches_matching_path(c))
+                .filter(|c| self.per == Per::Push || self.lines_in_range(c.lines_changed()))
+                .collect();
+            let total_lines = commits.iter().map(|c| c.lines_changed()).sum();
+            if self.per == Per::Push && !self.lines_in_range(total_lines) {
+                return 0;
+            }
+            commits.len()
+        };
+
+        if count == 0
+            || self.when.min_commits.is_some_and(|min| count < min)
+            || self.when.max_commits.is_some_and(|max| count > max)
+        {
+            return 0;
+        }
+
+        match self.per {
+            Per::Push => 1,
+            Per::Commit => count as u32,
+        }
+    }
+}
+
+fn leak(s: &str) -> &'static str {
+    Box::leak(s.trim().to_string().into_boxed_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        bonus_track::Clock,
+        git::{Push, PushedBranch},
+        storage::{DbConnection, PushEntry, PushHistory},
+    };
+
+    const RELEASE_PUSH: &str = r#"
+        [[bonus]]
+        id = "release_push"
+        name = "Release Push"
+        description = "Multiplier for pushing to release branches."
+
+        [bonus.when]
+        branches = ["release/*"]
+        min_commits = 2
+
+        [[bonus.tier]]
+        cost = 100
+        multiplier = 2
+    "#;
+
+    const DOCS: &str = r#"
+        [[bonus]]
+        id = "docs"
+        name = "Docs"
+        description = "Points for each commit touching the docs."
+        per = "commit"
+
+        [bonus.when]
+        paths = ["docs/"]
+        min_lines = 5
+
+        [[bonus.tier]]
+        cost = 50
+        points = 5
+
+        [[bonus.tier]]
+        cost = 500
+        points = 10
+    "#;
+
+    fn track(content: &str) -> CustomTrack {
+        parse(content, &[]).unwrap().remove(0)
+    }
+
+    fn branches(branches: Vec<(&str, Vec<Commit>)>) -> Push {
+        let branches = branches
+            .into_iter()
+            .map(|(name, commits)| PushedBranch::new(name, commits))
+            .collect();
+        Push::from_branches(branches, "github.com/me/repo", 0)
+    }
+
+    fn applies(track: &CustomTrack, push: &Push, history: &PushHistory, clock: &Clock) -> u32 {
+        track.applies(&PushContext {
+            push,
+            history,
+            clock,
+        })
+    }
+
+    #[test]
+    fn parses_tracks_and_tiers() {
+        let docs = track(DOCS);
+
+        assert_eq!(docs.id(), "docs");
+        assert_eq!(docs.name(), "Docs");
+        assert_eq!(docs.tiers().len(), 2);
+        assert_eq!(docs.reward_at_level(2), Some(Reward::FlatPoints(10)));
+        assert_eq!(
+            track(RELEASE_PUSH).reward_at_level(1),
+            Some(Reward::Multiplier(2))
+        );
+        assert!(parse("", &[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn rejects_invalid_tracks() {
+        let invalid = [
+            // taken by a built-in track
+            RELEASE_PUSH.replace("release_push", "streak"),
+            // not a valid id
+            RELEASE_PUSH.replace("release_push", "Release Push"),
+            // a tier needs exactly one reward
+            RELEASE_PUSH.replace("multiplier = 2", "multiplier = 2\npoints = 3"),
+            RELEASE_PUSH.replace("multiplier = 2", ""),
+            // unknown conditions are typos, not no-ops
+            RELEASE_PUSH.replace("min_commits", "min_comits"),
+            RELEASE_PUSH.replace("min_commits = 2", "days = [\"someday\"]"),
+            RELEASE_PUSH.replace("min_commits = 2", "hours = [25, 3]"),
+        ];
+        for content in invalid {
+            assert!(parse(&content, &["streak"]).is_err(), "{content}");
+        }
+
+        let twice = format!("{RELEASE_PUSH}\n{RELEASE_PUSH}");
+        assert!(parse(&twice, &[]).is_err());
+    }
+
+    #[test]
+    fn counts_commits_on_matching_branches() {
+        let conn = DbConnection::create_in_memory().unwrap();
+        let history = PushHistory::new(&conn);
+        let clock = Clock::default();
+        let release = track(RELEASE_PUSH);
+
+        let push = branches(vec![
+            ("release/1.2", vec![Commit::default()]),
+            ("main", vec![Commit::default()]),
+        ]);
+        assert_eq!(applies(&release, &push, &history, &clock), 0);
+
+        let push = branches(vec![
+            ("release/1.2", vec![Commit::default()]),
+            ("release/1.3", vec![Commit::default()]),
+        ]);
+        assert_eq!(applies(&release, &push, &history, &clock), 1);
+    }
+
+    #[test]
+    fn counts_each_commit_touching_matching_paths() {
+        let conn = DbConnection::create_in_memory().unwrap();
+        let history = PushHistory::new(&conn);
+        let clock = Clock::default();
+        let docs = track(DOCS);
+
+        let push = branches(vec![(
+            "main",
+            vec![
+                Commit::touching(&["docs/guide.md"], 10),
+                Commit::touching(&["src/main.rs", "docs/api/index.md"], 5),
+                // too small
+                Commit::touching(&["docs/typo.md"], 1),
+                Commit::touching(&["src/lib.rs"], 50),
+            ],
+        )]);
+        assert_eq!(applies(&docs, &push, &history, &clock), 2);
+    }
+
+    #[test]
+    fn checks_time_window_and_history() {
+        let conn = DbConnection::create_in_memory().unwrap();
+        let push = branches(vec![("main", vec![Commit::default()])]);
+        let early_bird = track(
+            r#"
+            [[bonus]]
+            id = "early_bird"
+            name = "Early Bird"
+            description = "First push of a weekday morning."
+
+            [bonus.when]
+            hours = [6, 9]
+            days = ["mon", "tue", "wed", "thu", "fri"]
+            max_pushes_today = 0
+
+            [[bonus.tier]]
+            cost = 10
+            points = 20
+            "#,
+        );
+
+        // day 4 is a monday
+        let monday_morning = 4 * 86400 + 7 * 3600;
+        let history = PushHistory::new(&conn);
+        let clock = Clock::at(monday_morning);
+        assert_eq!(applies(&early_bird, &push, &history, &clock), 1);
+
+        let clock = Clock::at(monday_morning + 3 * 3600);
+        assert_eq!(applies(&early_bird, &push, &history, &clock), 0);
+
+        let clock = Clock::at(monday_morning - 86400);
+        assert_eq!(applies(&early_bird, &push, &history, &clock), 0);
+
+        let history = history.with_entries([PushEntry::at(monday_morning - 600)]);
+        let clock = Clock::at(monday_morning);
+        assert_eq!(applies(&early_bird, &push, &history, &clock), 0);
+    }
+}
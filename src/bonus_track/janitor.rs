@@ -0,0 +1,97 @@
+use super::{BonusTrack, PushContext, Reward, Tier};
+
+/// bonus for cleanup commits that remove more than they add
+pub struct Janitor;
+
+static TIERS: &[Tier] = &[
+    Tier {
+        cost: 40,
+        reward: Reward::FlatPoints(5),
+    },
+    Tier {
+        cost: 400,
+        reward: Reward::FlatPoints(10),
+    },
+    Tier {
+        cost: 3000,
+        reward: Reward::FlatPoints(25),
+    },
+    Tier {
+        cost: 20000,
+        reward: Reward::FlatPoints(50),
+    },
+    Tier {
+        cost: 100000,
+        reward: Reward::FlatPoints(100),
+    },
+];
+
+impl BonusTrack for Janitor {
+    fn id(&self) -> &'static str {
+        "janitor"
+    }
+
+    fn name(&self) -> &'static str {
+        "Janitor"
+    }
+
+    fn description(&self) -> &'static str {
+        "More points for commits that delete more lines than they add."
+    }
+
+    fn tiers(&self) -> &'static [Tier] {
+        TIERS
+    }
+
+    fn applies(&self, ctx: &PushContext) -> u32 {
+        ctx.push
+            .commits()
+            .iter()
+            .filter(|c| c.deletions() > c.insertions())
+            .count() as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        bonus_track::Clock,
+        git::{Commit, Push},
+        storage::{DbConnection, PushHistory},
+    };
+
+    #[test]
+    fn applies_to_commits_that_delete_more_than_they_add() {
+        let conn = DbConnection::create_in_memory().unwrap();
+        let history = PushHistory::new(&conn);
+        let clock = Clock::default();
+        let push = Push::new(vec![
+            Commit::with_changes(0, 30),
+            Commit::with_changes(10, 2),
+            Commit::with_changes(4, 5),
+        ]);
+        let ctx = PushContext {
+            push: &push,
+            history: &history,
+            clock: &clock,
+        };
+
+        assert_eq!(Janitor.applies(&ctx), 2);
+    }
+
+    #[test]
+    fn does_not_apply_to_balanced_or_empty_commits() {
+        let conn = DbConnection::create_in_memory().unwrap();
+        let history = PushHistory::new(&conn);
+        let clock = Clock::default();
+        let push = Push::new(vec![Commit::with_changes(5, 5), Commit::with_changes(0, 0)]);
+        let ctx = PushContext {
+            push: &push,
+            history: &history,
+            clock: &clock,
+        };
+
+        assert_eq!(Janitor.applies(&ctx), 0);
+    }
+}
@@ -1,17 +1,24 @@
 mod big_push;
 mod commit_value;
 mod context;
+mod custom;
 mod early_push;
 mod first_push;
 mod friday_afternoon_push;
+mod janitor;
 mod late_push;
 mod many_lines_changed;
 mod multiple_repos;
 mod one_line_change;
 mod rapid_fire;
+mod ship_it;
 mod streak;
 mod weekend_push;
 
+use std::sync::OnceLock;
+
+use crate::balance::balance;
+
 pub use crate::clock::Clock;
 pub use context::PushContext;
 
@@ -20,11 +27,13 @@ use commit_value::CommitValue;
 use early_push::EarlyPush;
 use first_push::FirstPush;
 use friday_afternoon_push::FridayAfternoon;
+use janitor::Janitor;
 use late_push::LatePush;
 use many_lines_changed::ManyLinesChanged;
 use multiple_repos::MultipleRepos;
 use one_line_change::OneLineChange;
 use rapid_fire::RapidFire;
+use ship_it::ShipIt;
 use streak::Streak;
 use weekend_push::WeekendPush;
 
@@ -78,11 +87,13 @@ pub static COMMIT_VALUE: CommitValue = CommitValue;
 static EARLY_PUSH: EarlyPush = EarlyPush;
 static FIRST_PUSH: FirstPush = FirstPush;
 static FRIDAY_AFTERNOON: FridayAfternoon = FridayAfternoon;
+static JANITOR: Janitor = Janitor;
 static LATE_PUSH: LatePush = LatePush;
 static MANY_LINES_CHANGED: ManyLinesChanged = ManyLinesChanged;
 static MULTIPLE_REPOS: MultipleRepos = MultipleRepos;
 static ONE_LINE_CHANGE: OneLineChange = OneLineChange;
 static RAPID_FIRE: RapidFire = RapidFire;
+static SHIP_IT: ShipIt = ShipIt;
 static STREAK: Streak = Streak;
 static WEEKEND_PUSH: WeekendPush = WeekendPush;
 
@@ -95,9 +106,75 @@ pub static ALL_TRACKS: &[&'static dyn BonusTrack] = &[
     &MULTIPLE_REPOS,
     &ONE_LINE_CHANGE,
     &MANY_LINES_CHANGED,
+    &JANITOR,
     &STREAK,
     &EARLY_PUSH,
     &LATE_PUSH,
     &FRIDAY_AFTERNOON,
     &WEEKEND_PUSH,
+    &SHIP_IT,
 ];
+
+/// the built-in tracks followed by custom ones, once `load_tracks` has run
+static TRACKS: OnceLock<Vec<&'static dyn BonusTrack>> = OnceLock::new();
+
+/// all bonus tracks in display order, including custom ones
+pub fn all_tracks() -> &'static [&'static dyn BonusTrack] {
+    TRACKS.get().map(Vec::as_slice).unwrap_or(ALL_TRACKS)
+}
+
+/// sets up `all_tracks`: the built-in tracks with tiers from the balance file, then the
+/// tracks from the custom bonuses file. if the custom bonuses file is invalid, only the
+/// built-in tracks are used
+pub fn load_tracks() -> anyhow::Result<()> {
+    let mut tracks: Vec<&'static dyn BonusTrack> = ALL_TRACKS
+        .iter()
+        .map(|&track| match balance().bonus_track_tiers(track.id()) {
+            Some(tiers) => &*Box::leak(Box::new(Rebalanced { track, tiers })),
+            None => track,
+        })
+        .collect();
+
+    let custom = crate::storage::custom_bonuses_path().and_then(|path| {
+        let builtin_ids: Vec<&str> = ALL_TRACKS.iter().map(|t| t.id()).collect();
+        custom::load(&path, &builtin_ids)
+    });
+    let result = custom.map(|custom| {
+        tracks.extend(
+            custom
+                .into_iter()
+                .map(|t| &*Box::leak(Box::new(t)) as &'static dyn BonusTrack),
+        );
+    });
+
+    let _ = TRACKS.set(tracks);
+    result
+}
+
+/// a built-in track with its tiers replaced by the balance file's
+struct Rebalanced {
+    track: &'static dyn BonusTrack,
+    tiers: &'static [Tier],
+}
+
+impl BonusTrack for Rebalanced {
+    fn id(&self) -> &'static str {
+        self.track.id()
+    }
+
+    fn name(&self) -> &'static str {
+        self.track.name()
+    }
+
+    fn description(&self) -> &'static str {
+        self.track.description()
+    }
+
+    fn tiers(&self) -> &'static [Tier] {
+        self.tiers
+    }
+
+    fn applies(&self, ctx: &PushContext) -> u32 {
+        self.track.applies(ctx)
+    }
+}
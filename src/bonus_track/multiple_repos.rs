@@ -56,7 +56,7 @@ impl BonusTrack for MultipleRepos {
             .entries_since(ctx.clock.today_start())
             .unwrap_or_default()
             .into_iter()
-            .filter(|e| ctx.clock.day_id_of(e.timestamp()) == today)
+            .filter(|e| ctx.clock.day_id_of(e.timestamp()) == today && e.counts_as_push())
             .map(|e| e.remote_url().to_string())
             .collect();
 
@@ -0,0 +1,108 @@
+use super::{BonusTrack, PushContext, Reward, Tier};
+
+/// bonus for pushing release tags
+pub struct ShipIt;
+
+static TIERS: &[Tier] = &[
+    Tier {
+        cost: 100,
+        reward: Reward::FlatPoints(25),
+    },
+    Tier {
+        cost: 1000,
+        reward: Reward::FlatPoints(50),
+    },
+    Tier {
+        cost: 5000,
+        reward: Reward::FlatPoints(100),
+    },
+    Tier {
+        cost: 30000,
+        reward: Reward::FlatPoints(250),
+    },
+    Tier {
+        cost: 150000,
+        reward: Reward::FlatPoints(500),
+    },
+];
+
+impl BonusTrack for ShipIt {
+    fn id(&self) -> &'static str {
+        "ship_it"
+    }
+
+    fn name(&self) -> &'static str {
+        "Ship It"
+    }
+
+    fn description(&self) -> &'static str {
+        "More points for pushing release tags (annotated, or named like v1.2.3)."
+    }
+
+    fn tiers(&self) -> &'static [Tier] {
+        TIERS
+    }
+
+    fn applies(&self, ctx: &PushContext) -> u32 {
+        ctx.push.tags().iter().filter(|t| t.is_release()).count() as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        bonus_track::Clock,
+        git::{Push, PushedTag},
+        storage::{DbConnection, PushHistory},
+    };
+
+    fn applies_to(tags: Vec<PushedTag>) -> u32 {
+        let conn = DbConnection::create_in_memory().unwrap();
+        let history = PushHistory::new(&conn);
+        let clock = Clock::default();
+
+        let push = Push::from_tags(tags, "url/repo.git");
+        let ctx = PushContext {
+            push: &push,
+            history: &history,
+            clock: &clock,
+        };
+        ShipIt.applies(&ctx)
+    }
+
+    #[test]
+    fn applies_to_annotated_and_semver_tags() {
+        assert_eq!(
+            applies_to(vec![PushedTag::new("v1.2.0", "a", false, false)]),
+            1
+        );
+        assert_eq!(
+            applies_to(vec![PushedTag::new("release", "a", true, false)]),
+            1
+        );
+        assert_eq!(
+            applies_to(vec![
+                PushedTag::new("v1.2.0", "a", true, false),
+                PushedTag::new("v1.3.0", "b", false, false),
+            ]),
+            2
+        );
+    }
+
+    #[test]
+    fn does_not_apply_to_plain_tags_or_retags() {
+        assert_eq!(
+            applies_to(vec![PushedTag::new("wip", "a", false, false)]),
+            0
+        );
+        assert_eq!(
+            applies_to(vec![PushedTag::new("v1.2.1", "a", false, true)]),
+            0
+        );
+        assert_eq!(
+            applies_to(vec![PushedTag::new("v1.2.1", "a", true, true)]),
+            0
+        );
+    }
+}
@@ -1,4 +1,6 @@
-use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand, ValueEnum};
 
 #[derive(Parser)]
 #[command(name = "party", about = "earn party points by pushing code")]
@@ -10,10 +12,110 @@ pub struct Cli {
 #[derive(Subcommand)]
 pub enum Command {
     /// install hook in current repo
-    Init,
+    Init {
+        /// install for every repo via `core.hooksPath` instead
+        #[arg(long)]
+        global: bool,
+    },
 
     /// remove hook from current repo
-    Uninit,
+    Uninit {
+        /// remove the global install instead
+        #[arg(long)]
+        global: bool,
+    },
+
+    /// list the repos party was installed into, and whether their hooks are intact
+    Repos {
+        /// forget repos that no longer exist
+        #[arg(long, conflicts_with_all = ["snapshot", "uninstall"])]
+        prune: bool,
+
+        /// snapshot every repo again, so that nothing pushed so far is credited
+        #[arg(long, conflicts_with = "uninstall")]
+        snapshot: bool,
+
+        /// remove party from every repo
+        #[arg(long)]
+        uninstall: bool,
+    },
+
+    /// import past pushes from this repo's reflogs into your history
+    Backfill {
+        /// also award points for them, as if the party had been installed all along
+        #[arg(long)]
+        award_points: bool,
+    },
+
+    /// watch registered repos for pushes, for repos where hooks don't run
+    Watch {
+        /// register a repo to watch (the current one if no path is given)
+        #[arg(
+            long,
+            value_name = "PATH",
+            num_args = 0..=1,
+            default_missing_value = ".",
+            conflicts_with = "remove"
+        )]
+        add: Option<PathBuf>,
+
+        /// stop watching a repo (the current one if no path is given)
+        #[arg(long, value_name = "PATH", num_args = 0..=1, default_missing_value = ".")]
+        remove: Option<PathBuf>,
+
+        /// list the watched repos
+        #[arg(long, conflicts_with_all = ["add", "remove"])]
+        list: bool,
+
+        /// seconds between checks
+        #[arg(long, default_value_t = 5, value_parser = clap::value_parser!(u64).range(1..))]
+        interval: u64,
+
+        /// check every watched repo once and exit, e.g. from cron
+        #[arg(long, conflicts_with_all = ["add", "remove", "list"])]
+        once: bool,
+    },
+
+    /// throw the parties for pushes found by `party watch` (e.g. from your shell's rc file)
+    Pending,
+
+    /// credit pushes received by a bare repo, from its `post-receive` hook
+    Receive {
+        /// whose ledger each commit is credited to
+        #[arg(long, value_enum, default_value_t = Attribution::Author)]
+        by: Attribution,
+    },
+
+    /// preview the points a push would earn, without changing anything
+    Simulate {
+        /// simulate this many commits instead of the current branch's unpushed ones
+        #[arg(long)]
+        commits: Option<u64>,
+
+        /// lines changed per simulated commit (comma-separated, cycles if fewer than commits)
+        #[arg(long, value_delimiter = ',', requires = "commits")]
+        lines: Option<Vec<u64>>,
+
+        /// branch the simulated commits are pushed to [default: main]
+        #[arg(long, requires = "commits")]
+        branch: Option<String>,
+
+        /// local time of the push [default: now]
+        #[arg(long, value_name = "HH:MM", value_parser = parse_time_of_day)]
+        time: Option<u32>,
+
+        /// day of the week of the push [default: today]
+        #[arg(long, value_enum)]
+        day: Option<Weekday>,
+
+        /// pushes already made that day, instead of your real history
+        #[arg(long)]
+        pushes_today: Option<u32>,
+
+        /// days in a row with a push before that day, instead of your real history
+        #[arg(long)]
+        streak: Option<u32>,
+    },
 
     /// prints current party points
     Points,
@@ -25,6 +127,16 @@ pub enum Command {
     #[command(hide = true)]
     Hook,
 
+    /// called by git pre-push hook to pick up pushed tags (not user-facing)
+    #[command(hide = true)]
+    PrePush {
+        /// name of the remote being pushed to
+        remote: String,
+
+        /// url of the remote being pushed to
+        url: String,
+    },
+
     /// snapshot current refs (called before push in jj)
     #[command(hide = true)]
     Snapshot,
@@ -93,3 +205,54 @@ pub enum Command {
         id: String,
     },
 }
+
+impl Command {
+    /// the dev-only commands, which all play with points, parties or games
+    #[cfg(feature = "dev")]
+    pub fn is_dev(&self) -> bool {
+        matches!(
+            self,
+            Command::Cheat { .. }
+                | Command::Push { .. }
+                | Command::Reset
+                | Command::Bonus { .. }
+                | Command::Party { .. }
+                | Command::Palette { .. }
+                | Command::Game { .. }
+                | Command::Demo { .. }
+        )
+    }
+}
+
+/// who gets the credit for commits a server receives
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Attribution {
+    /// whoever wrote each commit, by author email
+    Author,
+
+    /// whoever pushed, as named by the server (e.g. gitolite's `GL_USER`)
+    Pusher,
+}
+
+/// a day of the week, for `party simulate`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Weekday {
+    Mon,
+    Tue,
+    Wed,
+    Thu,
+    Fri,
+    Sat,
+    Sun,
+}
+
+/// parses `HH:MM` into seconds since midnight
+fn parse_time_of_day(s: &str) -> Result<u32, String> {
+    let (hours, minutes) = s.split_once(':').ok_or("expected HH:MM")?;
+    let hours: u32 = hours.parse().map_err(|_| "invalid hours")?;
+    let minutes: u32 = minutes.parse().map_err(|_| "invalid minutes")?;
+    if hours > 23 || minutes > 59 {
+        return Err("expected a time between 00:00 and 23:59".to_string());
+    }
+    Ok(hours * 3600 + minutes * 60)
+}
@@ -51,12 +51,14 @@ pub fn push(
     let packs_earned = state.earn_points(breakdown.total);
 
     // record this push in history (like the real hook does)
-    let lines_changed: u64 = push.commits().iter().map(|c| c.lines_changed()).sum();
+    let insertions: u64 = push.commits().iter().map(|c| c.insertions()).sum();
+    let deletions: u64 = push.commits().iter().map(|c| c.deletions()).sum();
     let entry = PushEntry::with_current_time(
         "dev://fake".to_string(),
-        "main".to_string(),
+        vec![("main".to_string(), num_commits)],
         num_commits,
-        lines_changed,
+        insertions,
+        deletions,
         breakdown.total,
     );
     history.record(&entry)?;
@@ -77,15 +79,15 @@ pub fn reset(state: &mut State, pushes: &PushHistory) -> Result<()> {
 }
 
 pub fn bonus(track_id: &str, level: u32, state: &mut State) {
-    use crate::bonus_track::ALL_TRACKS;
+    use crate::bonus_track::all_tracks;
 
     // verify track exists
-    let track = ALL_TRACKS.iter().find(|t| t.id() == track_id);
+    let track = all_tracks().iter().find(|t| t.id() == track_id);
     if track.is_none() {
         eprintln!("unknown track: {}", track_id);
         eprintln!(
             "available: {:?}",
-            ALL_TRACKS.iter().map(|t| t.id()).collect::<Vec<_>>()
+            all_tracks().iter().map(|t| t.id()).collect::<Vec<_>>()
         );
         std::process::exit(1);
     }
@@ -9,7 +9,7 @@ pub mod wallet;
 
 pub use snake::Snake;
 pub use stack::Stack;
-pub use treasure_depths::TreasureDepths;
+pub use treasure_depths::{TreasureDepths, default_upgrade_costs};
 use wallet::Wallet;
 
 pub trait Game: Sync {
@@ -104,7 +104,7 @@ impl<G: Game> GameObject for G {
     }
 
     fn cost(&self) -> u64 {
-        self.cost()
+        crate::balance::balance().game_cost(Game::id(self), Game::cost(self))
     }
 
     fn clear_color(&self) -> (u8, u8, u8) {
@@ -23,6 +23,7 @@ use game::{Game as CoreGame, Input, Loadout};
 use menu::Menu;
 use render::Renderer;
 use store::Store;
+pub use store::default_upgrade_costs;
 
 /// (cols, rows) in canvas cells
 const GAME_DIMS: (usize, usize) = (60, 20);
@@ -4,6 +4,7 @@ use super::game::{
     BASE_INVENTORY_CAPACITY, BASE_REVEAL_RADIUS, BASE_STARTING_FUEL, BASE_STARTING_TIME, Loadout,
 };
 use super::menu::{BORDER_COLOR, center, draw_box, write_text};
+use crate::balance::balance;
 
 const TIER_COUNT: usize = 3;
 
@@ -28,6 +29,8 @@ const FUEL_TANK: usize = 2;
 const INVENTORY: usize = 3;
 
 struct UpgradeDef {
+    /// unique identifier for the balance file
+    id: &'static str,
     label: &'static str,
     /// total cost (in party points) to own each tier; costs[0] is always 0
     costs: [u64; TIER_COUNT],
@@ -35,23 +38,42 @@ struct UpgradeDef {
 
 const UPGRADES: [UpgradeDef; 4] = [
     UpgradeDef {
+        id: "visibility",
         label: "Visibility",
         costs: [0, 100, 200],
     },
     UpgradeDef {
+        id: "start_time",
         label: "Start Time",
         costs: [0, 500, 3000],
     },
     UpgradeDef {
+        id: "fuel_tank",
         label: "Fuel Tank",
         costs: [0, 1000, 5000],
     },
     UpgradeDef {
+        id: "inventory",
         label: "Inventory",
         costs: [0, 1000, 2000],
     },
 ];
 
+impl UpgradeDef {
+    /// `costs`, as tuned by the balance file
+    fn tier_costs(&self) -> [u64; TIER_COUNT] {
+        balance()
+            .treasure_depths_costs(self.id)
+            .and_then(|costs| costs.try_into().ok())
+            .unwrap_or(self.costs)
+    }
+}
+
+/// the built-in cost of each tier of an upgrade, for checking the balance file
+pub fn default_upgrade_costs(id: &str) -> Option<[u64; TIER_COUNT]> {
+    UPGRADES.iter().find(|u| u.id == id).map(|u| u.costs)
+}
+
 pub struct Store {
     /// the player's available party points; the spending cap
     budget: u64,
@@ -89,7 +111,7 @@ impl Store {
         }
 
         let next = cur + 1;
-        let costs = &UPGRADES[self.selected].costs;
+        let costs = UPGRADES[self.selected].tier_costs();
         let new_spent = self.spent() - costs[cur] + costs[next];
         if new_spent <= self.budget {
             self.tiers[self.selected] = next;
@@ -113,7 +135,7 @@ impl Store {
         UPGRADES
             .iter()
             .zip(self.tiers)
-            .map(|(u, t)| u.costs[t])
+            .map(|(u, t)| u.tier_costs()[t])
             .sum()
     }
 
@@ -183,7 +205,7 @@ impl Store {
             write_text(buf, row, tier_col, &format!("{}x", tier + 1), TEXT_COLOR);
 
             // cost, right-aligned within the box
-            let cost = def.costs[tier];
+            let cost = def.tier_costs()[tier];
             let cost_str = format!("{cost} P");
             let cost_col = base + INNER_WIDTH - 3 - cost_str.len();
             let cost_color = if cost == 0 { COST_FREE } else { COST_COLOR };
@@ -301,7 +323,7 @@ mod tests {
 
                 // a refused step that wasn't already maxed must be a budget block
                 if after == before && before < TIER_COUNT - 1 {
-                    let step = def.costs[before + 1] - def.costs[before];
+                    let step = def.tier_costs()[before + 1] - def.tier_costs()[before];
                     assert!(step > store.remaining());
                 }
             }
@@ -0,0 +1,212 @@
+//! Reconstructing past pushes from the reflogs of remote-tracking refs.
+//!
+//! Every push updates the remote-tracking ref of each pushed branch with the reflog
+//! message "update by push", so the reflogs record when each push happened and what
+//! it moved each branch from and to. Reflogs expire (90 days by default), so this
+//! only reaches back as far as git remembers.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use anyhow::Result;
+
+use crate::{
+    git::{
+        self, Commit, Push, PushedBranch, commands::ReflogEntry, config::PartyConfig,
+        detection::get_remote_ids,
+    },
+    storage::PatchIdStore,
+};
+
+const PUSH_MESSAGE: &str = "update by push";
+
+/// a push found in the reflogs
+pub struct PastPush {
+    pub timestamp: u64,
+    pub push: Push,
+}
+
+/// one branch moved by a push
+struct RefUpdate<'a> {
+    refname: String,
+    remote: &'a str,
+    branch: &'a str,
+    old_sha: Option<&'a str>,
+    new_sha: &'a str,
+}
+
+/// Finds every push in this repo's reflogs that contains commits not credited yet,
+/// oldest first. Patch-ids of those commits are recorded as a side effect, so running
+/// this again finds nothing new.
+pub fn find_past_pushes(repo_path: &Path, patch_ids: &PatchIdStore) -> Result<Vec<PastPush>> {
+    let remote_ids = get_remote_ids(repo_path);
+    let remotes: Vec<String> = remote_ids.iter().map(|(r, _)| r.clone()).collect();
+    let current_refs = git::commands::get_all_remote_refs(repo_path, &remotes);
+
+    let mut refs = Vec::new();
+    for remote in &remotes {
+        for branch in current_refs
+            .get(remote)
+            .into_iter()
+            .flatten()
+            .map(|(b, _)| b)
+        {
+            refs.push((format!("refs/remotes/{remote}/{branch}"), remote, branch));
+        }
+    }
+    let refnames: Vec<&str> = refs.iter().map(|(r, _, _)| r.as_str()).collect();
+    let reflogs = git::commands::get_reflogs(repo_path, &refnames);
+
+    // every push to every branch, oldest first
+    let mut updates: Vec<(u64, RefUpdate)> = Vec::new();
+    for (refname, remote, branch) in &refs {
+        let Some(entries) = reflogs.get(refname) else {
+            continue;
+        };
+        for (i, entry) in entries.iter().enumerate() {
+            if entry.message != PUSH_MESSAGE || git::is_null_sha(&entry.sha) {
+                continue;
+            }
+            let update = RefUpdate {
+                refname: refname.clone(),
+                remote,
+                branch,
+                old_sha: i.checked_sub(1).map(|prev| entries[prev].sha.as_str()),
+                new_sha: &entry.sha,
+            };
+            updates.push((entry.timestamp, update));
+        }
+    }
+    // stable, so updates to one ref within the same second stay in order
+    updates.sort_by_key(|(timestamp, update)| (*timestamp, update.remote));
+
+    // a single `git push` moves all of its branches on one remote at the same moment
+    let mut groups: Vec<(u64, Vec<RefUpdate>)> = Vec::new();
+    for (timestamp, update) in updates {
+        match groups.last_mut() {
+            Some((t, group))
+                if *t == timestamp
+                    && group[0].remote == update.remote
+                    && !group.iter().any(|u| u.refname == update.refname) =>
+            {
+                group.push(update)
+            }
+            _ => groups.push((timestamp, vec![update])),
+        }
+    }
+
+    let url_of: HashMap<&str, &str> = remote_ids
+        .iter()
+        .map(|(r, u)| (r.as_str(), u.as_str()))
+        .collect();
+    let all_urls: Vec<&str> = remote_ids.iter().map(|(_, u)| u.as_str()).collect();
+    let PartyConfig {
+        identity,
+        bots,
+        ignore_rules,
+        ..
+    } = PartyConfig::load(repo_path);
+
+    let mut pushes = Vec::new();
+    for (timestamp, group) in groups {
+        // bounded by where each pushed branch was before, and where every other
+        // remote branch was just before the push
+        let mut exclude: Vec<&str> = group.iter().filter_map(|u| u.old_sha).collect();
+        for (refname, _, _) in &refs {
+            if group.iter().any(|u| &u.refname == refname) {
+                continue;
+            }
+            let position = reflogs
+                .get(refname)
+                .and_then(|entries| position_before(entries, timestamp));
+            exclude.extend(position.filter(|sha| !git::is_null_sha(sha)));
+        }
+        let include: Vec<&str> = group.iter().map(|u| u.new_sha).collect();
+
+        let remote_url = url_of[group[0].remote];
+        let mut branch_commits: Vec<(&str, Vec<Commit>)> =
+            group.iter().map(|u| (u.branch, Vec::new())).collect();
+        let mut skipped_commits = 0;
+        let mut automated_commits = 0;
+
+        for info in git::commands::scan_commits(repo_path, &include, &exclude) {
+            let Some(patch_id) = info.patch_id else {
+                continue;
+            };
+            let Some(update) = group.iter().find(|u| u.new_sha == info.source) else {
+                continue;
+            };
+
+            let mut already_seen = false;
+            for url in &all_urls {
+                already_seen |= patch_ids.contains(url, &patch_id)?;
+            }
+            if already_seen {
+                continue;
+            }
+            patch_ids.record(remote_url, &patch_id)?;
+
+            if bots.is_automated(&info.commit) {
+                automated_commits += 1;
+                continue;
+            }
+            if !identity.is_mine(info.commit.author_email()) {
+                skipped_commits += 1;
+                continue;
+            }
+            if let Some((_, commits)) = branch_commits.iter_mut().find(|(b, _)| *b == update.branch)
+            {
+                let mut commit = info.commit;
+                commit.apply_ignore_rules(&ignore_rules);
+                commits.push(commit);
+            }
+        }
+
+        let branches: Vec<PushedBranch> = branch_commits
+            .into_iter()
+            .map(|(name, commits)| PushedBranch::new(name, commits))
+            .collect();
+        let push = Push::from_branches(branches, remote_url, skipped_commits)
+            .with_automated(automated_commits);
+        if !push.commits().is_empty() || push.automated_commits() > 0 {
+            pushes.push(PastPush { timestamp, push });
+        }
+    }
+
+    Ok(pushes)
+}
+
+/// where a ref pointed just before the given time, according to its reflog.
+/// reflogs only have one-second resolution, so anything in the same second as the
+/// push may have happened after it and is left out
+fn position_before(entries: &[ReflogEntry], timestamp: u64) -> Option<&str> {
+    entries
+        .iter()
+        .take_while(|e| e.timestamp < timestamp)
+        .last()
+        .map(|e| e.sha.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(sha: &str, timestamp: u64) -> ReflogEntry {
+        ReflogEntry {
+            sha: sha.to_string(),
+            timestamp,
+            message: PUSH_MESSAGE.to_string(),
+        }
+    }
+
+    #[test]
+    fn position_is_latest_entry_before_time() {
+        let entries = [entry("a", 100), entry("b", 200), entry("c", 300)];
+
+        assert_eq!(position_before(&entries, 50), None);
+        assert_eq!(position_before(&entries, 100), None);
+        assert_eq!(position_before(&entries, 101), Some("a"));
+        assert_eq!(position_before(&entries, 300), Some("b"));
+        assert_eq!(position_before(&entries, 1000), Some("c"));
+    }
+}
@@ -0,0 +1,126 @@
+//! Recognizing commits made by bots and CI (Dependabot, Renovate, release workflows...)
+//! rather than by a person. Automated commits don't earn points or keep streaks going,
+//! but are still counted in stats.
+//!
+//! A commit is automated when its author or committer name or email matches one of
+//! the patterns, case-insensitively. Patterns are globs where `*` matches anything.
+
+use crate::git::{Commit, ignore};
+
+/// git config key for extra bot patterns (can be set multiple times, globally or per
+/// repo), e.g. `git config --global --add party.bot 'ci@example.com'`
+pub const BOT_KEY: &str = "party.bot";
+
+/// GitHub apps commit as `name[bot]`, and most other bots at least say so in their name
+pub const DEFAULT_PATTERNS: &[&str] = &[
+    "*[bot]",
+    "*[bot]@*",
+    "*-bot",
+    "*-bot@*",
+    "dependabot*",
+    "renovate*",
+    "github-actions*",
+];
+
+#[derive(Debug, Clone)]
+pub struct Bots {
+    patterns: Vec<String>,
+}
+
+impl Default for Bots {
+    fn default() -> Self {
+        Self::from_config([])
+    }
+}
+
+impl Bots {
+    /// the default patterns plus every configured one
+    pub fn from_config<'a>(patterns: impl IntoIterator<Item = &'a str>) -> Self {
+        Self {
+            patterns: DEFAULT_PATTERNS
+                .iter()
+                .copied()
+                .chain(patterns)
+                .map(|p| p.trim().to_lowercase())
+                .filter(|p| !p.is_empty())
+                .collect(),
+        }
+    }
+
+    /// whether a bot wrote or committed this commit
+    pub fn is_automated(&self, commit: &Commit) -> bool {
+        [
+            commit.author_name(),
+            commit.author_email(),
+            commit.committer_name(),
+            commit.committer_email(),
+        ]
+        .iter()
+        .map(|s| s.trim().to_lowercase())
+        .any(|s| !s.is_empty() && self.patterns.iter().any(|p| ignore::matches_glob(p, &s)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commit(author: (&str, &str), committer: (&str, &str)) -> Commit {
+        Commit {
+            author_name: author.0.to_string(),
+            author_email: author.1.to_string(),
+            committer_name: committer.0.to_string(),
+            committer_email: committer.1.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn recognizes_common_bots_by_default() {
+        let bots = Bots::default();
+        let me = ("Ada", "ada@example.com");
+
+        assert!(bots.is_automated(&commit(
+            (
+                "dependabot[bot]",
+                "49699333+dependabot[bot]@users.noreply.github.com"
+            ),
+            ("GitHub", "noreply@github.com"),
+        )));
+        assert!(bots.is_automated(&commit(("Renovate Bot", "bot@renovateapp.com"), me)));
+        assert!(bots.is_automated(&commit(
+            me,
+            (
+                "github-actions[bot]",
+                "41898282+github-actions[bot]@users.noreply.github.com"
+            ),
+        )));
+    }
+
+    #[test]
+    fn people_are_not_bots() {
+        let bots = Bots::default();
+
+        // merging or squashing on github makes "GitHub" the committer
+        assert!(!bots.is_automated(&commit(
+            ("Ada", "ada@example.com"),
+            ("GitHub", "noreply@github.com"),
+        )));
+        assert!(!bots.is_automated(&commit(
+            ("Abbot", "abbot@example.com"),
+            ("Abbot", "abbot@example.com"),
+        )));
+    }
+
+    #[test]
+    fn configured_patterns_match_case_insensitively() {
+        let bots = Bots::from_config(["CI@example.com", "release *"]);
+
+        assert!(bots.is_automated(&commit(
+            ("Ada", "ada@example.com"),
+            ("CI", "ci@example.com")
+        )));
+        assert!(bots.is_automated(&commit(("Release Train", "rt@example.com"), ("", ""))));
+        assert!(!bots.is_automated(&commit(("Ada", "ada@example.com"), ("", ""))));
+    }
+}
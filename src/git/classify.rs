@@ -0,0 +1,218 @@
+//! Telling real work apart from commits that only pad out a push: empty commits,
+//! whitespace-only edits, and reverts (especially ones that cancel a commit in the
+//! same push).
+
+use std::collections::HashSet;
+
+use crate::git::Commit;
+
+/// what kind of change a commit makes, as far as scoring is concerned
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CommitKind {
+    Substantive,
+
+    /// no changes at all, e.g. `git commit --allow-empty`
+    Empty,
+
+    /// only whitespace changed, e.g. re-indenting or trailing spaces
+    WhitespaceOnly,
+
+    /// reverts an earlier commit
+    Revert,
+
+    /// a commit and its revert in the same push, which cancel each other out
+    RevertPair,
+}
+
+impl CommitKind {
+    pub fn is_substantive(self) -> bool {
+        self == Self::Substantive
+    }
+}
+
+/// classifies every commit in a push, in the same order
+pub fn classify_commits(commits: &[Commit]) -> Vec<CommitKind> {
+    let shas: HashSet<&str> = commits.iter().map(|c| c.sha()).collect();
+
+    // both sides of a revert whose original is in this push
+    let mut paired = HashSet::new();
+    for commit in commits {
+        if let Some(reverted) = reverted_sha(commit)
+            && shas.contains(reverted)
+        {
+            paired.insert(commit.sha());
+            paired.insert(reverted);
+        }
+    }
+
+    commits
+        .iter()
+        .map(|commit| {
+            if paired.contains(commit.sha()) {
+                CommitKind::RevertPair
+            } else {
+                classify(commit)
+            }
+        })
+        .collect()
+}
+
+/// classifies a single commit on its own, without looking for revert pairs
+pub fn classify(commit: &Commit) -> CommitKind {
+    if commit.files.is_empty() && commit.lines_changed() == 0 {
+        CommitKind::Empty
+    } else if commit.whitespace_only {
+        CommitKind::WhitespaceOnly
+    } else if reverted_sha(commit).is_some() {
+        CommitKind::Revert
+    } else {
+        CommitKind::Substantive
+    }
+}
+
+/// the commit this one reverts, from the "This reverts commit <sha>." line that
+/// `git revert` writes
+fn reverted_sha(commit: &Commit) -> Option<&str> {
+    commit.body().lines().find_map(|line| {
+        let sha = line.trim().strip_prefix("This reverts commit ")?;
+        let sha = sha.trim_end_matches('.');
+        (!sha.is_empty() && sha.chars().all(|c| c.is_ascii_hexdigit())).then_some(sha)
+    })
+}
+
+/// whether a patch (as printed by `git log -p`) changes nothing but whitespace:
+/// every file's removed and added lines are the same once whitespace is stripped.
+/// a patch without any changed lines (e.g. renames or mode changes) doesn't count
+pub(super) fn is_whitespace_only(patch: &str) -> bool {
+    let mut changed_lines = false;
+    let mut removed = String::new();
+    let mut added = String::new();
+    let mut in_hunk = false;
+
+    for line in patch.split('\n') {
+        if line.starts_with("diff ") {
+            if removed != added {
+                return false;
+            }
+            removed.clear();
+            added.clear();
+            in_hunk = false;
+        } else if line.starts_with("@@") {
+            in_hunk = true;
+        } else if !in_hunk {
+            // file headers, including "Binary files ... differ"
+            if line.starts_with("Binary files ") || line.starts_with("GIT binary patch") {
+                return false;
+            }
+        } else if let Some(text) = line.strip_prefix('-') {
+            changed_lines = true;
+            removed.extend(text.chars().filter(|c| !c.is_whitespace()));
+        } else if let Some(text) = line.strip_prefix('+') {
+            changed_lines = true;
+            added.extend(text.chars().filter(|c| !c.is_whitespace()));
+        }
+    }
+
+    changed_lines && removed == added
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commit(sha: &str, body: &str) -> Commit {
+        Commit {
+            sha: sha.to_string(),
+            body: body.to_string(),
+            insertions: 3,
+            deletions: 1,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn classifies_single_commits() {
+        assert_eq!(classify(&commit("a", "")), CommitKind::Substantive);
+        assert_eq!(classify(&Commit::default()), CommitKind::Empty);
+        assert_eq!(
+            classify(&Commit {
+                whitespace_only: true,
+                ..commit("a", "")
+            }),
+            CommitKind::WhitespaceOnly
+        );
+        assert_eq!(
+            classify(&commit(
+                "b",
+                "This reverts commit 0123abcd.\n\nit broke the build"
+            )),
+            CommitKind::Revert
+        );
+    }
+
+    #[test]
+    fn detects_revert_pairs_within_a_push() {
+        let commits = vec![
+            commit("c0ffee", "This reverts commit ba5eba11."),
+            commit("ba5eba11", ""),
+            commit("f00d", "This reverts commit deadbeef."),
+            commit("beef", ""),
+        ];
+
+        assert_eq!(
+            classify_commits(&commits),
+            vec![
+                CommitKind::RevertPair,
+                CommitKind::RevertPair,
+                CommitKind::Revert,
+                CommitKind::Substantive,
+            ]
+        );
+    }
+
+    #[test]
+    fn whitespace_only_patches() {
+        let reindent = "diff --git a/a.rs b/a.rs\n\
+            --- a/a.rs\n\
+            +++ b/a.rs\n\
+            @@ -1,2 +1,2 @@\n\
+            -fn main() {\n\
+            -println!(\"hi\");\n\
+            +fn main()  {\n\
+            +    println!(\"hi\");\n";
+        assert!(is_whitespace_only(reindent));
+
+        let real = "diff --git a/a.rs b/a.rs\n\
+            --- a/a.rs\n\
+            +++ b/a.rs\n\
+            @@ -1 +1 @@\n\
+            -let x = 1;\n\
+            +let x = 2;\n";
+        assert!(!is_whitespace_only(real));
+    }
+
+    #[test]
+    fn whitespace_is_compared_per_file() {
+        // moving a line from one file to another is a real change
+        let moved = "diff --git a/a.rs b/a.rs\n\
+            --- a/a.rs\n\
+            +++ b/a.rs\n\
+            @@ -1 +0,0 @@\n\
+            -let x = 1;\n\
+            diff --git a/b.rs b/b.rs\n\
+            --- a/b.rs\n\
+            +++ b/b.rs\n\
+            @@ -0,0 +1 @@\n\
+            +let x = 1;\n";
+        assert!(!is_whitespace_only(moved));
+    }
+
+    #[test]
+    fn patches_without_changed_lines_are_not_whitespace_only() {
+        let rename = "diff --git a/a.rs b/b.rs\n\
+            similarity index 100%\n\
+            rename from a.rs\n\
+            rename to b.rs\n";
+        assert!(!is_whitespace_only(rename));
+    }
+}
@@ -1,256 +1,626 @@
-use std::collections::HashMap;
-use std::path::Path;
-use std::process::Command;
-
-/// Returns all remote tracking branches and their SHAs.
-/// e.g., {"main" => "abc123", "feature" => "def456"}
-pub fn get_all_remote_refs(repo_path: &Path) -> HashMap<String, String> {
+//! Thin wrappers around git subprocesses.
+//!
+//! Detecting a push needs a fixed number of git invocations no matter how many
+//! commits or branches are involved: refs are read with one `for-each-ref` each,
+//! and every pushed commit is described by a single `git log` stream whose patches
+//! are piped through one `git patch-id` process.
+
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use crate::git::{Commit, FileChange, classify};
+
+/// Returns the root of the working tree containing `cwd` and the git dir shared by all
+/// of its worktrees, both as absolute paths. None outside a (non-bare) git repo.
+/// e.g., ("/code/x-worktree", "/code/x/.git")
+pub fn get_repo_dirs(cwd: &Path) -> Option<(PathBuf, PathBuf)> {
     let output = Command::new("git")
         .args([
-            "for-each-ref",
-            "--format=%(refname:short) %(objectname)",
-            "refs/remotes/origin/",
+            "rev-parse",
+            "--path-format=absolute",
+            "--show-toplevel",
+            "--git-common-dir",
         ])
+        .current_dir(cwd)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    let stdout = String::from_utf8(output.stdout).ok()?;
+    let mut lines = stdout.lines();
+    let toplevel = PathBuf::from(lines.next()?);
+    let common_dir = PathBuf::from(lines.next()?);
+    Some((toplevel, common_dir))
+}
+
+/// Returns the branch checked out in `repo_path`, or None on a detached HEAD.
+pub fn get_current_branch(repo_path: &Path) -> Option<String> {
+    let output = Command::new("git")
+        .args(["symbolic-ref", "--quiet", "--short", "HEAD"])
         .current_dir(repo_path)
         .output()
-        .ok();
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let branch = String::from_utf8(output.stdout).ok()?.trim().to_string();
+    (!branch.is_empty()).then_some(branch)
+}
 
-    let mut refs = HashMap::new();
-    if let Some(output) = output
-        && output.status.success()
+/// Returns every configured remote and its fetch url, in git's order.
+/// e.g., [("origin", "git@github.com:me/x.git"), ("upstream", "...")]
+pub fn get_remote_urls(repo_path: &Path) -> Vec<(String, String)> {
+    let output = Command::new("git")
+        .args(["remote", "-v"])
+        .current_dir(repo_path)
+        .output();
+
+    let mut remotes = Vec::new();
+    if let Ok(o) = output
+        && o.status.success()
     {
-        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stdout = String::from_utf8_lossy(&o.stdout);
         for line in stdout.lines() {
-            // format: "origin/main abc123..."
-            let parts: Vec<&str> = line.split_whitespace().collect();
-            if parts.len() >= 2 {
-                // strip "origin/" prefix
-                if let Some(branch) = parts[0].strip_prefix("origin/") {
-                    // skip HEAD
-                    if branch != "HEAD" {
-                        refs.insert(branch.to_string(), parts[1].to_string());
-                    }
-                }
+            // format: "origin\tgit@github.com:me/x.git (fetch)"
+            let Some((remote, rest)) = line.split_once('\t') else {
+                continue;
+            };
+            if let Some(url) = rest.strip_suffix(" (fetch)") {
+                remotes.push((remote.to_string(), url.to_string()));
             }
         }
     }
-    refs
+    remotes
 }
 
-/// Returns all local tracking branches and their SHAs.
-/// e.g., {"main" => "abc123", "feature" => "def456"}
-pub fn get_all_local_refs(repo_path: &Path) -> HashMap<String, String> {
+/// Returns every value of the given git config keys (local and global config),
+/// in a single call. Keys are lowercased by git, e.g. "user.email".
+/// e.g., [("user.email", "me@example.com"), ("party.email", "me@work.example")]
+pub fn get_config_values(repo_path: &Path, keys: &[&str]) -> Vec<(String, String)> {
+    // git only lowercases the ends of the regex, so a camelCase key in the middle of
+    // the alternation would never match
+    let pattern = keys
+        .iter()
+        .map(|k| k.to_lowercase().replace('.', "\\."))
+        .collect::<Vec<_>>()
+        .join("|");
+    let output = Command::new("git")
+        .args(["config", "--get-regexp", &format!("^({pattern})$")])
+        .current_dir(repo_path)
+        .output();
+
+    let mut values = Vec::new();
+    if let Ok(o) = output
+        && o.status.success()
+    {
+        let stdout = String::from_utf8_lossy(&o.stdout);
+        for line in stdout.lines() {
+            // format: "user.email me@example.com"
+            let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+            values.push((key.to_string(), value.to_string()));
+        }
+    }
+    values
+}
+
+/// Returns all remote tracking branches and their SHAs, grouped by remote.
+/// e.g., {"origin" => {"main" => "abc123"}, "upstream" => {"main" => "def456"}}
+pub fn get_all_remote_refs(
+    repo_path: &Path,
+    remotes: &[String],
+) -> HashMap<String, HashMap<String, String>> {
+    get_all_refs(repo_path, remotes).0
+}
+
+/// Returns all remote tracking branches grouped by remote (as in `get_all_remote_refs`),
+/// and all local branches, with their SHAs, in a single call.
+/// e.g., ({"origin" => {"main" => "abc123"}}, {"main" => "abc123", "feature" => "def456"})
+pub fn get_all_refs(
+    repo_path: &Path,
+    remotes: &[String],
+) -> (
+    HashMap<String, HashMap<String, String>>,
+    HashMap<String, String>,
+) {
     let output = Command::new("git")
         .args([
             "for-each-ref",
-            "--format=%(refname:short) %(objectname)",
-            "refs/heads",
+            "--format=%(refname) %(objectname)",
+            "refs/remotes/",
+            "refs/heads/",
         ])
         .current_dir(repo_path)
         .output()
         .ok();
 
-    let mut refs = HashMap::new();
+    let mut remote_refs: HashMap<String, HashMap<String, String>> = HashMap::new();
+    let mut local_refs = HashMap::new();
     if let Some(output) = output
         && output.status.success()
     {
         let stdout = String::from_utf8_lossy(&output.stdout);
         for line in stdout.lines() {
-            // format: "branch-name abc123..."
+            // format: "refs/remotes/origin/main abc123..."
             let parts: Vec<&str> = line.split_whitespace().collect();
-            if parts.len() >= 2 {
-                let branch = parts[0];
-                let sha = parts[1];
-                refs.insert(branch.to_string(), sha.to_string());
+            if parts.len() < 2 {
+                continue;
+            }
+
+            if let Some(branch) = parts[0].strip_prefix("refs/heads/") {
+                local_refs.insert(branch.to_string(), parts[1].to_string());
+                continue;
+            }
+
+            let Some(name) = parts[0].strip_prefix("refs/remotes/") else {
+                continue;
+            };
+
+            // remote names can contain slashes, so match the longest configured remote
+            let Some((remote, branch)) = remotes
+                .iter()
+                .filter_map(|r| {
+                    name.strip_prefix(r.as_str())
+                        .and_then(|rest| rest.strip_prefix('/'))
+                        .map(|branch| (r, branch))
+                })
+                .max_by_key(|(r, _)| r.len())
+            else {
+                continue;
+            };
+
+            // skip HEAD
+            if branch != "HEAD" {
+                remote_refs
+                    .entry(remote.clone())
+                    .or_default()
+                    .insert(branch.to_string(), parts[1].to_string());
             }
         }
     }
-    refs
+    (remote_refs, local_refs)
 }
 
-pub fn get_remote_url(repo_path: &Path) -> Option<String> {
+/// Returns the given commits that aren't reachable from any of the others, in a single
+/// call. None if any of them can't be found (e.g. garbage collected).
+pub fn get_independent_commits(repo_path: &Path, shas: &[&str]) -> Option<HashSet<String>> {
+    if shas.is_empty() {
+        return Some(HashSet::new());
+    }
+
     let output = Command::new("git")
-        .args(["remote", "get-url", "origin"])
+        .args(["merge-base", "--independent"])
+        .args(shas)
         .current_dir(repo_path)
         .output()
         .ok()?;
-
-    if output.status.success() {
-        Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
-    } else {
-        None
+    if !output.status.success() {
+        return None;
     }
+
+    let stdout = String::from_utf8(output.stdout).ok()?;
+    Some(stdout.lines().map(|l| l.trim().to_string()).collect())
 }
 
-/// List commits in range old_sha..new_sha (commits reachable from new but not old).
-pub fn list_commits_in_range(repo_path: &Path, old_sha: &str, new_sha: &str) -> Vec<String> {
-    let output = Command::new("git")
-        .args(["rev-list", &format!("{}..{}", old_sha, new_sha)])
-        .current_dir(repo_path)
-        .output();
+/// One entry in a ref's reflog.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReflogEntry {
+    /// where the ref pointed after this update
+    pub sha: String,
+    pub timestamp: u64,
 
-    match output {
-        Ok(o) if o.status.success() => String::from_utf8_lossy(&o.stdout)
-            .lines()
-            .map(|s| s.to_string())
-            .collect(),
-        _ => Vec::new(),
-    }
+    /// e.g. "update by push", "fetch: fast-forward"
+    pub message: String,
 }
 
-/// Check if a commit is reachable from any remote branch OTHER than the specified ones.
-/// Used to filter out commits that came from fetch but are in the push range due to rebasing.
-pub fn is_reachable_from_other_remote(
-    repo_path: &Path,
-    sha: &str,
-    exclude_branches: &[&str],
-) -> bool {
-    // Get all remote branches except the ones we're pushing
-    let refs_output = Command::new("git")
+/// Returns the reflog of each of the given refs, oldest entry first, in a single call.
+/// Refs without a reflog are left out.
+/// e.g., {"refs/remotes/origin/main" => [ReflogEntry { sha: "abc123", .. }, ..]}
+pub fn get_reflogs(repo_path: &Path, refnames: &[&str]) -> HashMap<String, Vec<ReflogEntry>> {
+    let mut reflogs: HashMap<String, Vec<ReflogEntry>> = HashMap::new();
+    if refnames.is_empty() {
+        return reflogs;
+    }
+
+    let output = Command::new("git")
         .args([
-            "for-each-ref",
-            "--format=%(refname)",
-            "refs/remotes/origin/",
+            "log",
+            "--walk-reflogs",
+            "--ignore-missing",
+            "--date=unix",
+            "--format=%H%x1f%gD%x1f%gs",
+            "--no-color",
         ])
+        .args(refnames)
+        .arg("--")
         .current_dir(repo_path)
-        .output();
+        .output()
+        .ok();
 
-    let other_refs: Vec<String> = match refs_output {
-        Ok(o) if o.status.success() => String::from_utf8_lossy(&o.stdout)
-            .lines()
-            .filter(|r| {
-                let dominated = exclude_branches
-                    .iter()
-                    .any(|b| r.ends_with(&format!("/{}", b)));
-                !dominated && !r.ends_with("/HEAD")
-            })
-            .map(|s| s.to_string())
-            .collect(),
-        _ => return false,
-    };
+    if let Some(output) = output
+        && output.status.success()
+    {
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        for line in stdout.lines() {
+            // format: "abc123<US>refs/remotes/origin/main@{1700000000}<US>update by push"
+            let mut fields = line.splitn(3, '\x1f');
+            let (Some(sha), Some(selector), Some(message)) =
+                (fields.next(), fields.next(), fields.next())
+            else {
+                continue;
+            };
+            let Some((refname, timestamp)) =
+                selector.strip_suffix('}').and_then(|s| s.rsplit_once("@{"))
+            else {
+                continue;
+            };
+            let Ok(timestamp) = timestamp.parse() else {
+                continue;
+            };
+
+            reflogs
+                .entry(refname.to_string())
+                .or_default()
+                .push(ReflogEntry {
+                    sha: sha.to_string(),
+                    timestamp,
+                    message: message.to_string(),
+                });
+        }
+    }
 
-    if other_refs.is_empty() {
-        return false;
+    // git lists each reflog newest first
+    for entries in reflogs.values_mut() {
+        entries.reverse();
+    }
+    reflogs
+}
+
+/// What we learned about a single commit from the log stream.
+#[derive(Debug, Clone)]
+pub struct CommitInfo {
+    pub commit: Commit,
+
+    /// the ref (as passed in `include`) through which the commit was reached
+    pub source: String,
+
+    /// None if the commit has no diff (e.g., merge or empty commits)
+    pub patch_id: Option<String>,
+}
+
+/// `git log` format for a commit's metadata. fields are separated by US (0x1f) and
+/// the whole header is terminated by RS (0x1e), since messages span multiple lines
+const LOG_FORMAT: &str = concat!(
+    "--format=%x00",
+    "%H%x1f%S%x1f",
+    "%an%x1f%ae%x1f%cn%x1f%ce%x1f%at%x1f%ct%x1f",
+    "%s%x1f%b%x1f%(trailers:only,unfold)",
+    "%x1e",
+);
+
+/// Describes every commit reachable from `include` but not from `exclude`
+/// (refs or SHAs), newest first.
+///
+/// Uses at most three git processes regardless of how many commits are involved:
+/// one `git cat-file` to drop stale exclusions, one `git log --numstat -p`, and one
+/// `git patch-id --stable` fed with its patches.
+pub fn scan_commits(repo_path: &Path, include: &[&str], exclude: &[&str]) -> Vec<CommitInfo> {
+    if include.is_empty() {
+        return Vec::new();
     }
 
-    // Check if commit is reachable from any of these refs
-    // git merge-base --is-ancestor <sha> <ref> returns 0 if sha is ancestor of ref
-    for ref_name in &other_refs {
-        let result = Command::new("git")
-            .args(["merge-base", "--is-ancestor", sha, ref_name])
-            .current_dir(repo_path)
-            .output();
+    // revisions go over stdin so that repos with many branches can't overflow argv.
+    // exclusions that no longer exist (e.g. a stale sha after gc) would make git bail
+    let mut revs = String::new();
+    for rev in include {
+        revs.push_str(&format!("{rev}\n"));
+    }
+    for rev in existing_revs(repo_path, exclude) {
+        revs.push_str(&format!("^{rev}\n"));
+    }
+
+    // each commit starts with a NUL, followed by its metadata header (see LOG_FORMAT),
+    // its numstat lines, and finally its patch
+    let Some(log) = run_with_stdin(
+        repo_path,
+        &[
+            "log",
+            "--stdin",
+            "--source",
+            "--no-color",
+            "--no-ext-diff",
+            "--numstat",
+            "-p",
+            LOG_FORMAT,
+        ],
+        revs.as_bytes(),
+    ) else {
+        return Vec::new();
+    };
+    let log = String::from_utf8_lossy(&log);
+
+    let mut commits = Vec::new();
+    let mut patches = String::new();
+
+    for chunk in log.split('\0').skip(1) {
+        let Some((header, rest)) = chunk.split_once('\x1e') else {
+            continue;
+        };
+        let Some((commit, source)) = parse_header(header) else {
+            continue;
+        };
+
+        let mut commit = commit;
+        let mut patch = String::new();
+
+        // NOTE: not `lines()`, which would strip the `\r` from CRLF patches and change their patch-ids
+        for line in rest.strip_suffix('\n').unwrap_or(rest).split('\n') {
+            if !patch.is_empty() || line.starts_with("diff ") {
+                patch.push_str(line);
+                patch.push('\n');
+            } else if let Some(file) = parse_numstat_line(line) {
+                commit.insertions += file.insertions;
+                commit.deletions += file.deletions;
+                commit.files.push(file);
+            }
+        }
 
-        if let Ok(o) = result
-            && o.status.success()
-        {
-            return true;
+        if !patch.is_empty() {
+            commit.whitespace_only = classify::is_whitespace_only(&patch);
+            patches.push_str(&format!("commit {}\n{patch}", commit.sha));
         }
+
+        commits.push(CommitInfo {
+            commit,
+            source,
+            patch_id: None,
+        });
+    }
+
+    let patch_ids = get_patch_ids(repo_path, &patches);
+    for info in &mut commits {
+        info.patch_id = patch_ids.get(&info.commit.sha).cloned();
+        info.commit.patch_id = info.patch_id.clone();
     }
 
-    false
+    commits
 }
 
-/// List commits on the given branches that aren't reachable from any other remote branch.
-/// Used for first-time pushes where we don't have an old SHA.
-pub fn list_unique_commits(repo_path: &Path, branches: &[&str]) -> Vec<String> {
-    // git rev-list origin/branch1 origin/branch2 ... --not --exclude=origin/branch1 --exclude=origin/branch2 ... --remotes=origin
-    let mut args = vec!["rev-list".to_string()];
+/// Parses the metadata header written by LOG_FORMAT into a commit (without
+/// file stats) and the ref it was reached through.
+fn parse_header(header: &str) -> Option<(Commit, String)> {
+    let fields: Vec<&str> = header.split('\x1f').collect();
+    let [
+        sha,
+        source,
+        author_name,
+        author_email,
+        committer_name,
+        committer_email,
+        authored_at,
+        committed_at,
+        subject,
+        body,
+        trailers,
+    ] = fields.as_slice()
+    else {
+        return None;
+    };
 
-    // add all branches as positive refs
-    for branch in branches {
-        args.push(format!("refs/remotes/origin/{}", branch));
-    }
+    let trailers = trailers
+        .lines()
+        .filter_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            Some((key.trim().to_string(), value.trim().to_string()))
+        })
+        .collect();
+
+    let commit = Commit {
+        sha: sha.to_string(),
+        author_name: author_name.to_string(),
+        author_email: author_email.to_string(),
+        committer_name: committer_name.to_string(),
+        committer_email: committer_email.to_string(),
+        authored_at: authored_at.parse().unwrap_or(0),
+        committed_at: committed_at.parse().unwrap_or(0),
+        subject: subject.to_string(),
+        body: body.trim_end().to_string(),
+        trailers,
+        ..Default::default()
+    };
 
-    args.push("--not".to_string());
+    Some((commit, source.to_string()))
+}
 
-    // exclude all the branches we're including
-    for branch in branches {
-        args.push(format!("--exclude=origin/{}", branch));
+/// Filters `revs` down to the ones that resolve to an object, in a single `git cat-file` call.
+fn existing_revs<'a>(repo_path: &Path, revs: &[&'a str]) -> Vec<&'a str> {
+    if revs.is_empty() {
+        return Vec::new();
     }
 
-    args.push("--remotes=origin".to_string());
+    let input: String = revs.iter().map(|r| format!("{r}\n")).collect();
+    let Some(output) = run_with_stdin(repo_path, &["cat-file", "--batch-check"], input.as_bytes())
+    else {
+        return Vec::new();
+    };
 
-    let output = Command::new("git")
-        .args(&args)
-        .current_dir(repo_path)
-        .output();
+    // one output line per input line, ending in " missing" if it didn't resolve
+    String::from_utf8_lossy(&output)
+        .lines()
+        .zip(revs)
+        .filter(|(line, _)| !line.ends_with(" missing"))
+        .map(|(_, rev)| *rev)
+        .collect()
+}
 
-    match output {
-        Ok(o) if o.status.success() => String::from_utf8_lossy(&o.stdout)
-            .lines()
-            .map(|s| s.to_string())
-            .collect(),
-        _ => Vec::new(),
+/// For each object a tag could point at, whether it's an annotated tag object and the
+/// commit it ultimately refers to, in a single `git cat-file` call. Objects that
+/// don't lead to a commit are left out.
+/// e.g., {"abc123" => (true, "def456"), "def456" => (false, "def456")}
+pub fn get_tag_targets(repo_path: &Path, shas: &[&str]) -> HashMap<String, (bool, String)> {
+    if shas.is_empty() {
+        return HashMap::new();
     }
+
+    // each object is looked up as itself and peeled down to a commit
+    let input: String = shas
+        .iter()
+        .map(|sha| format!("{sha}\n{sha}^{{commit}}\n"))
+        .collect();
+    let Some(output) = run_with_stdin(
+        repo_path,
+        &["cat-file", "--batch-check=%(objectname) %(objecttype)"],
+        input.as_bytes(),
+    ) else {
+        return HashMap::new();
+    };
+
+    // format: "<sha> <type>", or "<input> missing"
+    let output = String::from_utf8_lossy(&output);
+    let lines: Vec<&str> = output.lines().collect();
+    let mut targets = HashMap::new();
+    for (sha, pair) in shas.iter().zip(lines.chunks(2)) {
+        let [object, commit] = pair else {
+            continue;
+        };
+        let (Some((_, kind)), Some((commit_sha, "commit"))) =
+            (object.split_once(' '), commit.split_once(' '))
+        else {
+            continue;
+        };
+        targets.insert(sha.to_string(), (kind == "tag", commit_sha.to_string()));
+    }
+    targets
 }
 
-/// Returns total lines changed (added + removed) for a commit.
-pub fn get_lines_changed(repo_path: &Path, sha: &str) -> Option<u64> {
-    // git show --stat --format="" <sha>
-    // outputs lines like: " file.rs | 10 ++++----"
-    // with summary: " 3 files changed, 10 insertions(+), 5 deletions(-)"
-    let output = Command::new("git")
-        .args(["show", "--stat", "--format=", sha])
-        .current_dir(repo_path)
-        .output()
-        .ok()?;
+/// How long `get_remote_tags` waits on the remote before giving up.
+const REMOTE_TIMEOUT: Duration = Duration::from_secs(10);
 
+/// Asks `remote` which of the given tags it has, in a single `git ls-remote` call.
+/// Returns tag name => the object it points to, or None if the remote can't be reached.
+/// Never prompts for credentials or passphrases, since nobody may be watching, and
+/// gives up on a remote that takes longer than `REMOTE_TIMEOUT` to answer.
+/// e.g., {"v1.2.0" => "abc123"}
+pub fn get_remote_tags(
+    repo_path: &Path,
+    remote: &str,
+    names: &[&str],
+) -> Option<HashMap<String, String>> {
+    let refs: Vec<String> = names.iter().map(|n| format!("refs/tags/{n}")).collect();
+    let mut cmd = Command::new("git");
+    cmd.args(["ls-remote", "--tags", remote])
+        .args(&refs)
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .current_dir(repo_path);
+    if std::env::var_os("GIT_SSH_COMMAND").is_none() {
+        cmd.env(
+            "GIT_SSH_COMMAND",
+            "ssh -o BatchMode=yes -o ConnectTimeout=5",
+        );
+    }
+    let mut child = cmd.spawn().ok()?;
+
+    // only the named tags are listed, so the output is far too small to fill the pipe
+    // before git exits
+    let deadline = Instant::now() + REMOTE_TIMEOUT;
+    while child.try_wait().ok()?.is_none() {
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return None;
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    }
+    let output = child.wait_with_output().ok()?;
     if !output.status.success() {
         return None;
     }
 
+    // format: "<sha>\trefs/tags/<name>", plus "<sha>\trefs/tags/<name>^{}" for the
+    // commit an annotated tag points to
     let stdout = String::from_utf8_lossy(&output.stdout);
-    // parse the last line for the summary
-    let last_line = stdout.lines().last()?;
-
-    let mut insertions = 0u64;
-    let mut deletions = 0u64;
-
-    // look for "N insertion" and "N deletion" patterns
-    for word in last_line.split_whitespace().collect::<Vec<_>>().windows(2) {
-        if word[1].starts_with("insertion") {
-            insertions = word[0].parse().unwrap_or(0);
-        } else if word[1].starts_with("deletion") {
-            deletions = word[0].parse().unwrap_or(0);
-        }
+    let tags = stdout
+        .lines()
+        .filter_map(|line| {
+            let (sha, refname) = line.split_once('\t')?;
+            let name = refname.strip_prefix("refs/tags/")?;
+            (!name.ends_with("^{}")).then(|| (name.to_string(), sha.to_string()))
+        })
+        .collect();
+    Some(tags)
+}
+
+/// Parses a `--numstat` line like "10\t5\tsrc/main.rs".
+/// Binary files show up as "-\t-\tfile" and count as zero lines.
+fn parse_numstat_line(line: &str) -> Option<FileChange> {
+    let mut parts = line.splitn(3, '\t');
+    let (insertions, deletions, path) = (parts.next()?, parts.next()?, parts.next()?);
+
+    if insertions == "-" && deletions == "-" {
+        return Some(FileChange {
+            path: path.to_string(),
+            ..Default::default()
+        });
     }
 
-    Some(insertions + deletions)
+    Some(FileChange {
+        path: path.to_string(),
+        insertions: insertions.parse().ok()?,
+        deletions: deletions.parse().ok()?,
+    })
 }
 
-/// Get the patch-id for a commit. Returns None if the commit has no diff (e.g., merge commits).
-pub fn get_patch_id(repo_path: &Path, sha: &str) -> Option<String> {
-    // git show <sha> | git patch-id --stable
-    let show = Command::new("git")
-        .args(["show", sha])
-        .current_dir(repo_path)
-        .output()
-        .ok()?;
-
-    if !show.status.success() {
-        return None;
+/// Runs `git patch-id --stable` once over a stream of "commit <sha>" headers and patches.
+/// Returns a map from commit SHA to patch-id.
+fn get_patch_ids(repo_path: &Path, patches: &str) -> HashMap<String, String> {
+    if patches.is_empty() {
+        return HashMap::new();
     }
 
-    let patch_id = Command::new("git")
-        .args(["patch-id", "--stable"])
-        .stdin(std::process::Stdio::piped())
-        .stdout(std::process::Stdio::piped())
+    let Some(output) = run_with_stdin(repo_path, &["patch-id", "--stable"], patches.as_bytes())
+    else {
+        return HashMap::new();
+    };
+
+    // format: "<patch-id> <commit-sha>"
+    String::from_utf8_lossy(&output)
+        .lines()
+        .filter_map(|line| {
+            let (patch_id, sha) = line.split_once(' ')?;
+            Some((sha.trim().to_string(), patch_id.to_string()))
+        })
+        .collect()
+}
+
+/// Runs a git command with the given stdin, returning stdout if it succeeded.
+fn run_with_stdin(repo_path: &Path, args: &[&str], input: &[u8]) -> Option<Vec<u8>> {
+    let mut child = Command::new("git")
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
         .current_dir(repo_path)
         .spawn()
         .ok()?;
 
-    use std::io::Write;
-    patch_id.stdin.as_ref()?.write_all(&show.stdout).ok()?;
-    let output = patch_id.wait_with_output().ok()?;
-
-    if output.status.success() {
-        let line = String::from_utf8_lossy(&output.stdout);
-        // format: "<patch-id> <commit-sha>"
-        line.split_whitespace().next().map(|s| s.to_string())
-    } else {
-        None
-    }
+    // write from another thread so a large output can't deadlock against a full stdin pipe
+    let mut stdin = child.stdin.take()?;
+    let output = std::thread::scope(|s| {
+        s.spawn(move || {
+            let _ = stdin.write_all(input);
+        });
+        child.wait_with_output()
+    })
+    .ok()?;
+
+    output.status.success().then_some(output.stdout)
 }
 
 #[cfg(test)]
@@ -348,13 +718,34 @@ mod tests {
         }
     }
 
+    fn lines_changed(repo: &TestRepo, sha: &str) -> Option<u64> {
+        scan_commits(&repo.path, &[sha], &[])
+            .into_iter()
+            .find(|c| c.commit.sha == sha)
+            .map(|c| c.commit.lines_changed())
+    }
+
+    fn show_patch_id(repo: &TestRepo, sha: &str) -> String {
+        let show = Command::new("git")
+            .args(["show", sha])
+            .current_dir(&repo.path)
+            .output()
+            .unwrap();
+        let output = run_with_stdin(&repo.path, &["patch-id", "--stable"], &show.stdout).unwrap();
+        String::from_utf8_lossy(&output)
+            .split_whitespace()
+            .next()
+            .unwrap()
+            .to_string()
+    }
+
     #[test]
     fn lines_changed_counts_insertions() {
         let repo = TestRepo::new();
         repo.write_file("test.txt", "line1\nline2\nline3\n");
         let sha = repo.commit("add 3 lines");
 
-        let lines = get_lines_changed(&repo.path, &sha);
+        let lines = lines_changed(&repo, &sha);
         assert_eq!(lines, Some(3));
     }
 
@@ -367,7 +758,7 @@ mod tests {
         repo.write_file("test.txt", "line1\n");
         let sha = repo.commit("delete 2 lines");
 
-        let lines = get_lines_changed(&repo.path, &sha);
+        let lines = lines_changed(&repo, &sha);
         assert_eq!(lines, Some(2));
     }
 
@@ -381,8 +772,15 @@ mod tests {
         let sha = repo.commit("modify and add");
 
         // 1 deletion (bbb) + 2 insertions (BBB, ddd) = 3
-        let lines = get_lines_changed(&repo.path, &sha);
+        let lines = lines_changed(&repo, &sha);
         assert_eq!(lines, Some(3));
+
+        let commit = scan_commits(&repo.path, &[&sha], &[])
+            .into_iter()
+            .find(|c| c.commit.sha == sha)
+            .unwrap()
+            .commit;
+        assert_eq!((commit.insertions, commit.deletions), (2, 1));
     }
 
     #[test]
@@ -394,7 +792,7 @@ mod tests {
         repo.write_file("test.txt", "line1\nline2\nline3\n");
         let sha = repo.commit("add one line");
 
-        let lines = get_lines_changed(&repo.path, &sha);
+        let lines = lines_changed(&repo, &sha);
         assert_eq!(lines, Some(1));
     }
 
@@ -404,12 +802,12 @@ mod tests {
         repo.write_file("test.txt", "content\n");
         repo.commit("initial");
 
-        let lines = get_lines_changed(&repo.path, "invalid-sha");
+        let lines = lines_changed(&repo, "invalid-sha");
         assert_eq!(lines, None);
     }
 
     #[test]
-    fn test_get_all_local_refs() {
+    fn test_get_all_refs_lists_local_branches() {
         let repo = TestRepo::new();
 
         repo.write_file("test0.txt", "content\n");
@@ -426,7 +824,7 @@ mod tests {
         repo.write_file("test2.txt", "content\n");
         repo.commit("commit2");
 
-        let local_refs = get_all_local_refs(&repo.path);
+        let (_, local_refs) = get_all_refs(&repo.path, &[]);
         assert_eq!(
             local_refs.keys().collect::<HashSet<_>>(),
             HashSet::from([
@@ -436,4 +834,341 @@ mod tests {
             ])
         )
     }
+
+    #[test]
+    fn independent_commits_are_not_ancestors_of_the_others() {
+        let repo = TestRepo::new();
+        repo.write_file("test.txt", "one\n");
+        let first = repo.commit("first");
+        repo.write_file("test.txt", "two\n");
+        let second = repo.commit("second");
+
+        // a sibling of `second`
+        Command::new("git")
+            .args(["checkout", "--detach", &first])
+            .current_dir(&repo.path)
+            .output()
+            .unwrap();
+        repo.write_file("test.txt", "other\n");
+        let sibling = repo.commit("sibling");
+
+        let independent =
+            get_independent_commits(&repo.path, &[&first, &second, &sibling]).unwrap();
+        assert_eq!(independent, HashSet::from([second, sibling]));
+
+        assert_eq!(
+            get_independent_commits(&repo.path, &[&first, "0123456789abcdef"]),
+            None
+        );
+    }
+
+    #[test]
+    fn tag_targets_peel_annotated_tags_to_commits() {
+        let repo = TestRepo::new();
+        repo.write_file("test.txt", "content\n");
+        let sha = repo.commit("initial");
+
+        Command::new("git")
+            .args(["tag", "-a", "v1.0.0", "-m", "release"])
+            .current_dir(&repo.path)
+            .output()
+            .unwrap();
+        let output = Command::new("git")
+            .args(["rev-parse", "v1.0.0", "HEAD^{tree}"])
+            .current_dir(&repo.path)
+            .output()
+            .unwrap();
+        let output = String::from_utf8_lossy(&output.stdout);
+        let [tag, tree] = output.lines().collect::<Vec<_>>()[..] else {
+            panic!("expected two shas");
+        };
+
+        let targets = get_tag_targets(&repo.path, &[tag, &sha, tree, "0123456789abcdef"]);
+        assert_eq!(targets[tag], (true, sha.clone()));
+        assert_eq!(targets[&sha], (false, sha.clone()));
+        assert!(!targets.contains_key(tree));
+        assert_eq!(targets.len(), 2);
+    }
+
+    #[test]
+    fn test_get_all_remote_refs_groups_by_remote() {
+        let repo = TestRepo::new();
+        repo.write_file("test.txt", "content\n");
+        let sha = repo.commit("initial");
+
+        // fake remote-tracking refs for two remotes, one with a slash in its name
+        for (remote, branch) in [("origin", "main"), ("fork/me", "main"), ("fork/me", "wip")] {
+            Command::new("git")
+                .args([
+                    "update-ref",
+                    &format!("refs/remotes/{remote}/{branch}"),
+                    &sha,
+                ])
+                .current_dir(&repo.path)
+                .output()
+                .unwrap();
+        }
+
+        let remotes = vec![
+            "origin".to_string(),
+            "fork".to_string(),
+            "fork/me".to_string(),
+        ];
+        let refs = get_all_remote_refs(&repo.path, &remotes);
+
+        assert_eq!(refs.len(), 2);
+        assert_eq!(refs["origin"].keys().collect::<Vec<_>>(), vec!["main"]);
+        assert_eq!(
+            refs["fork/me"].keys().collect::<HashSet<_>>(),
+            HashSet::from([&"main".to_string(), &"wip".to_string()])
+        );
+    }
+
+    #[test]
+    fn reflogs_are_listed_oldest_first() {
+        let repo = TestRepo::new();
+        repo.write_file("test.txt", "one\n");
+        let first = repo.commit("first");
+        repo.write_file("test.txt", "two\n");
+        let second = repo.commit("second");
+
+        for (sha, message) in [(&first, "update by push"), (&second, "fetch: fast-forward")] {
+            Command::new("git")
+                .args(["update-ref", "-m", message, "refs/remotes/origin/main", sha])
+                .current_dir(&repo.path)
+                .output()
+                .unwrap();
+        }
+
+        let reflogs = get_reflogs(
+            &repo.path,
+            &["refs/remotes/origin/main", "refs/remotes/origin/gone"],
+        );
+
+        assert_eq!(reflogs.len(), 1);
+        let entries = &reflogs["refs/remotes/origin/main"];
+        let summary: Vec<(&str, &str)> = entries
+            .iter()
+            .map(|e| (e.sha.as_str(), e.message.as_str()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (first.as_str(), "update by push"),
+                (second.as_str(), "fetch: fast-forward")
+            ]
+        );
+        assert!(entries[0].timestamp > 0);
+    }
+
+    #[test]
+    fn get_config_values_matches_camel_case_keys() {
+        let repo = TestRepo::new();
+        for (key, value) in [
+            ("party.rewritePolicy", "clawback"),
+            ("party.ignore", "vendor/"),
+        ] {
+            Command::new("git")
+                .args(["config", key, value])
+                .current_dir(&repo.path)
+                .output()
+                .unwrap();
+        }
+
+        let values = get_config_values(
+            &repo.path,
+            &[
+                "party.rewritePolicy",
+                "party.ignore",
+                "party.ignoreDefaults",
+            ],
+        );
+
+        assert!(values.contains(&("party.rewritepolicy".into(), "clawback".into())));
+        assert!(values.contains(&("party.ignore".into(), "vendor/".into())));
+    }
+
+    #[test]
+    fn lines_changed_ignores_binary_files() {
+        let repo = TestRepo::new();
+        repo.write_file("test.txt", "line1\n");
+        fs::write(repo.path.join("image.bin"), [0u8, 1, 2, 3]).unwrap();
+        let sha = repo.commit("text and binary");
+
+        assert_eq!(lines_changed(&repo, &sha), Some(1));
+    }
+
+    #[test]
+    fn scan_commits_excludes_and_tracks_source() {
+        let repo = TestRepo::new();
+        repo.write_file("a.txt", "a\n");
+        let base = repo.commit("base");
+
+        repo.write_file("b.txt", "b\n");
+        let first = repo.commit("first");
+        repo.write_file("c.txt", "c\n");
+        let second = repo.commit("second");
+        let branch = repo.current_branch_name();
+
+        let refname = format!("refs/heads/{branch}");
+        let commits = scan_commits(&repo.path, &[&refname], &[&base]);
+
+        let shas: Vec<_> = commits.iter().map(|c| c.commit.sha.as_str()).collect();
+        assert_eq!(shas, vec![second.as_str(), first.as_str()]);
+        assert!(commits.iter().all(|c| c.source == refname));
+    }
+
+    #[test]
+    fn scan_commits_patch_ids_match_git_show() {
+        let repo = TestRepo::new();
+        repo.write_file("test.txt", "line1\r\nline2\r\n");
+        let first = repo.commit("crlf file");
+        repo.write_file("test.txt", "line1\r\nline2 changed\r\nline3\r\n");
+        let second = repo.commit("modify crlf file");
+
+        let commits = scan_commits(&repo.path, &[&second], &[]);
+
+        for sha in [&first, &second] {
+            let info = commits.iter().find(|c| &c.commit.sha == sha).unwrap();
+            assert_eq!(
+                info.patch_id.as_deref(),
+                Some(show_patch_id(&repo, sha).as_str())
+            );
+        }
+    }
+
+    #[test]
+    fn scan_commits_empty_commit_has_no_patch_id() {
+        let repo = TestRepo::new();
+        repo.write_file("test.txt", "content\n");
+        repo.commit("initial");
+
+        Command::new("git")
+            .args(["commit", "--allow-empty", "-m", "empty"])
+            .current_dir(&repo.path)
+            .output()
+            .unwrap();
+
+        let commits = scan_commits(&repo.path, &["HEAD"], &["HEAD~1"]);
+        assert_eq!(commits.len(), 1);
+        assert_eq!(commits[0].patch_id, None);
+        assert_eq!(commits[0].commit.lines_changed(), 0);
+    }
+
+    #[test]
+    fn get_remote_tags_reads_tag_objects() {
+        let repo = TestRepo::new();
+        repo.write_file("a.txt", "a\n");
+        let commit = repo.commit("initial");
+        for args in [
+            &["tag", "-a", "v1.0.0", "-m", "release"][..],
+            &["tag", "light"][..],
+        ] {
+            Command::new("git")
+                .args(args)
+                .current_dir(&repo.path)
+                .output()
+                .unwrap();
+        }
+        let tag_object = Command::new("git")
+            .args(["rev-parse", "v1.0.0"])
+            .current_dir(&repo.path)
+            .output()
+            .unwrap();
+        let tag_object = String::from_utf8_lossy(&tag_object.stdout)
+            .trim()
+            .to_string();
+
+        // the repo itself stands in for the remote
+        let tags = get_remote_tags(&repo.path, ".", &["v1.0.0", "light", "missing"]).unwrap();
+        assert_eq!(
+            tags,
+            HashMap::from([
+                ("v1.0.0".to_string(), tag_object),
+                ("light".to_string(), commit),
+            ])
+        );
+
+        assert_eq!(
+            get_remote_tags(&repo.path, "no-such-remote", &["light"]),
+            None
+        );
+    }
+
+    #[test]
+    fn scan_commits_reads_metadata() {
+        let repo = TestRepo::new();
+        repo.write_file("keep.txt", "a\nb\nc\n");
+        repo.commit("initial");
+
+        repo.write_file("keep.txt", "a\nB\n");
+        repo.write_file("new.txt", "x\n");
+        fs::write(repo.path.join("image.bin"), [0u8, 1, 2, 3]).unwrap();
+        Command::new("git")
+            .args(["add", "."])
+            .current_dir(&repo.path)
+            .output()
+            .unwrap();
+        Command::new("git")
+            .args([
+                "commit",
+                "-m",
+                "Fix the thing\n\nIt was broken.\nNow it isn't.\n\nCo-authored-by: Pal <pal@example.com>\nRefs: #12",
+            ])
+            .env("GIT_AUTHOR_NAME", "Ada")
+            .env("GIT_AUTHOR_EMAIL", "ada@example.com")
+            .env("GIT_AUTHOR_DATE", "@1700000000 +0000")
+            .env("GIT_COMMITTER_NAME", "Release Bot")
+            .env("GIT_COMMITTER_EMAIL", "release-bot@example.com")
+            .env("GIT_COMMITTER_DATE", "@1700000500 +0000")
+            .current_dir(&repo.path)
+            .output()
+            .unwrap();
+
+        let commits = scan_commits(&repo.path, &["HEAD"], &["HEAD~1"]);
+        assert_eq!(commits.len(), 1);
+        let commit = &commits[0].commit;
+
+        assert_eq!(commit.author_name, "Ada");
+        assert_eq!(commit.author_email, "ada@example.com");
+        assert_eq!(commit.committer_name, "Release Bot");
+        assert_eq!(commit.committer_email, "release-bot@example.com");
+        assert_eq!(commit.authored_at, 1_700_000_000);
+        assert_eq!(commit.committed_at, 1_700_000_500);
+        assert_eq!(commit.subject, "Fix the thing");
+        assert!(commit.body.starts_with("It was broken.\nNow it isn't."));
+        assert_eq!(
+            commit.trailers,
+            vec![
+                (
+                    "Co-authored-by".to_string(),
+                    "Pal <pal@example.com>".to_string()
+                ),
+                ("Refs".to_string(), "#12".to_string()),
+            ]
+        );
+
+        let mut files = commit.files.clone();
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        assert_eq!(
+            files,
+            vec![
+                FileChange {
+                    path: "image.bin".to_string(),
+                    ..Default::default()
+                },
+                FileChange {
+                    path: "keep.txt".to_string(),
+                    insertions: 1,
+                    deletions: 2,
+                },
+                FileChange {
+                    path: "new.txt".to_string(),
+                    insertions: 1,
+                    deletions: 0,
+                },
+            ]
+        );
+        assert_eq!(commit.lines_changed(), 4);
+    }
 }
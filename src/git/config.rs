@@ -0,0 +1,104 @@
+//! Everything party reads from a repo's git config, fetched in a single git call.
+
+use std::path::Path;
+
+use crate::git::{
+    self,
+    bots::{BOT_KEY, Bots},
+    identity::{ALIAS_EMAIL_KEY, Identity},
+    ignore::{IGNORE_DEFAULTS_KEY, IGNORE_KEY, IgnoreRules},
+    rewrite::{REWRITE_POLICY_KEY, RewritePolicy},
+};
+
+const KEYS: &[&str] = &[
+    "user.email",
+    ALIAS_EMAIL_KEY,
+    BOT_KEY,
+    REWRITE_POLICY_KEY,
+    IGNORE_KEY,
+    IGNORE_DEFAULTS_KEY,
+];
+
+/// the player's settings for a repo (local config, falling back on global)
+#[derive(Debug, Clone, Default)]
+pub struct PartyConfig {
+    /// who the player is: their git email plus any aliases
+    pub identity: Identity,
+    pub bots: Bots,
+    pub rewrite_policy: RewritePolicy,
+    pub ignore_rules: IgnoreRules,
+}
+
+impl PartyConfig {
+    pub fn load(repo_path: &Path) -> Self {
+        Self::from_values(&git::commands::get_config_values(repo_path, KEYS))
+    }
+
+    /// like `load`, with `emails` counting as the player's as well
+    pub fn load_with_emails<'a>(
+        repo_path: &Path,
+        emails: impl IntoIterator<Item = &'a str>,
+    ) -> Self {
+        let mut values = git::commands::get_config_values(repo_path, KEYS);
+        values.extend(
+            emails
+                .into_iter()
+                .map(|e| (ALIAS_EMAIL_KEY.to_string(), e.to_string())),
+        );
+        Self::from_values(&values)
+    }
+
+    /// from (lowercased key, value) pairs, as returned by `get_config_values`
+    fn from_values(config: &[(String, String)]) -> Self {
+        let values = |key: &str| {
+            let key = key.to_lowercase();
+            config
+                .iter()
+                .filter(move |(k, _)| *k == key)
+                .map(|(_, v)| v.as_str())
+        };
+
+        Self {
+            identity: Identity::new(values("user.email").chain(values(ALIAS_EMAIL_KEY))),
+            bots: Bots::from_config(values(BOT_KEY)),
+            rewrite_policy: RewritePolicy::from_config(values(REWRITE_POLICY_KEY)),
+            ignore_rules: IgnoreRules::from_config(values(IGNORE_KEY), values(IGNORE_DEFAULTS_KEY)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::Commit;
+
+    fn pairs(values: &[(&str, &str)]) -> Vec<(String, String)> {
+        values
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn reads_every_setting_from_one_set_of_values() {
+        let config = PartyConfig::from_values(&pairs(&[
+            ("user.email", "me@example.com"),
+            ("party.email", "me@work.example"),
+            ("party.bot", "ci@example.com"),
+            ("party.rewritepolicy", "clawback"),
+            ("party.ignore", "vendor/"),
+            ("party.ignoredefaults", "false"),
+        ]));
+
+        assert!(config.identity.is_mine("me@work.example"));
+        assert!(!config.identity.is_mine("you@example.com"));
+        let ci = Commit {
+            author_email: "ci@example.com".to_string(),
+            ..Default::default()
+        };
+        assert!(config.bots.is_automated(&ci));
+        assert_eq!(config.rewrite_policy, RewritePolicy::ClawBack);
+        assert!(config.ignore_rules.is_ignored("vendor/lib.rs"));
+        assert!(!config.ignore_rules.is_ignored("Cargo.lock"));
+    }
+}
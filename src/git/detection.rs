@@ -1,141 +1,445 @@
-use anyhow::Result;
+use std::collections::{HashMap, HashSet};
+
+use anyhow::{Result, bail};
 
 use crate::{
-    git::{self, Commit, Push},
+    git::{
+        self, Commit, Push, PushedBranch, config::PartyConfig, identity::Identity,
+        rewrite::RewritePolicy,
+    },
     storage::{BranchRefsStore, PatchIdStore},
 };
 
+/// every configured remote, paired with the canonical id of the repo it points to.
+/// refs and patch-ids are stored under that id, so different ways of writing the
+/// same url don't look like different repos
+pub fn get_remote_ids(repo_path: &std::path::Path) -> Vec<(String, String)> {
+    git::commands::get_remote_urls(repo_path)
+        .into_iter()
+        .map(|(remote, url)| (remote, git::remote::repo_id(&url)))
+        .collect()
+}
+
+/// where every remote branch of every remote points, e.g. ("origin", "main", "abc123"),
+/// in a stable order so that two calls can be compared to see whether anything moved
+pub fn remote_ref_positions(repo_path: &std::path::Path) -> Vec<(String, String, String)> {
+    let remotes: Vec<String> = get_remote_ids(repo_path)
+        .into_iter()
+        .map(|(r, _)| r)
+        .collect();
+    let mut positions: Vec<(String, String, String)> =
+        git::commands::get_all_remote_refs(repo_path, &remotes)
+            .into_iter()
+            .flat_map(|(remote, refs)| {
+                refs.into_iter()
+                    .map(move |(branch, sha)| (remote.clone(), branch, sha))
+            })
+            .collect();
+    positions.sort();
+    positions
+}
+
 /// Snapshot current remote refs so future pushes are calculated correctly.
 /// Called during init to avoid crediting pre-existing commits.
 pub fn snapshot_refs(repo_path: &std::path::Path, branch_refs: &BranchRefsStore) -> Result<()> {
-    // HACK: should we report an error here somehow?
-    let Some(remote_url) = git::commands::get_remote_url(repo_path) else {
-        return Ok(());
-    };
+    let remote_urls = get_remote_ids(repo_path);
+    let remotes: Vec<String> = remote_urls.iter().map(|(r, _)| r.clone()).collect();
+    let current_refs = git::commands::get_all_remote_refs(repo_path, &remotes);
 
-    let current_refs = git::commands::get_all_remote_refs(repo_path);
-    if current_refs.is_empty() {
-        return Ok(());
+    for (remote, remote_url) in &remote_urls {
+        branch_refs.mark_known(remote_url)?;
+        for (branch, sha) in current_refs.get(remote).into_iter().flatten() {
+            branch_refs.update_ref(remote_url, branch, sha)?;
+        }
     }
 
-    for (branch, sha) in current_refs {
-        branch_refs.update_ref(&remote_url, &branch, &sha)?;
+    Ok(())
+}
+
+/// The commits on the current branch that none of the repo's remotes have yet, as the
+/// push that would publish them. Commits already credited, by other authors or by bots
+/// are left out as they would be by the hook. Nothing is recorded.
+pub fn get_unpushed_commits(repo_path: &std::path::Path, patch_ids: &PatchIdStore) -> Result<Push> {
+    let remote_urls = get_remote_ids(repo_path);
+    let Some((_, push_url)) = remote_urls.first() else {
+        bail!("this repo has no remotes to push to");
+    };
+    let Some(branch) = git::commands::get_current_branch(repo_path) else {
+        bail!("no branch is checked out");
+    };
+
+    let remotes: Vec<String> = remote_urls.iter().map(|(r, _)| r.clone()).collect();
+    let (remote_refs, _) = git::commands::get_all_refs(repo_path, &remotes);
+    let exclude: Vec<String> = remote_refs
+        .iter()
+        .flat_map(|(remote, refs)| {
+            refs.keys()
+                .map(move |branch| format!("refs/remotes/{remote}/{branch}"))
+        })
+        .collect();
+    let exclude: Vec<&str> = exclude.iter().map(|s| s.as_str()).collect();
+    let include = format!("refs/heads/{branch}");
+    let commits = git::commands::scan_commits(repo_path, &[include.as_str()], &exclude);
+
+    let PartyConfig {
+        identity,
+        bots,
+        ignore_rules,
+        ..
+    } = PartyConfig::load(repo_path);
+
+    let mut mine = Vec::new();
+    let mut skipped_commits = 0;
+    let mut automated_commits = 0;
+    for info in commits {
+        let Some(patch_id) = info.patch_id else {
+            continue;
+        };
+        let mut credited = false;
+        for (_, url) in &remote_urls {
+            credited |= patch_ids.contains(url, &patch_id)?;
+        }
+        if credited {
+            continue;
+        }
+
+        let mut commit = info.commit;
+        commit.apply_ignore_rules(&ignore_rules);
+        if bots.is_automated(&commit) {
+            automated_commits += 1;
+        } else if !identity.is_mine(commit.author_email()) {
+            skipped_commits += 1;
+        } else {
+            mine.push(commit);
+        }
     }
 
-    Ok(())
+    let branches = vec![PushedBranch::new(branch, mine)];
+    Ok(
+        Push::from_branches(branches, push_url.as_str(), skipped_commits)
+            .with_automated(automated_commits),
+    )
 }
 
-/// Detect commits from recent push. Loads/saves refs and patch-id state as side effects.
-pub fn get_pushed_commits(branch_refs: &BranchRefsStore, patch_ids: &PatchIdStore) -> Option<Push> {
-    let repo_path = std::env::current_dir().expect("could not get current directory");
+/// Detect commits from a recent push to the repo at `repo_path`. Loads/saves refs and
+/// patch-id state as side effects.
+///
+/// Every configured remote is checked, so pushes to forks or secondary remotes are
+/// detected too. Refs and patch-ids are stored under the repo id of the remote that
+/// moved, but a commit that has already been credited on any of this repo's remotes is
+/// not credited again.
+pub fn get_pushed_commits(
+    repo_path: &std::path::Path,
+    branch_refs: &BranchRefsStore,
+    patch_ids: &PatchIdStore,
+) -> Option<Push> {
+    let remote_urls = get_remote_ids(repo_path);
+    if remote_urls.is_empty() {
+        return None;
+    }
+    crate::debug_log!("hook: remotes = {:?}", remote_urls);
 
-    let remote_url = git::commands::get_remote_url(&repo_path)?;
-    crate::debug_log!("hook: remote_url = {}", remote_url);
+    // a repo party has never seen (e.g. with a global install) gets seeded rather than
+    // having its whole history credited
+    let mut known = false;
+    for (_, url) in &remote_urls {
+        known |= branch_refs.is_known(url).ok()?;
+    }
+    if !known {
+        crate::debug_log!("hook: first sight of this repo, snapshotting refs");
+        snapshot_refs(repo_path, branch_refs).ok()?;
+        return None;
+    }
 
-    let current_refs = git::commands::get_all_remote_refs(&repo_path);
+    let remotes: Vec<String> = remote_urls.iter().map(|(r, _)| r.clone()).collect();
+    // remote branches and local branches, with their shas
+    let (current_refs, local_refs) = git::commands::get_all_refs(repo_path, &remotes);
     crate::debug_log!("hook: current_refs = {:?}", current_refs);
 
-    // collect commits from pushed branches
-    let mut commits = Vec::new();
-    let mut first_time_branches = Vec::new();
-    let mut pushed_branches = Vec::new();
+    // full ref name of each remote branch that moved due to a push -> (remote, branch)
+    let mut pushed_refs: HashMap<String, (&str, &str)> = HashMap::new();
+    // the order in which they were found, so the push is attributed deterministically
+    let mut pushed_order = Vec::new();
 
-    // get all local branch refs and their shas
-    let local_refs = git::commands::get_all_local_refs(&repo_path);
+    // everything the new commits must NOT be reachable from: the previous position of
+    // each pushed ref, plus every remote branch that wasn't pushed (which handles
+    // fetched commits that end up in the push range due to rebasing)
+    let mut exclude = Vec::new();
 
-    for (branch, new_sha) in &current_refs {
-        let local_sha = local_refs.get(branch);
-        if local_sha != Some(new_sha) {
-            continue; // fetch, not push
-        }
-        let old_sha = branch_refs.get_ref(&remote_url, branch).ok()?;
-        if old_sha.as_ref() == Some(new_sha) {
-            continue; // no change
-        }
+    // where each pushed ref was before the push, to spot commits thrown away by force pushes
+    let mut previous = Vec::new();
 
-        crate::debug_log!(
-            "hook: branch {} pushed ({:?} -> {})",
-            branch,
-            old_sha,
-            new_sha
-        );
-        pushed_branches.push(branch.clone());
-
-        match old_sha {
-            Some(old) => {
-                // update: get exact range (fast)
-                commits.extend(git::commands::list_commits_in_range(
-                    &repo_path, &old, new_sha,
-                ));
+    for (remote, remote_url) in &remote_urls {
+        for (branch, new_sha) in current_refs.get(remote).into_iter().flatten() {
+            let refname = format!("refs/remotes/{remote}/{branch}");
+
+            let local_sha = local_refs.get(branch);
+            if local_sha != Some(new_sha) {
+                exclude.push(refname);
+                continue; // fetch, not push
+            }
+            let old_sha = branch_refs.get_ref(remote_url, branch).ok()?;
+            if old_sha.as_ref() == Some(new_sha) {
+                exclude.push(refname);
+                continue; // no change
             }
-            None => {
-                // first-time push: need to process with other first-time branches
-                first_time_branches.push(branch.as_str());
+
+            crate::debug_log!(
+                "hook: branch {}/{} pushed ({:?} -> {})",
+                remote,
+                branch,
+                old_sha,
+                new_sha
+            );
+
+            // first-time pushes have no old sha; they're bounded by the other remote branches
+            if let Some(old_sha) = &old_sha {
+                previous.push((refname.clone(), old_sha.clone()));
             }
+            exclude.extend(old_sha);
+            pushed_order.push(refname.clone());
+            pushed_refs.insert(refname, (remote.as_str(), branch.as_str()));
         }
     }
 
-    // first-time pushes processed together to handle shared history
-    if !first_time_branches.is_empty() {
-        commits.extend(git::commands::list_unique_commits(
-            &repo_path,
-            &first_time_branches,
-        ));
-    }
+    // one scan for every pushed branch on every remote. a commit pushed to
+    // several remotes at once is only listed once
+    let include: Vec<&str> = pushed_order.iter().map(|s| s.as_str()).collect();
+    let exclude: Vec<&str> = exclude.iter().map(|s| s.as_str()).collect();
+    let commits = git::commands::scan_commits(repo_path, &include, &exclude);
 
-    if commits.is_empty() {
-        for (branch, sha) in current_refs {
-            branch_refs.update_ref(&remote_url, &branch, &sha).ok()?;
+    let update_all_refs = || -> Option<()> {
+        for (remote, remote_url) in &remote_urls {
+            branch_refs.mark_known(remote_url).ok()?;
+            for (branch, sha) in current_refs.get(remote).into_iter().flatten() {
+                branch_refs.update_ref(remote_url, branch, sha).ok()?;
+            }
         }
+        Some(())
+    };
+
+    // nothing new, and nothing that could have been thrown away
+    if commits.is_empty() && previous.is_empty() {
+        update_all_refs()?;
         return None;
     }
 
     crate::debug_log!("hook: {} commits to check", commits.len());
 
-    let now = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap()
-        .as_secs();
+    let url_of: HashMap<&str, &str> = remote_urls
+        .iter()
+        .map(|(r, u)| (r.as_str(), u.as_str()))
+        .collect();
+    let all_urls: Vec<&str> = remote_urls.iter().map(|(_, u)| u.as_str()).collect();
+    let is_credited = |patch_id: &str| -> Option<bool> {
+        let mut seen = false;
+        for url in &all_urls {
+            seen |= patch_ids.contains(url, patch_id).ok()?;
+        }
+        Some(seen)
+    };
+
+    // new commits grouped by the branch they were pushed to. a branch pushed to
+    // several remotes shows up once
+    let mut branch_commits: Vec<(&str, Vec<Commit>)> = Vec::new();
+    for refname in &pushed_order {
+        let (_, branch) = pushed_refs[refname];
+        if !branch_commits.iter().any(|(b, _)| *b == branch) {
+            branch_commits.push((branch, Vec::new()));
+        }
+    }
+    let mut push_remote_url = None;
+    let mut skipped_commits = 0;
+    let mut automated_commits = 0;
 
-    let mut new_commits = Vec::new();
+    // only commits authored by the player (their git email or one of their aliases) count
+    let PartyConfig {
+        identity,
+        bots,
+        rewrite_policy,
+        ignore_rules,
+    } = PartyConfig::load(repo_path);
 
-    // build list of branches to exclude from filtering (all branches we're pushing)
-    let exclude_branches: Vec<&str> = pushed_branches.iter().map(|s| s.as_str()).collect();
+    let discarded = if rewrite_policy == RewritePolicy::Ignore {
+        Vec::new()
+    } else {
+        let current_refnames: Vec<String> = remote_urls
+            .iter()
+            .flat_map(|(remote, _)| {
+                current_refs
+                    .get(remote)
+                    .into_iter()
+                    .flatten()
+                    .map(move |(branch, _)| format!("refs/remotes/{remote}/{branch}"))
+            })
+            .collect();
+        let current_shas: Vec<&str> = current_refs
+            .values()
+            .flat_map(|refs| refs.values().map(|s| s.as_str()))
+            .collect();
+        let kept: HashSet<&str> = commits
+            .iter()
+            .filter_map(|c| c.patch_id.as_deref())
+            .collect();
 
-    for sha in commits {
-        // skip commits reachable from other remote branches (handles stale refs after jj fetch)
-        if !exclude_branches.is_empty()
-            && git::commands::is_reachable_from_other_remote(&repo_path, &sha, &exclude_branches)
-        {
-            crate::debug_log!("hook: skipping {} (reachable from other remote)", sha);
+        find_discarded_commits(
+            repo_path,
+            &previous,
+            &current_refnames,
+            &current_shas,
+            &kept,
+            is_credited,
+            &identity,
+        )?
+    };
+
+    if commits.is_empty() && discarded.is_empty() {
+        update_all_refs()?;
+        return None;
+    }
+
+    for info in commits {
+        let Some(patch_id) = info.patch_id else {
+            continue;
+        };
+        let Some((remote, branch)) = pushed_refs.get(&info.source) else {
+            continue;
+        };
+
+        // a commit counts once per repo, no matter how many of its remotes it lands on
+        if is_credited(&patch_id)? {
             continue;
         }
 
-        if let Some(patch_id) = git::commands::get_patch_id(&repo_path, &sha)
-            && !patch_ids.contains(&remote_url, &patch_id).ok()?
-        {
-            let lines_changed = git::commands::get_lines_changed(&repo_path, &sha).unwrap_or(0);
-            crate::debug_log!(
-                "hook: new commit {} ({}) - {} lines",
-                sha,
-                patch_id,
-                lines_changed
-            );
-            patch_ids.record(&remote_url, &patch_id).ok()?;
-            new_commits.push(Commit::new(sha, lines_changed, now));
+        let remote_url = url_of[remote];
+        let mut commit = info.commit;
+        commit.apply_ignore_rules(&ignore_rules);
+        crate::debug_log!(
+            "hook: new commit {} ({}) on {}/{} by {} - {} lines - {}",
+            commit.sha(),
+            patch_id,
+            remote,
+            branch,
+            commit.author_email(),
+            commit.lines_changed(),
+            commit.subject()
+        );
+        // recorded even when skipped, so that someone else's work never counts later
+        patch_ids.record(remote_url, &patch_id).ok()?;
+
+        if bots.is_automated(&commit) {
+            crate::debug_log!("hook: skipping {} (automated)", commit.sha());
+            automated_commits += 1;
+            continue;
+        }
+        if !identity.is_mine(commit.author_email()) {
+            crate::debug_log!("hook: skipping {} (not authored by me)", commit.sha());
+            skipped_commits += 1;
+            continue;
+        }
+        push_remote_url.get_or_insert(remote_url);
+
+        if let Some((_, commits)) = branch_commits.iter_mut().find(|(b, _)| b == branch) {
+            commits.push(commit);
         }
     }
 
     // update stored refs
-    for (branch, sha) in current_refs {
-        branch_refs.update_ref(&remote_url, &branch, &sha).ok()?;
+    update_all_refs()?;
+
+    let branches: Vec<PushedBranch> = branch_commits
+        .into_iter()
+        .map(|(name, commits)| {
+            let discarded = discarded
+                .iter()
+                .filter(|(refname, _)| pushed_refs[refname].1 == name)
+                .map(|(_, commit)| commit.clone())
+                .collect();
+            PushedBranch::new(name, commits).with_discarded(discarded)
+        })
+        .collect();
+    crate::debug_log!(
+        "hook: {} new commits on {} branches ({} skipped, {} automated, {} discarded)",
+        branches.iter().map(|b| b.commits().len()).sum::<usize>(),
+        branches.len(),
+        skipped_commits,
+        automated_commits,
+        discarded.len()
+    );
+
+    // attribute the push to the remote that received new commits,
+    // falling back to the first remote that moved
+    let remote_url = push_remote_url
+        .or_else(|| {
+            let first = pushed_order.first()?;
+            Some(url_of[pushed_refs[first].0])
+        })
+        .unwrap_or_default();
+
+    Some(
+        Push::from_branches(branches, remote_url, skipped_commits)
+            .with_automated(automated_commits)
+            .with_rewrite_policy(rewrite_policy),
+    )
+}
+
+/// my previously credited commits that a push threw away: reachable from where a
+/// pushed ref used to be, but from no remote branch now, and not pushed again under a
+/// new sha (as a rebase would). returned with the ref that used to hold them
+fn find_discarded_commits(
+    repo_path: &std::path::Path,
+    previous: &[(String, String)],
+    current_refnames: &[String],
+    current_shas: &[&str],
+    kept: &HashSet<&str>,
+    is_credited: impl Fn(&str) -> Option<bool>,
+    identity: &Identity,
+) -> Option<Vec<(String, Commit)>> {
+    if previous.is_empty() {
+        return Some(Vec::new());
+    }
+
+    // a plain fast-forward leaves every old position behind a current branch. only old
+    // positions that are no longer reachable from anything can have lost commits
+    let mut tips: Vec<&str> = current_shas.to_vec();
+    tips.extend(previous.iter().map(|(_, old)| old.as_str()));
+    tips.sort_unstable();
+    tips.dedup();
+    let independent = git::commands::get_independent_commits(repo_path, &tips)?;
+
+    let abandoned: Vec<&(String, String)> = previous
+        .iter()
+        .filter(|(_, old)| independent.contains(old) && !current_shas.contains(&old.as_str()))
+        .collect();
+    if abandoned.is_empty() {
+        return Some(Vec::new());
     }
+    crate::debug_log!("hook: force push left behind {:?}", abandoned);
 
-    crate::debug_log!("hook: {} new commits", new_commits.len());
+    let include: Vec<&str> = abandoned.iter().map(|(_, old)| old.as_str()).collect();
+    let exclude: Vec<&str> = current_refnames.iter().map(|s| s.as_str()).collect();
+
+    let mut discarded = Vec::new();
+    for info in git::commands::scan_commits(repo_path, &include, &exclude) {
+        let Some(patch_id) = info.patch_id else {
+            continue;
+        };
+        if kept.contains(patch_id.as_str()) || !is_credited(&patch_id)? {
+            continue;
+        }
+        if !identity.is_mine(info.commit.author_email()) {
+            continue;
+        }
+        let Some((refname, _)) = abandoned.iter().find(|(_, old)| *old == info.source) else {
+            continue;
+        };
+        crate::debug_log!(
+            "hook: discarded commit {} ({})",
+            info.commit.sha(),
+            patch_id
+        );
+        discarded.push((refname.clone(), info.commit));
+    }
 
-    Some(Push::from_parts(
-        new_commits,
-        remote_url,
-        pushed_branches.into_iter().next().unwrap_or_default(),
-    ))
+    Some(discarded)
 }
@@ -0,0 +1,51 @@
+//! Deciding which commits in a push were written by the player.
+
+/// git config key for extra emails that count as "me" (can be set multiple times),
+/// e.g. `git config --global --add party.email me@work.example`
+pub const ALIAS_EMAIL_KEY: &str = "party.email";
+
+/// the set of author emails that belong to the player
+#[derive(Debug, Clone, Default)]
+pub struct Identity {
+    emails: Vec<String>,
+}
+
+impl Identity {
+    pub fn new(emails: impl IntoIterator<Item = impl AsRef<str>>) -> Self {
+        Self {
+            emails: emails
+                .into_iter()
+                .map(|e| e.as_ref().trim().to_lowercase())
+                .filter(|e| !e.is_empty())
+                .collect(),
+        }
+    }
+
+    /// whether a commit with this author email should be credited.
+    /// with no known emails at all we can't tell, so everything is credited
+    pub fn is_mine(&self, author_email: &str) -> bool {
+        self.emails.is_empty() || self.emails.contains(&author_email.trim().to_lowercase())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_any_email_case_insensitively() {
+        let identity = Identity::new(["me@example.com", "Me@Work.example"]);
+
+        assert!(identity.is_mine("me@example.com"));
+        assert!(identity.is_mine("ME@example.com"));
+        assert!(identity.is_mine("me@work.example"));
+        assert!(!identity.is_mine("teammate@example.com"));
+    }
+
+    #[test]
+    fn empty_identity_matches_everyone() {
+        let identity = Identity::new(["", "  "]);
+
+        assert!(identity.is_mine("anyone@example.com"));
+    }
+}
@@ -0,0 +1,310 @@
+//! Paths whose changes don't count towards a commit's line counts, like lockfiles and
+//! minified bundles. A commit that only touches ignored paths counts as zero lines.
+//!
+//! Patterns are globs: `*` matches within a path component, `**` across components,
+//! and `?` any single character. As in `.gitignore`, a pattern without a `/` matches a
+//! file or directory name anywhere in the repo (`Cargo.lock`, `vendor/`), while one
+//! with a `/` in the middle or at the start matches from the repo root
+//! (`/generated`, `docs/**/*.html`). A trailing `/` only matches directories.
+
+/// git config key for extra ignore patterns (can be set multiple times, globally or
+/// per repo), e.g. `git config --global --add party.ignore 'vendor/'`
+pub const IGNORE_KEY: &str = "party.ignore";
+
+/// git config key to turn off the built-in patterns, e.g.
+/// `git config party.ignoreDefaults false`
+pub const IGNORE_DEFAULTS_KEY: &str = "party.ignoreDefaults";
+
+/// generated files that nobody writes by hand. binary files are left out of line
+/// counts by git already, these catch the ones it diffs as text anyway
+pub const DEFAULT_PATTERNS: &[&str] = &[
+    // lockfiles
+    "Cargo.lock",
+    "package-lock.json",
+    "npm-shrinkwrap.json",
+    "yarn.lock",
+    "pnpm-lock.yaml",
+    "bun.lock",
+    "bun.lockb",
+    "Gemfile.lock",
+    "poetry.lock",
+    "uv.lock",
+    "Pipfile.lock",
+    "composer.lock",
+    "go.sum",
+    "flake.lock",
+    "mix.lock",
+    "pubspec.lock",
+    "Podfile.lock",
+    // minified assets and source maps
+    "*.min.js",
+    "*.min.mjs",
+    "*.min.css",
+    "*.js.map",
+    "*.css.map",
+    // binary files
+    "*.png",
+    "*.jpg",
+    "*.jpeg",
+    "*.gif",
+    "*.ico",
+    "*.webp",
+    "*.pdf",
+    "*.zip",
+    "*.gz",
+    "*.wasm",
+    "*.woff",
+    "*.woff2",
+    "*.ttf",
+];
+
+#[derive(Debug, Clone, Default)]
+pub struct IgnoreRules {
+    patterns: Vec<String>,
+}
+
+impl IgnoreRules {
+    pub fn new(patterns: impl IntoIterator<Item = impl AsRef<str>>) -> Self {
+        Self {
+            patterns: patterns
+                .into_iter()
+                .map(|p| p.as_ref().trim().to_string())
+                .filter(|p| !p.is_empty())
+                .collect(),
+        }
+    }
+
+    /// the rules from git config values: the defaults (unless the last
+    /// `ignoreDefaults` value turns them off) plus every configured pattern
+    pub fn from_config<'a>(
+        patterns: impl IntoIterator<Item = &'a str>,
+        use_defaults: impl IntoIterator<Item = &'a str>,
+    ) -> Self {
+        let use_defaults = use_defaults
+            .into_iter()
+            .filter_map(parse_bool)
+            .last()
+            .unwrap_or(true);
+        let defaults = DEFAULT_PATTERNS.iter().filter(|_| use_defaults).copied();
+        Self::new(defaults.chain(patterns))
+    }
+
+    /// whether changes to this path (as printed by `git log --numstat`) are ignored
+    pub fn is_ignored(&self, path: &str) -> bool {
+        let path = rename_target(path);
+        self.patterns.iter().any(|p| matches_path(p, &path))
+    }
+}
+
+/// git's boolean config spellings
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.trim().to_lowercase().as_str() {
+        "true" | "yes" | "on" | "1" => Some(true),
+        "false" | "no" | "off" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+/// numstat shows renames as `old => new` or `dir/{old => new}/file`; we want the new path
+fn rename_target(path: &str) -> String {
+    if let (Some(open), Some(close)) = (path.find('{'), path.find('}'))
+        && let Some((_, new)) = path[open + 1..close].split_once(" => ")
+    {
+        let joined = format!("{}{new}{}", &path[..open], &path[close + 1..]);
+        return joined.replace("//", "/");
+    }
+    match path.split_once(" => ") {
+        Some((_, new)) => new.to_string(),
+        None => path.to_string(),
+    }
+}
+
+fn matches_path(pattern: &str, path: &str) -> bool {
+    let components: Vec<&str> = path.split('/').collect();
+
+    // directory-only patterns can't match the file itself, just the directories above it
+    let (pattern, candidates) = match pattern.strip_suffix('/') {
+        Some(dir) => (dir, components.len().saturating_sub(1)),
+        None => (pattern, components.len()),
+    };
+
+    if let Some(anchored) = pattern
+        .strip_prefix('/')
+        .or_else(|| pattern.contains('/').then_some(pattern))
+    {
+        // anchored at the root: the path itself or any directory above it
+        let segments: Vec<&str> = anchored.split('/').collect();
+        return (1..=candidates).any(|n| matches_segments(&segments, &components[..n]));
+    }
+
+    // a bare name matches any file or directory along the path
+    components[..candidates]
+        .iter()
+        .any(|c| matches_glob(pattern, c))
+}
+
+/// matches path components against pattern segments, where `**` spans any number
+/// of components
+fn matches_segments(segments: &[&str], components: &[&str]) -> bool {
+    match segments.split_first() {
+        None => components.is_empty(),
+        Some((&"**", rest)) => {
+            (0..=components.len()).any(|skip| matches_segments(rest, &components[skip..]))
+        }
+        Some((segment, rest)) => match components.split_first() {
+            Some((component, remaining)) => {
+                matches_glob(segment, component) && matches_segments(rest, remaining)
+            }
+            None => false,
+        },
+    }
+}
+
+/// matches a single path component against a glob with `*` and `?`
+pub(crate) fn matches_glob(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+
+    // classic backtracking wildcard match
+    let (mut p, mut n) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, n));
+            p += 1;
+        } else if let Some((star_p, star_n)) = star {
+            p = star_p + 1;
+            n = star_n + 1;
+            star = Some((star_p, star_n + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::{Commit, FileChange};
+
+    fn file(path: &str, insertions: u64, deletions: u64) -> FileChange {
+        FileChange {
+            path: path.to_string(),
+            insertions,
+            deletions,
+        }
+    }
+
+    #[test]
+    fn bare_names_match_anywhere() {
+        let rules = IgnoreRules::new(["Cargo.lock", "*.min.js"]);
+
+        assert!(rules.is_ignored("Cargo.lock"));
+        assert!(rules.is_ignored("crates/foo/Cargo.lock"));
+        assert!(rules.is_ignored("static/app.min.js"));
+        assert!(!rules.is_ignored("static/app.js"));
+        assert!(!rules.is_ignored("Cargo.toml"));
+    }
+
+    #[test]
+    fn bare_names_match_directories() {
+        let rules = IgnoreRules::new(["node_modules/", "vendor/"]);
+
+        assert!(rules.is_ignored("node_modules/left-pad/index.js"));
+        assert!(rules.is_ignored("web/node_modules/left-pad/index.js"));
+        assert!(rules.is_ignored("src/vendor/lib.c"));
+        assert!(!rules.is_ignored("src/node_modules.rs"));
+        assert!(!rules.is_ignored("bin/vendor"));
+    }
+
+    #[test]
+    fn patterns_with_slashes_match_from_the_root() {
+        let rules = IgnoreRules::new(["/vendor/", "docs/*.html", "/gen"]);
+
+        assert!(rules.is_ignored("vendor/lib.c"));
+        assert!(rules.is_ignored("vendor/deep/lib.c"));
+        assert!(!rules.is_ignored("src/vendor/lib.c"));
+        assert!(rules.is_ignored("docs/index.html"));
+        assert!(!rules.is_ignored("docs/api/index.html"));
+        assert!(rules.is_ignored("gen/schema.rs"));
+        assert!(!rules.is_ignored("src/gen/schema.rs"));
+    }
+
+    #[test]
+    fn double_star_crosses_directories() {
+        let rules = IgnoreRules::new(["docs/**/*.html", "**/snapshots/"]);
+
+        assert!(rules.is_ignored("docs/index.html"));
+        assert!(rules.is_ignored("docs/api/v1/index.html"));
+        assert!(!rules.is_ignored("docs/api/index.md"));
+        assert!(rules.is_ignored("snapshots/a.snap"));
+        assert!(rules.is_ignored("tests/snapshots/a.snap"));
+    }
+
+    #[test]
+    fn question_mark_matches_one_character() {
+        let rules = IgnoreRules::new(["data?.csv"]);
+
+        assert!(rules.is_ignored("data1.csv"));
+        assert!(!rules.is_ignored("data10.csv"));
+    }
+
+    #[test]
+    fn renames_match_on_the_new_path() {
+        let rules = IgnoreRules::new(["*.min.js"]);
+
+        assert!(rules.is_ignored("app.js => app.min.js"));
+        assert!(rules.is_ignored("static/{app.js => app.min.js}"));
+        assert!(!rules.is_ignored("static/{app.min.js => app.js}"));
+        assert_eq!(rename_target("src/{ => new}/lib.rs"), "src/new/lib.rs");
+    }
+
+    #[test]
+    fn defaults_can_be_turned_off() {
+        let rules = IgnoreRules::from_config(["vendor/"], []);
+        assert!(rules.is_ignored("Cargo.lock"));
+        assert!(rules.is_ignored("vendor/lib.c"));
+
+        let rules = IgnoreRules::from_config(["vendor/"], ["true", "false"]);
+        assert!(!rules.is_ignored("Cargo.lock"));
+        assert!(rules.is_ignored("vendor/lib.c"));
+    }
+
+    #[test]
+    fn commits_recount_lines_without_ignored_files() {
+        let rules = IgnoreRules::from_config([], []);
+        let mut commit = Commit {
+            files: vec![file("Cargo.lock", 400, 120), file("src/main.rs", 7, 3)],
+            insertions: 407,
+            deletions: 123,
+            ..Default::default()
+        };
+
+        commit.apply_ignore_rules(&rules);
+
+        assert_eq!((commit.insertions(), commit.deletions()), (7, 3));
+        assert_eq!(commit.files.len(), 2);
+    }
+
+    #[test]
+    fn commits_touching_only_ignored_files_count_zero_lines() {
+        let rules = IgnoreRules::from_config([], []);
+        let mut commit = Commit {
+            files: vec![
+                file("package-lock.json", 2000, 1800),
+                file("dist/app.min.js", 1, 1),
+            ],
+            insertions: 2001,
+            deletions: 1801,
+            ..Default::default()
+        };
+
+        commit.apply_ignore_rules(&rules);
+
+        assert_eq!(commit.lines_changed(), 0);
+    }
+}
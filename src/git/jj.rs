@@ -0,0 +1,518 @@
+//! Finding pushes in a jj repo from jj's operation log, rather than from what a shell
+//! alias saw happen around `jj git push`.
+//!
+//! Every `jj git push` is an operation described like "push bookmark main to git remote
+//! origin", and jj can show the repo as of any operation. So the commits a push sent
+//! are the ones reachable from the remote's bookmarks after it but not before it. The
+//! log is read from where party last left off, so pushes made without the `jj push`
+//! alias are picked up the next time party runs.
+//!
+//! Commits are deduped by change id, which survives rewrites that change the patch,
+//! as well as by patch-id like in git repos.
+
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::process::Command;
+
+use anyhow::Result;
+
+use crate::{
+    git::{
+        self, Commit, Push, PushedBranch, backfill::PastPush, config::PartyConfig,
+        detection::get_remote_ids,
+    },
+    storage::{BranchRefsStore, JjStore, PatchIdStore},
+};
+
+/// how far back into the operation log to look
+const MAX_OPERATIONS: &str = "1000";
+
+/// one line per operation: id, unix end time, and the first line of its description
+const OP_LOG_TEMPLATE: &str =
+    r#"id ++ "\t" ++ time.end().format("%s") ++ "\t" ++ description.first_line() ++ "\n""#;
+
+/// an entry in jj's operation log
+#[derive(Debug, Clone, PartialEq)]
+struct Operation {
+    id: String,
+    timestamp: u64,
+    description: String,
+}
+
+/// what a push operation sent where
+#[derive(Debug, Clone, PartialEq)]
+struct PushOperation {
+    remote: String,
+    bookmarks: Vec<String>,
+}
+
+/// runs jj in a workspace, leaving the working copy alone so that party never adds
+/// operations of its own
+fn run_jj(jj_root: &Path, args: &[&str]) -> Option<String> {
+    let output = Command::new("jj")
+        .args(["--ignore-working-copy", "--no-pager", "--color=never"])
+        .args(args)
+        .current_dir(jj_root)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        crate::debug_log!(
+            "jj {:?} failed: {}",
+            args,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// the repo's operations, newest first
+fn get_operations(jj_root: &Path) -> Vec<Operation> {
+    run_jj(
+        jj_root,
+        &[
+            "op",
+            "log",
+            "--no-graph",
+            "-n",
+            MAX_OPERATIONS,
+            "-T",
+            OP_LOG_TEMPLATE,
+        ],
+    )
+    .map(|output| parse_operations(&output))
+    .unwrap_or_default()
+}
+
+fn parse_operations(output: &str) -> Vec<Operation> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, '\t');
+            let (id, timestamp, description) = (fields.next()?, fields.next()?, fields.next()?);
+            Some(Operation {
+                id: id.to_string(),
+                timestamp: timestamp.parse().ok()?,
+                description: description.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// the remote and bookmarks of a push, from its description, e.g.
+/// "push bookmarks feature, main to git remote origin"
+fn parse_push(description: &str) -> Option<PushOperation> {
+    let (what, remote) = description
+        .strip_prefix("push ")?
+        .rsplit_once(" to git remote ")?;
+
+    // older versions of jj called bookmarks branches
+    let names = ["bookmarks ", "bookmark ", "branches ", "branch "]
+        .iter()
+        .find_map(|prefix| what.strip_prefix(prefix));
+    let bookmarks = names
+        .map(|names| names.split(", ").map(|n| n.trim().to_string()).collect())
+        .unwrap_or_default();
+
+    Some(PushOperation {
+        remote: remote.trim().to_string(),
+        bookmarks,
+    })
+}
+
+/// a string literal for a revset
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// where each of a remote's bookmarks pointed as of an operation, as commit id ->
+/// bookmark names
+fn remote_heads(jj_root: &Path, op: &str, remote: &str) -> Option<HashMap<String, Vec<String>>> {
+    let revset = format!("remote_bookmarks(remote=exact:{})", quote(remote));
+    let output = run_jj(
+        jj_root,
+        &[
+            "log",
+            "--at-op",
+            op,
+            "--no-graph",
+            "-r",
+            &revset,
+            "-T",
+            r#"commit_id ++ "\t" ++ remote_bookmarks ++ "\n""#,
+        ],
+    )?;
+    Some(parse_remote_heads(&output, remote))
+}
+
+/// lines like "<commit id>\tmain@origin feature@origin main@upstream"
+fn parse_remote_heads(output: &str, remote: &str) -> HashMap<String, Vec<String>> {
+    let suffix = format!("@{remote}");
+    output
+        .lines()
+        .filter_map(|line| {
+            let (commit_id, refs) = line.split_once('\t').unwrap_or((line, ""));
+            if commit_id.is_empty() {
+                return None;
+            }
+            let names = refs
+                .split_whitespace()
+                // conflicted or out-of-sync bookmarks are marked with trailing `?` or `*`
+                .map(|r| r.trim_end_matches(['?', '*']))
+                .filter_map(|r| r.strip_suffix(&suffix))
+                .map(|name| name.to_string())
+                .collect();
+            Some((commit_id.to_string(), names))
+        })
+        .collect()
+}
+
+/// every commit that a bookmark on any of `remotes` pointed to as of an operation
+fn all_remote_heads(jj_root: &Path, op: &str, remotes: &[&str]) -> Option<Vec<String>> {
+    if remotes.is_empty() {
+        return Some(Vec::new());
+    }
+    let revset = remotes
+        .iter()
+        .map(|remote| format!("remote_bookmarks(remote=exact:{})", quote(remote)))
+        .collect::<Vec<_>>()
+        .join(" | ");
+    let output = run_jj(
+        jj_root,
+        &[
+            "log",
+            "--at-op",
+            op,
+            "--no-graph",
+            "-r",
+            &revset,
+            "-T",
+            r#"commit_id ++ "\n""#,
+        ],
+    )?;
+    Some(output.lines().map(|line| line.to_string()).collect())
+}
+
+/// the change id of every commit reachable from `include` but not from `exclude`,
+/// as commit id -> change id
+fn change_ids(
+    jj_root: &Path,
+    op: &str,
+    include: &[&str],
+    exclude: &[&str],
+) -> Option<HashMap<String, String>> {
+    let mut revset = format!("::({})", include.join(" | "));
+    if !exclude.is_empty() {
+        revset.push_str(&format!(" ~ ::({})", exclude.join(" | ")));
+    }
+    let output = run_jj(
+        jj_root,
+        &[
+            "log",
+            "--at-op",
+            op,
+            "--no-graph",
+            "-r",
+            &revset,
+            "-T",
+            r#"commit_id ++ "\t" ++ change_id ++ "\n""#,
+        ],
+    )?;
+
+    Some(
+        output
+            .lines()
+            .filter_map(|line| line.split_once('\t'))
+            .map(|(commit_id, change_id)| (commit_id.to_string(), change_id.to_string()))
+            .collect(),
+    )
+}
+
+/// where party keeps track of a jj repo's operation log
+fn repo_key(jj_root: &Path) -> String {
+    jj_root
+        .canonicalize()
+        .unwrap_or_else(|_| jj_root.to_path_buf())
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Marks every operation so far as seen, so that pushes from before party was set up
+/// aren't credited.
+pub fn snapshot_operations(jj_root: &Path, jj: &JjStore) -> Result<()> {
+    if let Some(newest) = get_operations(jj_root).first() {
+        jj.set_last_operation(&repo_key(jj_root), &newest.id)?;
+    }
+    Ok(())
+}
+
+/// Every push in the operation log since party last looked, oldest first, with the
+/// commits that earn credit. Records their change ids and patch-ids as a side effect.
+/// None if jj hasn't pushed since then, so anything that was pushed went through git.
+pub fn find_new_pushes(
+    jj_root: &Path,
+    jj: &JjStore,
+    patch_ids: &PatchIdStore,
+    branch_refs: &BranchRefsStore,
+) -> Result<Option<Vec<PastPush>>> {
+    let key = repo_key(jj_root);
+    let operations = get_operations(jj_root);
+    let Some(newest) = operations.first() else {
+        return Ok(None);
+    };
+    let Ok(git_dir) = git::repo::jj_git_dir(jj_root) else {
+        return Ok(None);
+    };
+
+    // a repo whose operation log party has never read, e.g. one set up before party
+    // read it, is started at the newest operation. anything pushed since its refs were
+    // snapshotted is found the way a git repo's pushes are. a repo party has never seen
+    // at all gets its refs snapshotted instead, so its history isn't credited
+    let last = jj.last_operation(&key)?;
+    if last.is_none() {
+        crate::debug_log!("jj: starting operation log at {}", newest.id);
+        jj.set_last_operation(&key, &newest.id)?;
+        let found =
+            git::get_pushed_commits(&git_dir, branch_refs, patch_ids).map(|push| PastPush {
+                timestamp: newest.timestamp,
+                push,
+            });
+        return Ok(Some(found.into_iter().collect()));
+    }
+
+    // if the last seen operation isn't in the log anymore (e.g. after `jj op abandon`),
+    // there's no telling what's new, so start over from here
+    let pending: Vec<&Operation> = operations
+        .iter()
+        .take_while(|op| Some(&op.id) != last.as_ref())
+        .collect();
+    if pending.len() == operations.len() {
+        crate::debug_log!(
+            "jj: lost track of the operation log, restarting at {}",
+            newest.id
+        );
+        jj.set_last_operation(&key, &newest.id)?;
+        return Ok(Some(Vec::new()));
+    }
+
+    let pushes: Vec<(&Operation, PushOperation)> = pending
+        .iter()
+        .rev()
+        .filter_map(|op| Some((*op, parse_push(&op.description)?)))
+        .collect();
+    if pushes.is_empty() {
+        jj.set_last_operation(&key, &newest.id)?;
+        return Ok(None);
+    }
+
+    let remote_ids = get_remote_ids(&git_dir);
+    let url_of: HashMap<&str, &str> = remote_ids
+        .iter()
+        .map(|(r, u)| (r.as_str(), u.as_str()))
+        .collect();
+    let all_urls: Vec<&str> = remote_ids.iter().map(|(_, u)| u.as_str()).collect();
+    let remotes: Vec<&str> = remote_ids.iter().map(|(r, _)| r.as_str()).collect();
+
+    // jj has its own idea of who the user is, which counts as well as git's
+    let jj_email = run_jj(jj_root, &["config", "get", "user.email"]).unwrap_or_default();
+    let PartyConfig {
+        identity,
+        bots,
+        ignore_rules,
+        ..
+    } = PartyConfig::load_with_emails(&git_dir, [jj_email.trim()]);
+
+    let mut found = Vec::new();
+    for (op, push_op) in pushes {
+        let Some(&remote_url) = url_of.get(push_op.remote.as_str()) else {
+            crate::debug_log!("jj: push to unknown remote {}", push_op.remote);
+            continue;
+        };
+        let parent = format!("{}-", op.id);
+        let (Some(before), Some(after)) = (
+            remote_heads(jj_root, &parent, &push_op.remote),
+            remote_heads(jj_root, &op.id, &push_op.remote),
+        ) else {
+            continue;
+        };
+
+        let include: Vec<&str> = after
+            .keys()
+            .filter(|id| !before.contains_key(*id))
+            .map(|id| id.as_str())
+            .collect();
+        if include.is_empty() {
+            continue;
+        }
+
+        // anything any remote already had, e.g. commits fetched from upstream and
+        // pushed to a fork, isn't new
+        let elsewhere = all_remote_heads(jj_root, &parent, &remotes).unwrap_or_default();
+        let mut exclude: Vec<&str> = before
+            .keys()
+            .chain(&elsewhere)
+            .map(|id| id.as_str())
+            .collect();
+        exclude.sort_unstable();
+        exclude.dedup();
+        let changes = change_ids(jj_root, &op.id, &include, &exclude).unwrap_or_default();
+
+        // the pushed bookmarks, in the order jj named them, then any others that moved
+        let mut branch_commits: Vec<(String, Vec<Commit>)> = push_op
+            .bookmarks
+            .iter()
+            .map(|b| (b.clone(), Vec::new()))
+            .collect();
+        for name in include.iter().flat_map(|id| &after[*id]) {
+            if !branch_commits.iter().any(|(b, _)| b == name) {
+                branch_commits.push((name.clone(), Vec::new()));
+            }
+        }
+
+        let mut skipped_commits = 0;
+        let mut automated_commits = 0;
+        let mut seen_changes = HashSet::new();
+        for info in git::commands::scan_commits(&git_dir, &include, &exclude) {
+            let Some(patch_id) = info.patch_id else {
+                continue;
+            };
+            let change_id = changes.get(info.commit.sha());
+
+            let mut credited = change_id.is_some_and(|c| !seen_changes.insert(c.clone()));
+            for url in &all_urls {
+                credited |= patch_ids.contains(url, &patch_id)?;
+                if let Some(change_id) = change_id {
+                    credited |= jj.contains_change(url, change_id)?;
+                }
+            }
+            if credited {
+                continue;
+            }
+            patch_ids.record(remote_url, &patch_id)?;
+            if let Some(change_id) = change_id {
+                jj.record_change(remote_url, change_id)?;
+            }
+
+            if bots.is_automated(&info.commit) {
+                automated_commits += 1;
+                continue;
+            }
+            if !identity.is_mine(info.commit.author_email()) {
+                skipped_commits += 1;
+                continue;
+            }
+
+            // credited to the first bookmark on its head that this push moved
+            let branch = after
+                .get(&info.source)
+                .and_then(|names| names.first())
+                .or_else(|| push_op.bookmarks.first());
+            let Some(branch) = branch else {
+                continue;
+            };
+            if let Some((_, commits)) = branch_commits.iter_mut().find(|(b, _)| b == branch) {
+                let mut commit = info.commit;
+                commit.apply_ignore_rules(&ignore_rules);
+                commits.push(commit);
+            }
+        }
+
+        let branches: Vec<PushedBranch> = branch_commits
+            .into_iter()
+            .map(|(name, commits)| PushedBranch::new(name, commits))
+            .collect();
+        let push = Push::from_branches(branches, remote_url, skipped_commits)
+            .with_automated(automated_commits);
+        crate::debug_log!(
+            "jj: operation {} pushed {} new commits to {}",
+            op.id,
+            push.commits().len(),
+            remote_url
+        );
+        if !push.commits().is_empty() || push.automated_commits() > 0 {
+            found.push(PastPush {
+                timestamp: op.timestamp,
+                push,
+            });
+        }
+    }
+
+    jj.set_last_operation(&key, &newest.id)?;
+    Ok(Some(found))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_operation_log() {
+        let output = "\
+            abc123\t1700000500\tpush bookmark main to git remote origin\n\
+            def456\t1700000400\tcommit 3f2a\n\
+            bad\tnot-a-time\tsomething\n";
+
+        assert_eq!(
+            parse_operations(output),
+            vec![
+                Operation {
+                    id: "abc123".to_string(),
+                    timestamp: 1_700_000_500,
+                    description: "push bookmark main to git remote origin".to_string(),
+                },
+                Operation {
+                    id: "def456".to_string(),
+                    timestamp: 1_700_000_400,
+                    description: "commit 3f2a".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_push_descriptions() {
+        assert_eq!(
+            parse_push("push bookmark main to git remote origin"),
+            Some(PushOperation {
+                remote: "origin".to_string(),
+                bookmarks: vec!["main".to_string()],
+            })
+        );
+        assert_eq!(
+            parse_push("push bookmarks feature, main to git remote upstream"),
+            Some(PushOperation {
+                remote: "upstream".to_string(),
+                bookmarks: vec!["feature".to_string(), "main".to_string()],
+            })
+        );
+        assert_eq!(
+            parse_push("push branch main to git remote origin"),
+            Some(PushOperation {
+                remote: "origin".to_string(),
+                bookmarks: vec!["main".to_string()],
+            })
+        );
+        assert_eq!(parse_push("fetch from git remote(s) origin"), None);
+        assert_eq!(parse_push("commit 3f2a"), None);
+    }
+
+    #[test]
+    fn parses_remote_heads_for_one_remote() {
+        let output = "\
+            aaa\tmain@origin main@upstream\n\
+            bbb\tfeature@origin?? docs@upstream\n\
+            ccc\t\n";
+
+        let heads = parse_remote_heads(output, "origin");
+
+        assert_eq!(heads["aaa"], vec!["main".to_string()]);
+        assert_eq!(heads["bbb"], vec!["feature".to_string()]);
+        assert!(heads["ccc"].is_empty());
+    }
+
+    #[test]
+    fn quotes_revset_strings() {
+        assert_eq!(quote("origin"), "\"origin\"");
+        assert_eq!(quote(r#"we"ird"#), r#""we\"ird""#);
+    }
+}
@@ -1,48 +1,354 @@
 //! Git operations for detecting pushes and tracking seen commits.
 
+pub mod backfill;
+pub mod bots;
+pub mod classify;
 mod commands;
+pub mod config;
 pub mod detection;
+pub mod identity;
+pub mod ignore;
+pub mod jj;
+pub mod receive;
+pub mod remote;
+pub mod repo;
+pub mod rewrite;
+pub mod tags;
 
-pub use detection::{get_pushed_commits, snapshot_refs};
+pub use detection::{
+    get_pushed_commits, get_unpushed_commits, remote_ref_positions, snapshot_refs,
+};
+use ignore::IgnoreRules;
+use rewrite::RewritePolicy;
+use serde::{Deserialize, Serialize};
+
+/// whether `sha` is git's all-zero stand-in for "no object", as used for refs that
+/// are being created or deleted
+pub fn is_null_sha(sha: &str) -> bool {
+    sha.bytes().all(|b| b == b'0')
+}
+
+/// lines added and removed in a single file by a commit
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileChange {
+    path: String,
+    insertions: u64,
+    deletions: u64,
+}
+
+impl FileChange {
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    #[expect(dead_code)]
+    pub fn insertions(&self) -> u64 {
+        self.insertions
+    }
+
+    #[expect(dead_code)]
+    pub fn deletions(&self) -> u64 {
+        self.deletions
+    }
+}
 
 /// data about a single commit in a push
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Commit {
-    lines_changed: u64,
-    #[expect(dead_code)]
     sha: String,
-    #[expect(dead_code)]
-    timestamp: u64,
+    author_name: String,
+    author_email: String,
+
+    /// who made the commit object, which differs from the author for cherry-picks,
+    /// rebases, and commits made by CI on someone's behalf
+    committer_name: String,
+    committer_email: String,
+
+    /// unix timestamps
+    authored_at: u64,
+    committed_at: u64,
+
+    /// first paragraph of the message, joined into one line
+    subject: String,
+
+    /// rest of the message after the subject, including any trailers
+    body: String,
+
+    /// `Key: value` trailers at the end of the message, e.g. ("Co-authored-by", "...")
+    trailers: Vec<(String, String)>,
+
+    files: Vec<FileChange>,
+
+    /// lines added and removed across all text files
+    insertions: u64,
+    deletions: u64,
+
+    /// the diff only changes whitespace
+    whitespace_only: bool,
+
+    /// identifies the change whatever commit it's in, once it has been worked out
+    patch_id: Option<String>,
 }
 
 impl Commit {
     pub fn new(sha: impl Into<String>, lines_changed: u64, timestamp: u64) -> Self {
         Self {
             sha: sha.into(),
-            lines_changed,
-            timestamp,
+            insertions: lines_changed,
+            authored_at: timestamp,
+            committed_at: timestamp,
+            ..Default::default()
         }
     }
 
     #[cfg(test)]
     pub fn with_lines(lines_changed: u64) -> Self {
         Self {
-            lines_changed,
+            insertions: lines_changed,
+            ..Default::default()
+        }
+    }
+
+    #[cfg(test)]
+    pub fn with_sha(sha: &str) -> Self {
+        Self {
+            sha: sha.to_string(),
+            insertions: 10,
+            ..Default::default()
+        }
+    }
+
+    #[cfg(test)]
+    pub fn whitespace_only(lines_changed: u64) -> Self {
+        Self {
+            insertions: lines_changed,
+            whitespace_only: true,
+            ..Default::default()
+        }
+    }
+
+    /// a commit as written by `git revert <reverted>`
+    #[cfg(test)]
+    pub fn reverting(sha: &str, reverted: &str) -> Self {
+        Self {
+            sha: sha.to_string(),
+            body: format!("This reverts commit {reverted}."),
+            insertions: 10,
             ..Default::default()
         }
     }
 
+    #[cfg(test)]
+    pub fn with_changes(insertions: u64, deletions: u64) -> Self {
+        Self {
+            insertions,
+            deletions,
+            ..Default::default()
+        }
+    }
+
+    /// a commit that changes `lines` lines in each of `paths`
+    #[cfg(test)]
+    pub fn touching(paths: &[&str], lines: u64) -> Self {
+        Self {
+            files: paths
+                .iter()
+                .map(|path| FileChange {
+                    path: path.to_string(),
+                    insertions: lines,
+                    deletions: 0,
+                })
+                .collect(),
+            insertions: lines * paths.len() as u64,
+            ..Default::default()
+        }
+    }
+
+    #[cfg(test)]
+    pub fn with_patch_id(self, patch_id: impl Into<String>) -> Self {
+        Self {
+            patch_id: Some(patch_id.into()),
+            ..self
+        }
+    }
+
+    pub fn sha(&self) -> &str {
+        &self.sha
+    }
+
+    pub fn patch_id(&self) -> Option<&str> {
+        self.patch_id.as_deref()
+    }
+
+    pub fn author_name(&self) -> &str {
+        &self.author_name
+    }
+
+    pub fn author_email(&self) -> &str {
+        &self.author_email
+    }
+
+    pub fn committer_name(&self) -> &str {
+        &self.committer_name
+    }
+
+    pub fn committer_email(&self) -> &str {
+        &self.committer_email
+    }
+
+    #[expect(dead_code)]
+    pub fn authored_at(&self) -> u64 {
+        self.authored_at
+    }
+
+    #[expect(dead_code)]
+    pub fn committed_at(&self) -> u64 {
+        self.committed_at
+    }
+
+    pub fn subject(&self) -> &str {
+        &self.subject
+    }
+
+    pub fn body(&self) -> &str {
+        &self.body
+    }
+
+    #[expect(dead_code)]
+    pub fn trailers(&self) -> &[(String, String)] {
+        &self.trailers
+    }
+
+    pub fn files(&self) -> &[FileChange] {
+        &self.files
+    }
+
+    pub fn insertions(&self) -> u64 {
+        self.insertions
+    }
+
+    pub fn deletions(&self) -> u64 {
+        self.deletions
+    }
+
+    /// insertions + deletions
     pub fn lines_changed(&self) -> u64 {
-        self.lines_changed
+        self.insertions + self.deletions
+    }
+
+    /// recounts insertions and deletions without the files the rules ignore.
+    /// the files themselves are kept, so tracks can still see what was touched
+    pub fn apply_ignore_rules(&mut self, rules: &IgnoreRules) {
+        let counted = self.files.iter().filter(|f| !rules.is_ignored(&f.path));
+        (self.insertions, self.deletions) = counted.fold((0, 0), |(ins, del), f| {
+            (ins + f.insertions, del + f.deletions)
+        });
+    }
+}
+
+/// a branch updated by a push, along with the new commits it received
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PushedBranch {
+    name: String,
+    commits: Vec<Commit>,
+
+    /// my previously credited commits that a force push threw away
+    discarded: Vec<Commit>,
+}
+
+impl PushedBranch {
+    pub fn new(name: impl Into<String>, commits: Vec<Commit>) -> Self {
+        Self {
+            name: name.into(),
+            commits,
+            discarded: Vec::new(),
+        }
+    }
+
+    pub fn with_discarded(self, discarded: Vec<Commit>) -> Self {
+        Self { discarded, ..self }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn commits(&self) -> &[Commit] {
+        &self.commits
+    }
+}
+
+/// a tag created on a remote by a push
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PushedTag {
+    name: String,
+
+    /// the commit the tag points to
+    commit_sha: String,
+
+    /// annotated tags are tag objects with their own message, rather than a bare ref
+    annotated: bool,
+
+    /// the commit already had a tag pushed to this repo
+    retag: bool,
+}
+
+impl PushedTag {
+    pub fn new(
+        name: impl Into<String>,
+        commit_sha: impl Into<String>,
+        annotated: bool,
+        retag: bool,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            commit_sha: commit_sha.into(),
+            annotated,
+            retag,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn commit_sha(&self) -> &str {
+        &self.commit_sha
+    }
+
+    /// named like a semantic version, e.g. "v1.2.0" or "2.0.0-rc.1"
+    pub fn is_semver(&self) -> bool {
+        tags::is_semver(&self.name)
+    }
+
+    /// whether the tag marks a release: annotated or semver-named, on a commit that
+    /// hasn't been tagged before
+    pub fn is_release(&self) -> bool {
+        !self.retag && (self.annotated || self.is_semver())
     }
 }
 
 /// data about a single push
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Push {
+    /// every new commit in the push, across all branches
     commits: Vec<Commit>,
     remote_url: String,
-    branch: String,
+
+    /// every branch the push touched, including ones that received no new commits
+    branches: Vec<PushedBranch>,
+
+    /// how many new commits were left out because someone else authored them
+    skipped_commits: u64,
+
+    /// how many new commits were left out because a bot made them
+    automated_commits: u64,
+
+    /// what to do about commits thrown away by a force push
+    rewrite_policy: RewritePolicy,
+
+    /// tags the push created on the remote
+    tags: Vec<PushedTag>,
 }
 
 impl Default for Push {
@@ -50,7 +356,11 @@ impl Default for Push {
         Self {
             commits: Vec::new(),
             remote_url: "git@github.com:user/repo.git".to_string(),
-            branch: "main".to_string(),
+            branches: vec![PushedBranch::new("main", Vec::new())],
+            skipped_commits: 0,
+            automated_commits: 0,
+            rewrite_policy: RewritePolicy::default(),
+            tags: Vec::new(),
         }
     }
 }
@@ -59,6 +369,7 @@ impl Push {
     #[cfg(test)]
     pub fn new(commits: Vec<Commit>) -> Self {
         Self {
+            branches: vec![PushedBranch::new("main", commits.clone())],
             commits,
             ..Default::default()
         }
@@ -67,21 +378,60 @@ impl Push {
     #[cfg(any(test, feature = "dev"))]
     pub fn with_repo(commits: Vec<Commit>, remote_url: impl Into<String>) -> Self {
         Self {
+            branches: vec![PushedBranch::new("main", commits.clone())],
             commits,
             remote_url: remote_url.into(),
             ..Default::default()
         }
     }
 
-    pub fn from_parts(
-        commits: Vec<Commit>,
+    pub fn from_branches(
+        branches: Vec<PushedBranch>,
         remote_url: impl Into<String>,
-        branch: impl Into<String>,
+        skipped_commits: u64,
     ) -> Self {
         Self {
-            commits,
+            commits: branches.iter().flat_map(|b| b.commits.clone()).collect(),
             remote_url: remote_url.into(),
-            branch: branch.into(),
+            branches,
+            skipped_commits,
+            automated_commits: 0,
+            rewrite_policy: RewritePolicy::default(),
+            tags: Vec::new(),
+        }
+    }
+
+    /// a push of tags only, which don't touch any branch
+    pub fn from_tags(tags: Vec<PushedTag>, remote_url: impl Into<String>) -> Self {
+        Self {
+            remote_url: remote_url.into(),
+            branches: Vec::new(),
+            tags,
+            ..Default::default()
+        }
+    }
+
+    /// the same push, having also created `tags`
+    pub fn with_tags(mut self, tags: &[PushedTag]) -> Self {
+        self.tags.extend_from_slice(tags);
+        self
+    }
+
+    pub fn add_tag(&mut self, tag: PushedTag) {
+        self.tags.push(tag);
+    }
+
+    pub fn with_automated(self, automated_commits: u64) -> Self {
+        Self {
+            automated_commits,
+            ..self
+        }
+    }
+
+    pub fn with_rewrite_policy(self, rewrite_policy: RewritePolicy) -> Self {
+        Self {
+            rewrite_policy,
+            ..self
         }
     }
 
@@ -89,11 +439,53 @@ impl Push {
         &self.commits
     }
 
+    /// the same push with only the commits that `keep` returns true for
+    pub fn filter_commits(&self, keep: impl Fn(&Commit) -> bool) -> Self {
+        let branches = self
+            .branches
+            .iter()
+            .map(|b| PushedBranch {
+                commits: b.commits.iter().filter(|c| keep(c)).cloned().collect(),
+                ..b.clone()
+            })
+            .collect();
+        Self {
+            commits: self.commits.iter().filter(|c| keep(c)).cloned().collect(),
+            remote_url: self.remote_url.clone(),
+            branches,
+            skipped_commits: self.skipped_commits,
+            automated_commits: self.automated_commits,
+            rewrite_policy: self.rewrite_policy,
+            tags: self.tags.clone(),
+        }
+    }
+
     pub fn remote_url(&self) -> &str {
         &self.remote_url
     }
 
-    pub fn branch(&self) -> &str {
-        &self.branch
+    pub fn branches(&self) -> &[PushedBranch] {
+        &self.branches
+    }
+
+    pub fn skipped_commits(&self) -> u64 {
+        self.skipped_commits
+    }
+
+    pub fn automated_commits(&self) -> u64 {
+        self.automated_commits
+    }
+
+    /// my previously credited commits that the push threw away, across all branches
+    pub fn discarded_commits(&self) -> impl Iterator<Item = &Commit> {
+        self.branches.iter().flat_map(|b| b.discarded.iter())
+    }
+
+    pub fn rewrite_policy(&self) -> RewritePolicy {
+        self.rewrite_policy
+    }
+
+    pub fn tags(&self) -> &[PushedTag] {
+        &self.tags
     }
 }
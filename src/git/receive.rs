@@ -0,0 +1,128 @@
+//! Pushes as a server sees them. A bare repo's `post-receive` hook is handed one line
+//! per updated ref on stdin, `<old sha> <new sha> <ref name>`, after every ref has
+//! been updated. The commits a push brought in are the ones reachable from the new
+//! positions but not from the old ones, or from any branch the push didn't touch.
+
+use std::path::Path;
+
+use crate::git::{self, Commit, config::PartyConfig};
+
+/// a branch moved by the push
+#[derive(Debug, PartialEq)]
+struct BranchUpdate<'a> {
+    branch: &'a str,
+
+    /// None for a branch the push created
+    old_sha: Option<&'a str>,
+    new_sha: &'a str,
+}
+
+/// a commit brought in by a push
+#[derive(Debug)]
+pub struct ReceivedCommit {
+    pub branch: String,
+    pub commit: Commit,
+    pub patch_id: String,
+
+    /// made by a bot rather than a person
+    pub automated: bool,
+}
+
+/// Every new commit in a push to the bare repo at `repo_path`, newest first. `input` is
+/// what git writes to the `post-receive` hook's stdin. Merges and empty commits have
+/// no patch-id and are left out, like on the client.
+pub fn get_received_commits(repo_path: &Path, input: &str) -> Vec<ReceivedCommit> {
+    let updates = parse_updates(input);
+    if updates.is_empty() {
+        return Vec::new();
+    }
+
+    // refs are already updated by now, so branches the push didn't touch are where
+    // they were before it
+    let (_, branches) = git::commands::get_all_refs(repo_path, &[]);
+    let mut exclude: Vec<&str> = updates.iter().filter_map(|u| u.old_sha).collect();
+    exclude.extend(
+        branches
+            .iter()
+            .filter(|(name, _)| !updates.iter().any(|u| u.branch == name.as_str()))
+            .map(|(_, sha)| sha.as_str()),
+    );
+    let include: Vec<&str> = updates.iter().map(|u| u.new_sha).collect();
+
+    let PartyConfig {
+        bots, ignore_rules, ..
+    } = PartyConfig::load(repo_path);
+
+    git::commands::scan_commits(repo_path, &include, &exclude)
+        .into_iter()
+        .filter_map(|info| {
+            let patch_id = info.patch_id?;
+            let update = updates.iter().find(|u| u.new_sha == info.source)?;
+            let mut commit = info.commit;
+            commit.apply_ignore_rules(&ignore_rules);
+            Some(ReceivedCommit {
+                branch: update.branch.to_string(),
+                automated: bots.is_automated(&commit),
+                commit,
+                patch_id,
+            })
+        })
+        .collect()
+}
+
+/// branches the push created or moved. deleted branches and other refs (tags, notes)
+/// are left out
+fn parse_updates(input: &str) -> Vec<BranchUpdate<'_>> {
+    input
+        .lines()
+        .filter_map(|line| {
+            let [old_sha, new_sha, refname] = line.split_whitespace().collect::<Vec<_>>()[..]
+            else {
+                return None;
+            };
+            let branch = refname.strip_prefix("refs/heads/")?;
+            if git::is_null_sha(new_sha) {
+                return None;
+            }
+            Some(BranchUpdate {
+                branch,
+                old_sha: (!git::is_null_sha(old_sha)).then_some(old_sha),
+                new_sha,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ZERO: &str = "0000000000000000000000000000000000000000";
+
+    #[test]
+    fn parses_branch_updates() {
+        let input = format!(
+            "aaa bbb refs/heads/main\n\
+             {ZERO} ccc refs/heads/feature/login\n\
+             ddd {ZERO} refs/heads/old\n\
+             {ZERO} eee refs/tags/v1.0.0\n\
+             garbage\n"
+        );
+
+        assert_eq!(
+            parse_updates(&input),
+            vec![
+                BranchUpdate {
+                    branch: "main",
+                    old_sha: Some("aaa"),
+                    new_sha: "bbb",
+                },
+                BranchUpdate {
+                    branch: "feature/login",
+                    old_sha: None,
+                    new_sha: "ccc",
+                },
+            ]
+        );
+    }
+}
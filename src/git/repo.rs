@@ -0,0 +1,140 @@
+//! Finding the repository that contains a directory, so that party behaves the same
+//! from the repo root, a subdirectory, a linked worktree or a submodule.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{Result, anyhow};
+
+use crate::git::commands;
+
+/// a git working tree, as resolved by git itself
+#[derive(Debug, Clone, PartialEq)]
+pub struct GitRepo {
+    /// root of the working tree. linked worktrees and submodules have their own
+    pub toplevel: PathBuf,
+
+    /// git dir shared by every worktree of the repo, which is where hooks live
+    pub common_dir: PathBuf,
+}
+
+impl GitRepo {
+    pub fn find(cwd: &Path) -> Option<Self> {
+        let (toplevel, common_dir) = commands::get_repo_dirs(cwd)?;
+        Some(Self {
+            toplevel,
+            common_dir,
+        })
+    }
+
+    pub fn hooks_dir(&self) -> PathBuf {
+        self.common_dir.join("hooks")
+    }
+}
+
+/// root of the jj workspace containing `cwd`, if any
+pub fn find_jj_root(cwd: &Path) -> Option<PathBuf> {
+    let output = Command::new("jj")
+        .arg("root")
+        .current_dir(cwd)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    let root = String::from_utf8(output.stdout).ok()?;
+    Some(PathBuf::from(root.trim()))
+}
+
+/// the git repo backing a jj workspace, colocated or not
+pub fn jj_git_dir(jj_root: &Path) -> Result<PathBuf> {
+    let jj_dir = jj_root.join(".jj");
+    let mut repo_dir = jj_dir.join("repo");
+
+    // secondary workspaces point at the main workspace's repo dir with a file
+    if repo_dir.is_file() {
+        let target = std::fs::read_to_string(&repo_dir)?;
+        repo_dir = jj_dir.join(target.trim());
+    }
+
+    let store_dir = repo_dir.join("store");
+    let git_target = std::fs::read_to_string(store_dir.join("git_target"))
+        .map_err(|e| anyhow!("could not find the git repo for this jj workspace: {e}"))?;
+    Ok(store_dir.join(git_target.trim()).canonicalize()?)
+}
+
+/// directory to run git commands from: the working tree root, or for a jj workspace
+/// that isn't colocated, the git repo that jj keeps internally
+pub fn git_work_dir(cwd: &Path) -> Option<PathBuf> {
+    if let Some(repo) = GitRepo::find(cwd) {
+        return Some(repo.toplevel);
+    }
+
+    let jj_root = find_jj_root(cwd)?;
+    jj_git_dir(&jj_root).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn git(cwd: &Path, args: &[&str]) {
+        let output = Command::new("git")
+            .args(args)
+            .current_dir(cwd)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "git {args:?} failed");
+    }
+
+    #[test]
+    fn finds_repo_from_subdirectory() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        git(&root, &["init"]);
+        fs::create_dir_all(root.join("a/b")).unwrap();
+
+        let repo = GitRepo::find(&root.join("a/b")).unwrap();
+
+        assert_eq!(repo.toplevel, root);
+        assert_eq!(repo.common_dir, root.join(".git"));
+        assert_eq!(git_work_dir(&root.join("a/b")), Some(root));
+    }
+
+    #[test]
+    fn linked_worktree_shares_common_dir() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        let main = root.join("main");
+        fs::create_dir(&main).unwrap();
+        git(&main, &["init"]);
+        git(
+            &main,
+            &[
+                "-c",
+                "user.name=Test",
+                "-c",
+                "user.email=test@example.com",
+                "commit",
+                "--allow-empty",
+                "-m",
+                "initial",
+            ],
+        );
+        git(&main, &["worktree", "add", "-b", "wt", "../wt"]);
+
+        let repo = GitRepo::find(&root.join("wt")).unwrap();
+
+        assert_eq!(repo.toplevel, root.join("wt"));
+        assert_eq!(repo.hooks_dir(), main.join(".git/hooks"));
+    }
+
+    #[test]
+    fn no_repo_outside_git() {
+        let dir = tempdir().unwrap();
+        assert_eq!(GitRepo::find(dir.path()), None);
+    }
+}
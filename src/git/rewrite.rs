@@ -0,0 +1,73 @@
+//! What to do when a push rewrites history and throws away commits that already
+//! earned points, e.g. `git reset --hard HEAD~3 && git push --force`.
+//!
+//! Commits that come back with the same patch-id (rebases, reorders) aren't considered
+//! thrown away, so only commits whose changes really left the remote count.
+
+use serde::{Deserialize, Serialize};
+
+/// git config key for the policy, e.g. `git config --global party.rewritePolicy clawback`
+pub const REWRITE_POLICY_KEY: &str = "party.rewritePolicy";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RewritePolicy {
+    /// don't look for discarded commits at all
+    Ignore,
+
+    /// note discarded commits in the push and history, but keep the points
+    #[default]
+    Flag,
+
+    /// take back the points that discarded commits earned
+    ClawBack,
+}
+
+impl RewritePolicy {
+    /// parses a config value, case-insensitively
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "ignore" => Some(Self::Ignore),
+            "flag" => Some(Self::Flag),
+            "clawback" | "claw-back" => Some(Self::ClawBack),
+            _ => None,
+        }
+    }
+
+    /// the policy from git config values. the last valid value wins, as with git
+    pub fn from_config<'a>(values: impl IntoIterator<Item = &'a str>) -> Self {
+        values
+            .into_iter()
+            .filter_map(Self::parse)
+            .last()
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_config_values() {
+        assert_eq!(RewritePolicy::parse("ignore"), Some(RewritePolicy::Ignore));
+        assert_eq!(RewritePolicy::parse(" Flag "), Some(RewritePolicy::Flag));
+        assert_eq!(
+            RewritePolicy::parse("clawback"),
+            Some(RewritePolicy::ClawBack)
+        );
+        assert_eq!(RewritePolicy::parse("nope"), None);
+    }
+
+    #[test]
+    fn last_valid_config_value_wins() {
+        assert_eq!(RewritePolicy::from_config([]), RewritePolicy::Flag);
+        assert_eq!(
+            RewritePolicy::from_config(["clawback", "bogus"]),
+            RewritePolicy::ClawBack
+        );
+        assert_eq!(
+            RewritePolicy::from_config(["clawback", "ignore"]),
+            RewritePolicy::Ignore
+        );
+    }
+}
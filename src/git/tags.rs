@@ -0,0 +1,279 @@
+//! Tags pushed to a remote. Pushing a tag doesn't move any local ref, so the
+//! reference-transaction hook never sees it; instead tags are read from the list of
+//! refs git hands the pre-push hook. That runs before anything is sent, and the push
+//! can still fail, so the tags are only noted there. They're credited once the remote
+//! is seen to have them: right after a push that moved a branch, or the next time
+//! party runs.
+
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use anyhow::Result;
+
+use crate::{
+    git::{self, Push, PushedTag, backfill::PastPush},
+    storage::{PendingTag, PushHistory, TagStore},
+};
+
+/// a pending tag that hasn't shown up on its remote by now was most likely rejected
+const PENDING_TAG_TTL_SECS: u64 = 24 * 60 * 60;
+
+/// tags the push is about to create on `remote`, as given to the pre-push hook along
+/// with its url. `input` is what git writes to the hook's stdin
+pub fn get_pending_tags(remote: &str, url: &str, input: &str, now: u64) -> Vec<PendingTag> {
+    let new_tags = parse_new_tags(input);
+    if new_tags.is_empty() {
+        return Vec::new();
+    }
+
+    let cwd = std::env::current_dir().expect("could not get current directory");
+    let Some(repo_path) = git::repo::git_work_dir(&cwd) else {
+        return Vec::new();
+    };
+
+    // stored under the same id as the remote's branches, even if it has a separate push url
+    let remote_url = git::detection::get_remote_ids(&repo_path)
+        .into_iter()
+        .find(|(r, _)| r == remote)
+        .map(|(_, id)| id)
+        .unwrap_or_else(|| git::remote::repo_id(url));
+
+    let shas: Vec<&str> = new_tags.iter().map(|(_, sha)| *sha).collect();
+    let targets = git::commands::get_tag_targets(&repo_path, &shas);
+
+    new_tags
+        .into_iter()
+        .filter_map(|(name, sha)| {
+            let (annotated, commit) = targets.get(sha)?;
+            crate::debug_log!(
+                "pre-push: new tag {} on {} (annotated: {})",
+                name,
+                commit,
+                annotated
+            );
+            Some(PendingTag {
+                repo_path: repo_path.to_string_lossy().into_owned(),
+                remote: remote.to_string(),
+                remote_url: remote_url.clone(),
+                name: name.to_string(),
+                object_sha: sha.to_string(),
+                commit_sha: commit.clone(),
+                annotated: *annotated,
+                pushed_at: now,
+            })
+        })
+        .collect()
+}
+
+/// takes the pending tags that have reached their remote, as one push per remote.
+/// `pushed_to` narrows it down to the tags for one repo and remote id, e.g. the one
+/// that was just pushed to, so no other remote is asked. a tag on a commit that
+/// already had one pushed to the same repo is marked as a re-tag, so moving or adding
+/// tags can't earn anything twice. tags that never made it are dropped after a while,
+/// and ones whose remote can't be reached right now are left for next time
+pub fn take_pushed_tags(
+    tags: &TagStore,
+    history: &PushHistory,
+    pushed_to: Option<(&Path, &str)>,
+    now: u64,
+) -> Result<Vec<PastPush>> {
+    let repo_path = pushed_to.map(|(p, _)| p.to_string_lossy().into_owned());
+    let mut pending = tags.pending(repo_path.as_deref())?;
+    if let Some((_, remote_url)) = pushed_to {
+        pending.retain(|t| t.remote_url == remote_url);
+    }
+    if pending.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    // one ls-remote per remote of each repo
+    let mut by_remote: HashMap<(&str, &str), Vec<&PendingTag>> = HashMap::new();
+    for tag in &pending {
+        if tag.pushed_at + PENDING_TAG_TTL_SECS < now {
+            crate::debug_log!("tags: giving up on {} pushed to {}", tag.name, tag.remote);
+            tags.remove_pending(tag)?;
+            continue;
+        }
+        by_remote
+            .entry((tag.repo_path.as_str(), tag.remote.as_str()))
+            .or_default()
+            .push(tag);
+    }
+
+    let mut pushed = Vec::new();
+    for ((repo_path, remote), candidates) in by_remote {
+        let names: Vec<&str> = candidates.iter().map(|t| t.name.as_str()).collect();
+        let Some(on_remote) = git::commands::get_remote_tags(Path::new(repo_path), remote, &names)
+        else {
+            crate::debug_log!("tags: could not reach {remote} from {repo_path}");
+            continue;
+        };
+        for tag in candidates {
+            if on_remote.get(&tag.name) == Some(&tag.object_sha) {
+                tags.remove_pending(tag)?;
+                pushed.push(tag);
+            }
+        }
+    }
+    // oldest first, as they were pushed
+    pushed.sort_by_key(|t| (t.pushed_at, t.name.as_str()));
+
+    let mut pushes: Vec<PastPush> = Vec::new();
+    let mut tagged = HashSet::new();
+    for tag in pushed {
+        let retag = !tagged.insert((tag.remote_url.as_str(), tag.commit_sha.as_str()))
+            || history.is_tagged(&tag.remote_url, &tag.commit_sha)?;
+        crate::debug_log!(
+            "tags: {} on {} reached {} (retag: {})",
+            tag.name,
+            tag.commit_sha,
+            tag.remote_url,
+            retag
+        );
+        let pushed_tag = PushedTag::new(&tag.name, &tag.commit_sha, tag.annotated, retag);
+
+        match pushes
+            .iter_mut()
+            .find(|p| p.push.remote_url() == tag.remote_url)
+        {
+            Some(past) => {
+                past.timestamp = past.timestamp.max(tag.pushed_at);
+                past.push.add_tag(pushed_tag);
+            }
+            None => pushes.push(PastPush {
+                timestamp: tag.pushed_at,
+                push: Push::from_tags(vec![pushed_tag], &tag.remote_url),
+            }),
+        }
+    }
+
+    Ok(pushes)
+}
+
+/// tags the push will create on the remote, as (tag name, local object sha).
+/// each line of input reads `<local ref> <local sha> <remote ref> <remote sha>`
+fn parse_new_tags(input: &str) -> Vec<(&str, &str)> {
+    input
+        .lines()
+        .filter_map(|line| {
+            let [_, local_sha, remote_ref, remote_sha] =
+                line.split_whitespace().collect::<Vec<_>>()[..]
+            else {
+                return None;
+            };
+            let name = remote_ref.strip_prefix("refs/tags/")?;
+
+            // deleting a tag, or moving one the remote already has
+            if git::is_null_sha(local_sha) || !git::is_null_sha(remote_sha) {
+                return None;
+            }
+            Some((name, local_sha))
+        })
+        .collect()
+}
+
+/// whether a tag name is a semantic version, optionally prefixed with "v".
+/// e.g. "v1.2.0", "0.3.1-beta.2", "2.0.0+build.5"
+pub fn is_semver(name: &str) -> bool {
+    let version = name.strip_prefix('v').unwrap_or(name);
+    let (version, build) = match version.split_once('+') {
+        Some((version, build)) => (version, Some(build)),
+        None => (version, None),
+    };
+    let (core, pre) = match version.split_once('-') {
+        Some((core, pre)) => (core, Some(pre)),
+        None => (version, None),
+    };
+
+    let numbers: Vec<&str> = core.split('.').collect();
+    numbers.len() == 3
+        && numbers.iter().all(|n| is_version_number(n))
+        && pre.is_none_or(is_identifiers)
+        && build.is_none_or(is_identifiers)
+}
+
+/// digits without leading zeros
+fn is_version_number(n: &str) -> bool {
+    !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()) && (n == "0" || !n.starts_with('0'))
+}
+
+/// dot-separated, non-empty runs of letters, digits and hyphens
+fn is_identifiers(s: &str) -> bool {
+    s.split('.')
+        .all(|id| !id.is_empty() && id.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn semver_names() {
+        for name in [
+            "v1.2.0",
+            "1.2.0",
+            "0.0.1",
+            "v2.0.0-rc.1",
+            "1.0.0+build.5",
+            "1.0.0-x-y.2+z",
+        ] {
+            assert!(is_semver(name), "{name} should be semver");
+        }
+        for name in [
+            "v1.2", "1.2.3.4", "release", "v01.2.3", "1.2.3-", "1.2.3+", "vv1.2.3", "1.2.x",
+        ] {
+            assert!(!is_semver(name), "{name} should not be semver");
+        }
+    }
+
+    #[test]
+    fn only_tags_new_to_the_remote_are_parsed() {
+        let zero = "0000000000000000000000000000000000000000";
+        let input = format!(
+            "refs/heads/main abc refs/heads/main {zero}\n\
+             refs/tags/v1.0.0 def refs/tags/v1.0.0 {zero}\n\
+             refs/tags/moved 123 refs/tags/moved 456\n\
+             (delete) {zero} refs/tags/gone 789\n\
+             HEAD fed refs/tags/latest {zero}\n"
+        );
+
+        assert_eq!(
+            parse_new_tags(&input),
+            vec![("v1.0.0", "def"), ("latest", "fed")]
+        );
+    }
+
+    #[test]
+    fn tags_that_never_reach_the_remote_are_given_up_on() {
+        let conn = crate::storage::DbConnection::create_in_memory().unwrap();
+        let tags = TagStore::new(&conn);
+        let history = PushHistory::new(&conn);
+        tags.add_pending(&PendingTag {
+            repo_path: "/no/such/repo".to_string(),
+            remote: "origin".to_string(),
+            remote_url: "github.com/me/x".to_string(),
+            name: "v1.0.0".to_string(),
+            object_sha: "abc".to_string(),
+            commit_sha: "def".to_string(),
+            annotated: true,
+            pushed_at: 100,
+        })
+        .unwrap();
+
+        // a day later it's still waiting, since the remote can't be asked
+        let now = 100 + PENDING_TAG_TTL_SECS;
+        assert!(
+            take_pushed_tags(&tags, &history, None, now)
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(tags.pending(None).unwrap().len(), 1);
+
+        assert!(
+            take_pushed_tags(&tags, &history, None, now + 1)
+                .unwrap()
+                .is_empty()
+        );
+        assert!(tags.pending(None).unwrap().is_empty());
+    }
+}
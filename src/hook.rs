@@ -1,56 +1,234 @@
+use std::io::Read;
+
 use anyhow::Result;
 
 use crate::{
     clock::Clock,
-    git,
+    git::{self, Push, backfill::PastPush},
     party::{self, RenderContext},
     scoring,
     state::State,
-    storage::{BranchRefsStore, PatchIdStore, PushEntry, PushHistory},
+    storage::{
+        BranchRefsStore, JjStore, PatchIdStore, PushEntry, PushHistory, TagStore, WatchStore,
+    },
 };
 
+/// set by the global hook when it hands off to a repo's own hook, which may run party too
+pub const SKIP_HOOK_ENV: &str = "PARTY_SKIP_HOOK";
+
 pub fn post_push(
     state: &mut State,
     branch_refs: &BranchRefsStore,
     history: &PushHistory,
     patch_ids: &PatchIdStore,
+    jj: &JjStore,
+    tags: &TagStore,
 ) -> Result<()> {
+    if std::env::var_os(SKIP_HOOK_ENV).is_some() {
+        return Ok(());
+    }
+
+    // jj keeps its own record of pushes. any that happened without the `jj push` alias
+    // are credited quietly, and the latest one gets the party. a plain `git push` in a
+    // colocated repo isn't in there, so without a new jj push it's found like in git
+    let cwd = std::env::current_dir()?;
+    if let Some(jj_root) = git::repo::find_jj_root(&cwd)
+        && let Some(mut pushes) = git::jj::find_new_pushes(&jj_root, jj, patch_ids, branch_refs)?
+    {
+        let Some(latest) = pushes.pop() else {
+            return Ok(());
+        };
+        for past in &pushes {
+            credit_quietly(state, history, past)?;
+        }
+        return celebrate(state, history, patch_ids, &latest.push);
+    }
+
+    let Some(repo_path) = git::repo::git_work_dir(&cwd) else {
+        return Ok(());
+    };
+
     // HACK: should we do something else if this fails?
-    let Some(push) = git::get_pushed_commits(branch_refs, patch_ids) else {
+    let Some(mut push) = git::get_pushed_commits(&repo_path, branch_refs, patch_ids) else {
         return Ok(());
     };
 
-    let clock = Clock::from_now();
+    // the remote took this push, so tags sent along with it have landed by now too and
+    // join its party. tags pushed anywhere else wait for the next catch-up
+    let now = Clock::from_now().now();
+    let pushed_to = Some((repo_path.as_path(), push.remote_url()));
+    for past in git::tags::take_pushed_tags(tags, history, pushed_to, now)? {
+        push = push.with_tags(past.push.tags());
+    }
+
+    celebrate(state, history, patch_ids, &push)
+}
+
+/// called from the pre-push hook with the remote's name and url, and git's list of refs
+/// to push on stdin. tags never show up in the reference-transaction hook, so they're
+/// noted here, and credited once the remote has them (see `git::tags`)
+pub fn tag_push(tags: &TagStore, remote: &str, url: &str) -> Result<()> {
+    if std::env::var_os(SKIP_HOOK_ENV).is_some() {
+        return Ok(());
+    }
+
+    let mut input = String::new();
+    std::io::stdin().read_to_string(&mut input)?;
+
+    let now = Clock::from_now().now();
+    for tag in git::tags::get_pending_tags(remote, url, &input, now) {
+        tags.add_pending(&tag)?;
+    }
+
+    Ok(())
+}
+
+/// credits tags that reached their remote since the pre-push hook noted them, which
+/// nothing else notices when a push creates tags without moving a branch
+pub fn tag_catch_up(state: &mut State, history: &PushHistory, tags: &TagStore) -> Result<()> {
+    let now = Clock::from_now().now();
+    let pushes = git::tags::take_pushed_tags(tags, history, None, now)?;
+    if pushes.is_empty() {
+        return Ok(());
+    }
+
+    let mut points = 0;
+    let mut count = 0;
+    for past in &pushes {
+        points += credit_quietly(state, history, past)?;
+        count += past.push.tags().len();
+    }
+    let tag_word = if count == 1 { "tag" } else { "tags" };
+    println!("credited {count} pushed {tag_word} for {points} party points");
+
+    Ok(())
+}
+
+/// scores a push, records it, and throws the party
+fn celebrate(
+    state: &mut State,
+    history: &PushHistory,
+    patch_ids: &PatchIdStore,
+    push: &Push,
+) -> Result<()> {
+    celebrate_at(state, history, patch_ids, push, &Clock::from_now())
+}
+
+/// throws the parties for pushes that `party watch` found while no one was looking.
+/// they're scored as of when they happened
+pub fn queued_parties(
+    state: &mut State,
+    history: &PushHistory,
+    patch_ids: &PatchIdStore,
+    watch: &WatchStore,
+) -> Result<()> {
+    let tz_offset_secs = Clock::from_now().tz_offset_secs();
+    for (timestamp, push) in watch.take_queued()? {
+        celebrate_at(
+            state,
+            history,
+            patch_ids,
+            &push,
+            &Clock::with_offset(timestamp, tz_offset_secs),
+        )?;
+    }
+
+    Ok(())
+}
 
-    let breakdown = scoring::calculate_points(&push, state, history, &clock);
+fn celebrate_at(
+    state: &mut State,
+    history: &PushHistory,
+    patch_ids: &PatchIdStore,
+    push: &Push,
+    clock: &Clock,
+) -> Result<()> {
+    let breakdown = scoring::calculate_points(push, state, history, clock);
     let packs_earned = state.earn_points(breakdown.total);
+    let clawed_back = state.claw_back(breakdown.clawed_back);
 
     // record push to history AFTER scoring so first_push_of_day
     // bonus can correctly detect if this is the first push today.
-    // only record if there are new commits - empty pushes (rebases) shouldn't
-    // affect bonus track calculations like first_push_of_day
-    if !push.branch().is_empty() && !push.commits().is_empty() {
-        let lines_changed: u64 = push.commits().iter().map(|c| c.lines_changed()).sum();
-        let entry = PushEntry::with_current_time(
-            push.remote_url().to_string(),
-            push.branch().to_string(),
-            push.commits().len() as u64,
-            lines_changed,
-            breakdown.total,
-        );
+    // only record if something happened - empty pushes (rebases) shouldn't
+    // affect bonus track calculations like first_push_of_day. bot commits are
+    // recorded for stats, but bonus tracks don't count them as pushes
+    if !push.commits().is_empty()
+        || !push.tags().is_empty()
+        || push.automated_commits() > 0
+        || breakdown.discarded > 0
+    {
+        let entry = PushEntry::from_push(clock.now(), push, breakdown.total)
+            .with_discarded(breakdown.discarded, clawed_back)
+            .with_commit_points(breakdown.commit_points.clone());
         history.record(&entry)?;
     }
 
-    let ctx = RenderContext::new(&push, history, &breakdown, state, &clock, packs_earned);
+    // a commit whose points were taken back can earn them again if it's pushed again
+    for (patch_id, points) in &breakdown.commit_points {
+        if *points < 0 {
+            patch_ids.forget(push.remote_url(), patch_id)?;
+        }
+    }
+
+    let ctx = RenderContext::new(push, history, &breakdown, state, clock, packs_earned);
     party::display(&ctx);
 
     Ok(())
 }
 
+/// credits any pushes made in the current jj repo since party last ran, such as ones
+/// made with `jj git push` directly rather than through the alias
+pub fn jj_catch_up(
+    state: &mut State,
+    branch_refs: &BranchRefsStore,
+    history: &PushHistory,
+    patch_ids: &PatchIdStore,
+    jj: &JjStore,
+) -> Result<()> {
+    let cwd = std::env::current_dir()?;
+    let Some(jj_root) = git::repo::find_jj_root(&cwd) else {
+        return Ok(());
+    };
+
+    let pushes =
+        git::jj::find_new_pushes(&jj_root, jj, patch_ids, branch_refs)?.unwrap_or_default();
+    if pushes.is_empty() {
+        return Ok(());
+    }
+
+    let mut points = 0;
+    for past in &pushes {
+        points += credit_quietly(state, history, past)?;
+    }
+    let push_word = if pushes.len() == 1 { "push" } else { "pushes" };
+    println!(
+        "credited {} jj {push_word} from outside party for {points} party points",
+        pushes.len()
+    );
+
+    Ok(())
+}
+
+/// scores a push as of when it happened and records it, without a party
+fn credit_quietly(state: &mut State, history: &PushHistory, past: &PastPush) -> Result<u64> {
+    let clock = Clock::with_offset(past.timestamp, Clock::from_now().tz_offset_secs());
+    let breakdown = scoring::calculate_points(&past.push, state, history, &clock);
+    state.earn_points(breakdown.total);
+    history.record(
+        &PushEntry::from_push(past.timestamp, &past.push, breakdown.total)
+            .with_commit_points(breakdown.commit_points),
+    )?;
+
+    Ok(breakdown.total)
+}
+
 /// stores ref start before pushing commits (only used in jj integration)
 pub fn pre_push(branch_refs: &BranchRefsStore) -> Result<()> {
     let cwd = std::env::current_dir().expect("could not get current directory");
-    git::snapshot_refs(&cwd, branch_refs)?;
+    let Some(work_dir) = git::repo::git_work_dir(&cwd) else {
+        return Ok(());
+    };
+    git::snapshot_refs(&work_dir, branch_refs)?;
 
     Ok(())
 }
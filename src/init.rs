@@ -6,9 +6,11 @@ use std::{
 use anyhow::{Context, Result, anyhow};
 
 use crate::{
-    git,
+    clock::Clock,
+    git::{self, repo::GitRepo},
+    hook::SKIP_HOOK_ENV,
     state::{self, State},
-    storage::BranchRefsStore,
+    storage::{self, BranchRefsStore, JjStore, RepoEntry, RepoRegistry, Vcs},
 };
 
 const STARTER_POINTS: u64 = 10;
@@ -20,23 +22,250 @@ enum RepoType {
 }
 
 const GIT_HOOK_SCRIPT: &str = r#"#!/bin/sh
+if [ "$1" = "committed" ]; then
+    party hook </dev/null
+fi
+"#;
+
+/// what `GIT_HOOK_SCRIPT` used to be, before it stopped handing git's list of ref
+/// updates on to party
+const OLD_GIT_HOOK_SCRIPT: &str = r#"#!/bin/sh
 if [ "$1" = "committed" ]; then
     party hook
 fi
 "#;
 
+/// what gets added to a hook that existed before party was installed
+const HOOK_BLOCK: &str = r#"# >>> post-push-party >>>
+if [ "$1" = "committed" ]; then
+    party hook </dev/null
+fi
+# <<< post-push-party <<<"#;
+
+const PRE_PUSH_HOOK_SCRIPT: &str = r#"#!/bin/sh
+party pre-push "$@"
+"#;
+
+/// the refs being pushed arrive on stdin, so the block saves them for party and then
+/// hands them on to the rest of the hook
+const PRE_PUSH_HOOK_BLOCK: &str = r#"# >>> post-push-party >>>
+party_refs="$(mktemp)"
+cat >"$party_refs"
+party pre-push "$@" <"$party_refs"
+exec <"$party_refs"
+rm -f "$party_refs"
+# <<< post-push-party <<<"#;
+
+/// one of the git hooks party runs from
+struct PartyHook {
+    name: &'static str,
+
+    /// written when the repo doesn't have this hook yet
+    script: &'static str,
+
+    /// what earlier versions wrote instead of `script`, still recognized as party's own
+    old_scripts: &'static [&'static str],
+
+    /// added to a hook that existed before party was installed
+    block: &'static str,
+}
+
+/// pushed branches move remote-tracking refs, which this hook sees once the push is done
+const REF_TRANS_HOOK: PartyHook = PartyHook {
+    name: REF_TRANS_HOOK_NAME,
+    script: GIT_HOOK_SCRIPT,
+    old_scripts: &[OLD_GIT_HOOK_SCRIPT],
+    block: HOOK_BLOCK,
+};
+
+/// pushed tags don't move any local ref, so they're noted just before the push and
+/// credited once the remote has them
+const PRE_PUSH_HOOK: PartyHook = PartyHook {
+    name: "pre-push",
+    script: PRE_PUSH_HOOK_SCRIPT,
+    old_scripts: &[],
+    block: PRE_PUSH_HOOK_BLOCK,
+};
+
+const PARTY_HOOKS: &[PartyHook] = &[REF_TRANS_HOOK, PRE_PUSH_HOOK];
+
+impl PartyHook {
+    /// whether `content` is a whole hook that party wrote, now or in an earlier version
+    fn is_own_script(&self, content: &str) -> bool {
+        content == self.script || self.old_scripts.contains(&content)
+    }
+}
+
+/// name of a wrapped hook inside e.g. `reference-transaction.d/`
+const WRAPPED_HOOK_NAME: &str = "original";
+
+/// the hooks installed into `core.hooksPath`. git only looks in the global hooks dir
+/// then, so each one hands off to the repo's own hook. hooks where a stand-in could
+/// change what git does, like push-to-checkout, proc-receive, fsmonitor-watchman and
+/// git-p4's, are left out, so a repo's own copies of those don't run
+const GIT_HOOK_NAMES: &[&str] = &[
+    "applypatch-msg",
+    "pre-applypatch",
+    "post-applypatch",
+    "pre-commit",
+    "pre-merge-commit",
+    "prepare-commit-msg",
+    "commit-msg",
+    "post-commit",
+    "pre-rebase",
+    "post-checkout",
+    "post-merge",
+    "pre-push",
+    "pre-receive",
+    "update",
+    "post-receive",
+    "post-update",
+    "reference-transaction",
+    "pre-auto-gc",
+    "post-rewrite",
+    "sendemail-validate",
+    "post-index-change",
+];
+
+const REF_TRANS_HOOK_NAME: &str = "reference-transaction";
+
 const JJ_PUSH_CMD: &str = "[\"util\", \"exec\", \"--\", \"bash\", \"-c\", \"party snapshot && jj git push \\\"$@\\\" && party hook\", \"--\"]";
 
-pub fn run(state: &mut State, branch_refs: &BranchRefsStore<'_>) -> Result<()> {
+pub fn run(
+    state: &mut State,
+    branch_refs: &BranchRefsStore<'_>,
+    jj: &JjStore<'_>,
+    repos: &RepoRegistry<'_>,
+) -> Result<()> {
     let cwd = std::env::current_dir().context("could not get current directory")?;
 
     install_party_hook(&cwd).context("could not install party hook")?;
     println!("installed party hook");
     println!("push code to earn party points!");
 
-    // snapshot current refs so we don't credit pre-existing commits
-    git::snapshot_refs(&cwd, branch_refs)?;
+    snapshot(&cwd, branch_refs, jj)?;
+    if let Some(entry) = repo_entry(&cwd) {
+        repos.register(&entry)?;
+    }
+
+    give_starter_points(state);
+
+    Ok(())
+}
 
+/// snapshots current refs (and for jj, the operation log) so we don't credit
+/// pre-existing commits
+pub fn snapshot(cwd: &Path, branch_refs: &BranchRefsStore<'_>, jj: &JjStore<'_>) -> Result<()> {
+    let work_dir = git::repo::git_work_dir(cwd).context("could not find git repository")?;
+    git::snapshot_refs(&work_dir, branch_refs)?;
+    if let Some(jj_root) = git::repo::find_jj_root(cwd) {
+        git::jj::snapshot_operations(&jj_root, jj)?;
+    }
+    Ok(())
+}
+
+/// the repo containing `cwd`, as it's kept in the registry
+fn repo_entry(cwd: &Path) -> Option<RepoEntry> {
+    let (root, vcs) = match detect_repo_type(cwd)? {
+        RepoType::Jj => (git::repo::find_jj_root(cwd)?, Vcs::Jj),
+        RepoType::Git => (GitRepo::find(cwd)?.toplevel, Vcs::Git),
+    };
+    let work_dir = git::repo::git_work_dir(cwd)?;
+    let root = root.canonicalize().unwrap_or(root);
+
+    Some(RepoEntry {
+        path: root.to_string_lossy().into_owned(),
+        vcs,
+        remote_urls: git::detection::get_remote_ids(&work_dir)
+            .into_iter()
+            .map(|(_, url)| url)
+            .collect(),
+        added_at: Clock::from_now().now(),
+        installed: true,
+        watched: false,
+    })
+}
+
+/// how party's hook looks in a registered repo
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookHealth {
+    Installed,
+
+    /// installed before tags were tracked, so without the pre-push hook
+    Partial,
+
+    /// the repo is there, but the hook (or jj alias) isn't
+    Missing,
+
+    /// the repo isn't there anymore
+    Gone,
+}
+
+impl HookHealth {
+    pub fn label(self) -> &'static str {
+        match self {
+            HookHealth::Installed => "ok",
+            HookHealth::Partial => "no tags",
+            HookHealth::Missing => "missing",
+            HookHealth::Gone => "gone",
+        }
+    }
+}
+
+pub fn hook_health(path: &Path, vcs: Vcs) -> HookHealth {
+    if !path.is_dir() {
+        return HookHealth::Gone;
+    }
+
+    match vcs {
+        Vcs::Jj if get_jj_push_config(path).is_some_and(|cmd| cmd == JJ_PUSH_CMD) => {
+            HookHealth::Installed
+        }
+        Vcs::Jj if path.join(".jj").is_dir() => HookHealth::Missing,
+        Vcs::Jj => HookHealth::Gone,
+        Vcs::Git => {
+            let Some(repo) = GitRepo::find(path) else {
+                return HookHealth::Gone;
+            };
+            let dir = repo.hooks_dir();
+            let installed = |hook: &PartyHook| {
+                std::fs::read_to_string(dir.join(hook.name)).is_ok_and(|content| {
+                    hook.is_own_script(&content) || content.contains(hook.block)
+                })
+            };
+            match (installed(&REF_TRANS_HOOK), installed(&PRE_PUSH_HOOK)) {
+                (true, true) => HookHealth::Installed,
+                (true, false) => HookHealth::Partial,
+                (false, _) => HookHealth::Missing,
+            }
+        }
+    }
+}
+
+pub fn run_global(state: &mut State) -> Result<()> {
+    let dir = storage::global_hooks_dir()?;
+
+    if let Some(existing) = get_global_hooks_path()?
+        && Path::new(&existing) != dir
+    {
+        return Err(anyhow!(
+            "core.hooksPath is already set globally to {existing}\nto install party globally, unset it first or run `party init` in each repo instead"
+        ));
+    }
+
+    install_global_hooks(&dir).context("could not install global hooks")?;
+    set_global_hooks_path(&dir).context("could not set core.hooksPath")?;
+    println!("installed party hooks for every repo");
+    println!("hooks in each repo's .git/hooks will keep running");
+    println!("push code to earn party points!");
+
+    // repos are snapshotted the first time the hook sees them, so nothing to do here
+    give_starter_points(state);
+
+    Ok(())
+}
+
+fn give_starter_points(state: &mut State) {
     // give starter points on first init
     if *state == state::State::default() {
         state.party_points = STARTER_POINTS;
@@ -44,36 +273,59 @@ pub fn run(state: &mut State, branch_refs: &BranchRefsStore<'_>) -> Result<()> {
         println!("🎁 You got {} starter party points!", STARTER_POINTS);
         println!("Run `party` to spend them!");
     }
-
-    Ok(())
 }
 
-pub fn run_uninit() -> Result<()> {
+pub fn run_uninit(repos: &RepoRegistry<'_>) -> Result<()> {
     let cwd = std::env::current_dir().context("could not get current directory")?;
 
     uninstall_party_hook(&cwd).context("could not uninstall party hook")?;
+    if let Some(entry) = repo_entry(&cwd) {
+        repos.unregister(&entry.path)?;
+    }
 
     println!("removed party hook");
 
     Ok(())
 }
 
-fn detect_repo_type(cwd: &Path) -> Option<RepoType> {
-    let jj_dir = cwd.join(".jj");
-    let git_dir = cwd.join(".git");
+pub fn run_uninit_global() -> Result<()> {
+    let dir = storage::global_hooks_dir()?;
+
+    match get_global_hooks_path()? {
+        Some(existing) if Path::new(&existing) == dir => {
+            unset_global_hooks_path().context("could not unset core.hooksPath")?
+        }
+        Some(existing) => {
+            return Err(anyhow!(
+                "core.hooksPath is set to {existing}, not party's hooks, so leaving it alone"
+            ));
+        }
+        None if !dir.exists() => return Err(anyhow!("party is not installed globally")),
+        None => {}
+    }
+
+    uninstall_global_hooks(&dir).context("could not remove global hooks")?;
+    println!("removed global party hooks");
 
-    if jj_dir.is_dir() {
+    Ok(())
+}
+
+fn detect_repo_type(cwd: &Path) -> Option<RepoType> {
+    // a colocated jj repo is also a git repo, so jj is checked first
+    if git::repo::find_jj_root(cwd).is_some() {
         Some(RepoType::Jj)
-    } else if git_dir.is_dir() {
+    } else if GitRepo::find(cwd).is_some() {
         Some(RepoType::Git)
     } else {
         None
     }
 }
 
-fn git_store_path(cwd: &Path) -> Result<PathBuf> {
+fn git_hooks_dir(cwd: &Path) -> Result<PathBuf> {
     match detect_repo_type(cwd) {
-        Some(RepoType::Git) => Ok(cwd.join(".git")),
+        Some(RepoType::Git) => GitRepo::find(cwd)
+            .map(|repo| repo.hooks_dir())
+            .ok_or_else(|| anyhow!("not a git or jj repository")),
         // NOTE: technically this branch is unreachable because we currently
         // install the hook for jj via a `jj push` alias, not the ref-trans hook
         //
@@ -81,32 +333,17 @@ fn git_store_path(cwd: &Path) -> Result<PathBuf> {
         // (such as if jj ever adds native hook support, or at least changes to piping
         // git hook output directly to stdout)
         Some(RepoType::Jj) => {
-            let path_to_git_target = jj_git_target_path(cwd);
-            let path_to_store_from_target_dir = std::fs::read_to_string(path_to_git_target)?;
-            let path_to_git_store =
-                jj_repo_store_path(cwd).join(path_to_store_from_target_dir.trim());
-            Ok(path_to_git_store.canonicalize()?)
+            let jj_root = git::repo::find_jj_root(cwd)
+                .ok_or_else(|| anyhow!("not a git or jj repository"))?;
+            Ok(git::repo::jj_git_dir(&jj_root)?.join("hooks"))
         }
         None => Err(anyhow!("not a git or jj repository")),
     }
 }
 
-fn git_hook_path(cwd: &Path) -> Result<PathBuf> {
-    let git_store_path = git_store_path(cwd)?;
-    Ok(git_store_path.join("hooks/reference-transaction"))
-}
-
-fn jj_git_target_path(repo_path: &Path) -> PathBuf {
-    jj_repo_store_path(repo_path).join("git_target")
-}
-
-fn jj_repo_store_path(repo_path: &Path) -> PathBuf {
-    repo_path.join(".jj/repo/store")
-}
-
 fn install_party_hook(cwd: &Path) -> Result<()> {
     match detect_repo_type(cwd) {
-        Some(RepoType::Git) => install_git_ref_trans_hook(cwd),
+        Some(RepoType::Git) => install_git_hooks(cwd),
         Some(RepoType::Jj) => install_jj_push_config(cwd),
         None => Err(anyhow!("not a git or jj repository")),
     }
@@ -158,28 +395,107 @@ fn remove_jj_push_config(cwd: &Path) -> Result<()> {
     Ok(())
 }
 
-fn install_git_ref_trans_hook(cwd: &Path) -> Result<()> {
-    let path = git_hook_path(cwd)?;
-    if path.exists() {
-        return Err(anyhow!(
-            "git hook already exists at {}\nto install party in this repo, please add the following to your existing hook:\n\n{GIT_HOOK_SCRIPT}",
-            path.display()
-        ));
+fn install_git_hooks(cwd: &Path) -> Result<()> {
+    let dir = git_hooks_dir(cwd)?;
+    for hook in PARTY_HOOKS {
+        install_git_hook(&dir.join(hook.name), hook)?;
+    }
+    Ok(())
+}
+
+fn install_git_hook(path: &Path, hook: &PartyHook) -> Result<()> {
+    if !path.exists() {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, hook.script)?;
+        set_executable(path)?;
+        return Ok(());
     }
 
-    if let Some(parent) = path.parent() {
-        std::fs::create_dir_all(parent)?;
+    match std::fs::read_to_string(path).ok() {
+        // already installed, nothing to do
+        Some(content) if content == hook.script || content.contains(hook.block) => Ok(()),
+
+        // installed by an earlier version, so bring it up to date
+        Some(content) if hook.is_own_script(&content) => {
+            std::fs::write(path, hook.script)?;
+            Ok(())
+        }
+
+        Some(content) if is_shell_script(&content) => {
+            std::fs::write(path, insert_hook_block(&content, hook.block))?;
+            println!("added party to existing hook at {}", path.display());
+            Ok(())
+        }
+
+        // not something we can safely edit, so run it from a wrapper instead
+        _ => {
+            let moved = wrapped_hook_path(path);
+            if moved.exists() {
+                return Err(anyhow!(
+                    "cannot wrap existing git hook, {} already exists",
+                    moved.display()
+                ));
+            }
+            if let Some(parent) = moved.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::rename(path, &moved)?;
+            std::fs::write(path, wrapper_hook_script(hook.block))?;
+            set_executable(path)?;
+            println!("moved existing hook to {}", moved.display());
+            Ok(())
+        }
     }
-    std::fs::write(&path, GIT_HOOK_SCRIPT)?;
+}
+
+fn set_executable(path: &Path) -> Result<()> {
     #[cfg(unix)]
     {
         use std::os::unix::fs::PermissionsExt;
         std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755))?;
     }
-
+    #[cfg(not(unix))]
+    let _ = path;
     Ok(())
 }
 
+/// whether we know how to insert the party block into this hook
+fn is_shell_script(content: &str) -> bool {
+    let Some(shebang) = content.lines().next().and_then(|l| l.strip_prefix("#!")) else {
+        return false;
+    };
+
+    let mut parts = shebang.split_whitespace();
+    let mut interpreter = parts.next().unwrap_or_default();
+    if interpreter.ends_with("/env") {
+        interpreter = parts.next().unwrap_or_default();
+    }
+
+    let name = interpreter.rsplit('/').next().unwrap_or_default();
+    matches!(name, "sh" | "bash" | "dash" | "ksh" | "zsh")
+}
+
+/// puts the party block right after the shebang line, before anything in the
+/// hook has a chance to `exit` or `exec`.
+/// removing `"\n" + block` gives back the original byte for byte
+fn insert_hook_block(content: &str, block: &str) -> String {
+    let at = content.find('\n').unwrap_or(content.len());
+    format!("{}\n{block}{}", &content[..at], &content[at..])
+}
+
+/// where a non-shell hook is moved when we wrap it
+fn wrapped_hook_path(hook_path: &Path) -> PathBuf {
+    let mut dir = hook_path.as_os_str().to_owned();
+    dir.push(".d");
+    PathBuf::from(dir).join(WRAPPED_HOOK_NAME)
+}
+
+fn wrapper_hook_script(block: &str) -> String {
+    format!("#!/bin/sh\n{block}\nexec \"$0.d/{WRAPPED_HOOK_NAME}\" \"$@\"\n")
+}
+
 fn install_jj_push_config(cwd: &Path) -> Result<()> {
     let push_cmd = get_jj_push_config(cwd);
 
@@ -203,25 +519,131 @@ fn install_jj_push_config(cwd: &Path) -> Result<()> {
     }
 }
 
-fn uninstall_party_hook(cwd: &Path) -> Result<()> {
+/// hands off to the repo's own hook of the same name. the ones party runs from also
+/// get the party block, and tell a local party hook not to run a second time
+fn delegating_hook_script(name: &str) -> String {
+    let party_hook = PARTY_HOOKS.iter().find(|hook| hook.name == name);
+    let skip = if party_hook.is_some() {
+        format!("{SKIP_HOOK_ENV}=1 ")
+    } else {
+        String::new()
+    };
+    let script = format!(
+        r#"#!/bin/sh
+# installed by `party init --global`, runs this repo's own hook if it has one
+hook="$(git rev-parse --git-common-dir)/hooks/$(basename "$0")"
+if [ -x "$hook" ]; then
+    {skip}exec "$hook" "$@"
+fi
+"#
+    );
+
+    match party_hook {
+        Some(hook) => insert_hook_block(&script, hook.block),
+        None => script,
+    }
+}
+
+fn install_global_hooks(dir: &Path) -> Result<()> {
+    std::fs::create_dir_all(dir)?;
+    for name in GIT_HOOK_NAMES {
+        let path = dir.join(name);
+        std::fs::write(&path, delegating_hook_script(name))?;
+        set_executable(&path)?;
+    }
+    Ok(())
+}
+
+fn uninstall_global_hooks(dir: &Path) -> Result<()> {
+    for name in GIT_HOOK_NAMES {
+        let path = dir.join(name);
+        if path.exists() {
+            std::fs::remove_file(path)?;
+        }
+    }
+    // leave the directory alone if anything else lives there
+    let _ = std::fs::remove_dir(dir);
+    Ok(())
+}
+
+fn get_global_hooks_path() -> Result<Option<String>> {
+    let output = Command::new("git")
+        .args(["config", "--global", "--get", "core.hooksPath"])
+        .output()?;
+    if output.status.success() {
+        Ok(Some(String::from_utf8(output.stdout)?.trim().to_string()))
+    } else {
+        Ok(None)
+    }
+}
+
+fn set_global_hooks_path(dir: &Path) -> Result<()> {
+    let output = Command::new("git")
+        .args(["config", "--global", "core.hooksPath"])
+        .arg(dir)
+        .output()?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(anyhow!("git config failed: {}", stderr.trim()));
+    }
+    Ok(())
+}
+
+fn unset_global_hooks_path() -> Result<()> {
+    let output = Command::new("git")
+        .args(["config", "--global", "--unset", "core.hooksPath"])
+        .output()?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(anyhow!("git config failed: {}", stderr.trim()));
+    }
+    Ok(())
+}
+
+pub fn uninstall_party_hook(cwd: &Path) -> Result<()> {
     match detect_repo_type(cwd) {
-        Some(RepoType::Git) => uninstall_git_ref_trans_hook(cwd),
+        Some(RepoType::Git) => uninstall_git_hooks(cwd),
         Some(RepoType::Jj) => uninstall_jj_push_config(cwd),
         None => Err(anyhow!("not a git or jj repository")),
     }
 }
 
-fn uninstall_git_ref_trans_hook(cwd: &Path) -> Result<()> {
-    let path = git_hook_path(cwd)?;
+fn uninstall_git_hooks(cwd: &Path) -> Result<()> {
+    let dir = git_hooks_dir(cwd)?;
 
-    if !path.exists() {
+    let ref_trans = dir.join(REF_TRANS_HOOK.name);
+    if !ref_trans.exists() {
         return Err(anyhow!("party hook not installed in this repo"));
     }
+    uninstall_git_hook(&ref_trans, &REF_TRANS_HOOK)?;
+
+    // repos set up before tags were tracked won't have this one
+    let pre_push = dir.join(PRE_PUSH_HOOK.name);
+    if pre_push.exists() {
+        uninstall_git_hook(&pre_push, &PRE_PUSH_HOOK)?;
+    }
+
+    Ok(())
+}
 
-    let content = std::fs::read_to_string(&path)?;
-    if content == GIT_HOOK_SCRIPT {
+fn uninstall_git_hook(path: &Path, hook: &PartyHook) -> Result<()> {
+    let content = std::fs::read_to_string(path).unwrap_or_default();
+    let moved = wrapped_hook_path(path);
+    let inserted = format!("\n{}", hook.block);
+
+    if hook.is_own_script(&content) {
         std::fs::remove_file(path)?;
         Ok(())
+    } else if content == wrapper_hook_script(hook.block) && moved.exists() {
+        std::fs::rename(&moved, path)?;
+        if let Some(dir) = moved.parent() {
+            // leave the directory alone if anything else lives there
+            let _ = std::fs::remove_dir(dir);
+        }
+        Ok(())
+    } else if content.matches(&inserted).count() == 1 {
+        std::fs::write(path, content.replacen(&inserted, "", 1))?;
+        Ok(())
     } else {
         Err(anyhow!(
             "hook has been modified, please remove manually\nedit: {}",
@@ -282,6 +704,10 @@ mod tests {
         Ok(())
     }
 
+    fn git_hook_path(cwd: &Path) -> Result<PathBuf> {
+        Ok(git_hooks_dir(cwd)?.join(REF_TRANS_HOOK_NAME))
+    }
+
     fn git_ref_trans_contents(cwd: &Path) -> Result<String> {
         Ok(fs::read_to_string(git_hook_path(cwd)?)?)
     }
@@ -289,15 +715,29 @@ mod tests {
     #[test]
     fn detect_git_repo() {
         let dir = tempdir().unwrap();
-        fs::create_dir(dir.path().join(".git")).unwrap();
+        git_init(dir.path()).unwrap();
 
         assert_eq!(detect_repo_type(dir.path()), Some(RepoType::Git));
     }
 
+    #[test]
+    fn detect_git_repo_from_subdirectory() {
+        let dir = tempdir().unwrap();
+        git_init(dir.path()).unwrap();
+        let subdir = dir.path().join("src/nested");
+        fs::create_dir_all(&subdir).unwrap();
+
+        assert_eq!(detect_repo_type(&subdir), Some(RepoType::Git));
+        assert_eq!(
+            git_hook_path(&subdir).unwrap(),
+            git_hook_path(dir.path()).unwrap()
+        );
+    }
+
     #[test]
     fn detect_jj_repo() {
         let dir = tempdir().unwrap();
-        fs::create_dir(dir.path().join(".jj")).unwrap();
+        jj_init_no_colocate(dir.path()).unwrap();
 
         assert_eq!(detect_repo_type(dir.path()), Some(RepoType::Jj));
     }
@@ -305,8 +745,7 @@ mod tests {
     #[test]
     fn jj_takes_precedence_over_git() {
         let dir = tempdir().unwrap();
-        fs::create_dir(dir.path().join(".git")).unwrap();
-        fs::create_dir(dir.path().join(".jj")).unwrap();
+        jj_init(dir.path()).unwrap();
 
         assert_eq!(detect_repo_type(dir.path()), Some(RepoType::Jj));
     }
@@ -358,15 +797,51 @@ mod tests {
         let cwd = dir.path();
         git_init(cwd).unwrap();
 
-        // write a modified hook
+        // write an existing hook
         let content = "#!/bin/sh\nsome_command\nsome_other_command";
         fs::write(git_hook_path(cwd).unwrap(), content).unwrap();
 
-        let result = install_party_hook(cwd);
-        assert!(result.is_err());
+        install_party_hook(cwd).unwrap();
 
         let new_content = git_ref_trans_contents(cwd).unwrap();
-        assert_eq!(new_content, content);
+        assert!(new_content.contains("party hook"));
+        assert!(new_content.starts_with("#!/bin/sh\n"));
+        assert!(new_content.ends_with("\nsome_command\nsome_other_command"));
+    }
+
+    #[test]
+    fn init_twice_does_not_duplicate_hook_block_git() {
+        let dir = tempdir().unwrap();
+        let cwd = dir.path();
+        git_init(cwd).unwrap();
+
+        fs::write(git_hook_path(cwd).unwrap(), "#!/bin/bash\nlefthook run\n").unwrap();
+
+        install_party_hook(cwd).unwrap();
+        let once = git_ref_trans_contents(cwd).unwrap();
+        install_party_hook(cwd).unwrap();
+
+        assert_eq!(git_ref_trans_contents(cwd).unwrap(), once);
+    }
+
+    #[test]
+    fn shell_script_detection() {
+        assert!(is_shell_script("#!/bin/sh\n"));
+        assert!(is_shell_script("#!/usr/bin/env bash\necho hi"));
+        assert!(is_shell_script("#!/bin/zsh -e\n"));
+        assert!(!is_shell_script("#!/usr/bin/env python3\n"));
+        assert!(!is_shell_script("echo no shebang\n"));
+        assert!(!is_shell_script(""));
+    }
+
+    #[test]
+    fn inserted_hook_block_comes_right_after_shebang() {
+        let inserted = insert_hook_block("#!/bin/sh\nexit 0\n", HOOK_BLOCK);
+        assert_eq!(inserted, format!("#!/bin/sh\n{HOOK_BLOCK}\nexit 0\n"));
+
+        // no trailing newline at all
+        let inserted = insert_hook_block("#!/bin/sh", HOOK_BLOCK);
+        assert_eq!(inserted, format!("#!/bin/sh\n{HOOK_BLOCK}"));
     }
 
     #[test]
@@ -416,6 +891,41 @@ mod tests {
         assert!(!git_hook_path(cwd).unwrap().exists());
     }
 
+    #[test]
+    fn hooks_from_earlier_versions_are_updated_and_removed_git() {
+        let dir = tempdir().unwrap();
+        let cwd = dir.path();
+        git_init(cwd).unwrap();
+        let path = git_hook_path(cwd).unwrap();
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, OLD_GIT_HOOK_SCRIPT).unwrap();
+
+        install_party_hook(cwd).unwrap();
+        assert_eq!(git_ref_trans_contents(cwd).unwrap(), GIT_HOOK_SCRIPT);
+
+        fs::write(&path, OLD_GIT_HOOK_SCRIPT).unwrap();
+        uninstall_party_hook(cwd).unwrap();
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn init_installs_pre_push_hook_git() {
+        let dir = tempdir().unwrap();
+        let cwd = dir.path();
+        git_init(cwd).unwrap();
+
+        install_party_hook(cwd).unwrap();
+        let pre_push = git_hooks_dir(cwd).unwrap().join("pre-push");
+        assert!(
+            fs::read_to_string(&pre_push)
+                .unwrap()
+                .contains("party pre-push")
+        );
+
+        uninstall_party_hook(cwd).unwrap();
+        assert!(!pre_push.exists());
+    }
+
     #[test]
     fn uninit_removes_hook_jj() {
         let dir = tempdir().unwrap();
@@ -464,6 +974,41 @@ mod tests {
         assert!(git_hook_path(cwd).unwrap().exists());
     }
 
+    #[test]
+    fn global_hooks_delegate_to_repo_hooks() {
+        let dir = tempdir().unwrap();
+        install_global_hooks(dir.path()).unwrap();
+
+        for name in GIT_HOOK_NAMES {
+            let content = fs::read_to_string(dir.path().join(name)).unwrap();
+            assert!(content.contains("hooks/$(basename \"$0\")"));
+            assert_eq!(
+                content.contains("post-push-party"),
+                PARTY_HOOKS.iter().any(|hook| hook.name == *name),
+                "only the hooks party runs from should have the party block ({name})"
+            );
+        }
+        assert!(!dir.path().join("push-to-checkout").exists());
+
+        uninstall_global_hooks(dir.path()).unwrap();
+        assert!(!dir.path().exists());
+    }
+
+    #[test]
+    fn uninit_restores_existing_hook_git() {
+        let dir = tempdir().unwrap();
+        let cwd = dir.path();
+        git_init(cwd).unwrap();
+
+        let content = "#!/bin/sh\r\nsome_command\n\nexit 0";
+        fs::write(git_hook_path(cwd).unwrap(), content).unwrap();
+
+        install_party_hook(cwd).unwrap();
+        uninstall_party_hook(cwd).unwrap();
+
+        assert_eq!(git_ref_trans_contents(cwd).unwrap(), content);
+    }
+
     #[test]
     fn uninit_when_modified_jj() {
         let dir = tempdir().unwrap();
@@ -1,3 +1,5 @@
+mod backfill;
+mod balance;
 mod bonus_track;
 mod cli;
 mod clock;
@@ -9,17 +11,24 @@ mod hook;
 mod init;
 mod pack;
 mod party;
+mod receive;
+mod repos;
 mod scoring;
+mod simulate;
 mod state;
 mod storage;
 mod tui;
+mod watch;
 
 use clap::Parser;
 use cli::{Cli, Command};
 
 use crate::{
     state::State,
-    storage::{BranchRefsStore, DbConnection, PatchIdStore, PushHistory},
+    storage::{
+        BranchRefsStore, DbConnection, JjStore, PatchIdStore, PushHistory, RepoRegistry, TagStore,
+        WatchStore,
+    },
 };
 
 fn main() -> anyhow::Result<()> {
@@ -31,17 +40,110 @@ fn main() -> anyhow::Result<()> {
     let branch_refs = BranchRefsStore::new(&conn);
     let history = PushHistory::new(&conn);
     let patch_ids = PatchIdStore::new(&conn);
+    let jj = JjStore::new(&conn);
+    let watch = WatchStore::new(&conn);
+    let repos = RepoRegistry::new(&conn);
+    let tags = TagStore::new(&conn);
+
+    // the balance and custom bonuses files only matter to commands that score or show
+    // points. a broken one shouldn't stop anyone from pushing, so it's only pointed out
+    // where someone is looking, not on every hook call
+    let shows_points = matches!(
+        cli.command,
+        None | Some(Command::Points | Command::Stats | Command::Simulate { .. })
+    );
+    let scores = matches!(
+        cli.command,
+        Some(Command::Hook | Command::Pending | Command::Receive { .. } | Command::Backfill { .. })
+    );
+    #[cfg(feature = "dev")]
+    let shows_points = shows_points || cli.command.as_ref().is_some_and(Command::is_dev);
+    if shows_points || scores {
+        load_economy(shows_points);
+    }
+
+    // pushes made with plain `jj git push` are found in jj's operation log next time
+    // party looks at the repo. failing to read it shouldn't get in the way
+    if matches!(cli.command, None | Some(Command::Points | Command::Stats))
+        && let Err(e) = hook::jj_catch_up(&mut state, &branch_refs, &history, &patch_ids, &jj)
+    {
+        crate::debug_log!("could not catch up on jj pushes: {e:#}");
+    }
+
+    // likewise for tags that were pushed without moving a branch, once the remote has them
+    if matches!(cli.command, None | Some(Command::Points | Command::Stats))
+        && let Err(e) = hook::tag_catch_up(&mut state, &history, &tags)
+    {
+        crate::debug_log!("could not catch up on pushed tags: {e:#}");
+    }
 
     match cli.command {
-        Some(Command::Init) => init::run(&mut state, &branch_refs)?,
-        Some(Command::Uninit) => init::run_uninit()?,
+        Some(Command::Init { global: false }) => init::run(&mut state, &branch_refs, &jj, &repos)?,
+        Some(Command::Init { global: true }) => init::run_global(&mut state)?,
+        Some(Command::Uninit { global: false }) => init::run_uninit(&repos)?,
+        Some(Command::Uninit { global: true }) => init::run_uninit_global()?,
+        Some(Command::Repos { prune: true, .. }) => repos::prune(&repos)?,
+        Some(Command::Repos { snapshot: true, .. }) => repos::snapshot(&repos, &branch_refs, &jj)?,
+        Some(Command::Repos {
+            uninstall: true, ..
+        }) => repos::uninstall(&repos)?,
+        Some(Command::Repos { .. }) => repos::list(&repos)?,
+        Some(Command::Backfill { award_points }) => {
+            backfill::run(&mut state, &history, &patch_ids, award_points)?
+        }
+        Some(Command::Watch {
+            add: Some(path), ..
+        }) => watch::add(&path, &branch_refs, &patch_ids, &watch, &repos)?,
+        Some(Command::Watch {
+            remove: Some(path), ..
+        }) => watch::remove(&path, &repos)?,
+        Some(Command::Watch { list: true, .. }) => watch::list(&repos)?,
+        Some(Command::Watch { interval, once, .. }) => watch::run(
+            std::time::Duration::from_secs(interval),
+            once,
+            &branch_refs,
+            &patch_ids,
+            &watch,
+            &repos,
+        )?,
+        Some(Command::Pending) => hook::queued_parties(&mut state, &history, &patch_ids, &watch)?,
+        Some(Command::Receive { by }) => receive::run(by)?,
+        Some(Command::Simulate {
+            commits,
+            lines,
+            branch,
+            time,
+            day,
+            pushes_today,
+            streak,
+        }) => {
+            let scenario = simulate::Scenario {
+                commits,
+                lines,
+                branch,
+                time,
+                day,
+                pushes_today,
+                streak,
+            };
+            simulate::run(scenario, &state, &history, &patch_ids)?;
+
+            // a preview doesn't save anything, not even unchanged state
+            return Ok(());
+        }
         Some(Command::Points) => state::points(&state),
         Some(Command::Stats) => state::stats(&state, &history),
-        Some(Command::Hook) => hook::post_push(&mut state, &branch_refs, &history, &patch_ids)?,
+        Some(Command::Hook) => {
+            hook::post_push(&mut state, &branch_refs, &history, &patch_ids, &jj, &tags)?
+        }
+        Some(Command::PrePush { remote, url }) => hook::tag_push(&tags, &remote, &url)?,
         Some(Command::Dump) => state::dump(&state),
         Some(Command::Snapshot) => hook::pre_push(&branch_refs)?,
 
-        None => tui::run(&mut state, &conn)?,
+        None => {
+            hook::queued_parties(&mut state, &history, &patch_ids, &watch)?;
+            tui::run(&mut state, &conn)?
+        }
 
         #[cfg(feature = "dev")]
         Some(Command::Cheat { amount }) => dev::cheat(amount, &mut state),
@@ -65,3 +167,20 @@ fn main() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+/// reads the balance and custom bonuses files, falling back to the built-in numbers
+/// for whichever is broken. `warn` says so on stderr as well as in the debug log
+fn load_economy(warn: bool) {
+    if let Err(e) = balance::load() {
+        crate::debug_log!("could not load balance file: {e:#}");
+        if warn {
+            eprintln!("party: ignoring balance file: {e:#}");
+        }
+    }
+    if let Err(e) = bonus_track::load_tracks() {
+        crate::debug_log!("could not load custom bonuses: {e:#}");
+        if warn {
+            eprintln!("party: ignoring custom bonuses: {e:#}");
+        }
+    }
+}
@@ -1,6 +1,6 @@
 use rand::seq::{IndexedRandom, SliceRandom};
 
-use crate::state::State;
+use crate::{balance::balance, state::State};
 
 mod pack_item;
 mod rarity;
@@ -30,11 +30,20 @@ pub const PREMIUM_PACK_TEMPLATE: PackTemplate =
     PackTemplate(&[Rarity::Rare, Rarity::Rare, Rarity::Epic]);
 
 impl Pack {
-    pub fn cost(&self) -> u64 {
+    /// unique identifier for the balance file
+    pub fn id(&self) -> &'static str {
         match self {
+            Pack::Basic => "basic",
+            Pack::Premium => "premium",
+        }
+    }
+
+    pub fn cost(&self) -> u64 {
+        let default = match self {
             Pack::Basic => 1_000,
             Pack::Premium => 10_000,
-        }
+        };
+        balance().pack_cost(self.id(), default)
     }
 
     pub fn name(&self) -> &'static str {
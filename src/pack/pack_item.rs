@@ -1,4 +1,5 @@
 use crate::{
+    balance::balance,
     game::{GameRef, SNAKE, STACK, TREASURE_DEPTHS},
     pack::Rarity,
     party::{FIREWORKS_PARTY, Palette},
@@ -28,6 +29,17 @@ const RARE_POINTS: u64 = 100;
 const EPIC_POINTS: u64 = 400;
 const LEGENDARY_POINTS: u64 = 1600;
 
+/// points in a point bundle of the given rarity, as tuned by the balance file
+fn bundle_points(rarity: Rarity) -> u64 {
+    let default = match rarity {
+        Rarity::Common => COMMON_POINTS,
+        Rarity::Rare => RARE_POINTS,
+        Rarity::Epic => EPIC_POINTS,
+        Rarity::Legendary => LEGENDARY_POINTS,
+    };
+    balance().pack_item_points(rarity, default)
+}
+
 const COMMON_PALETTES: &[Palette] = &[
     Palette::RED_ANSI,
     Palette::GREEN_ANSI,
@@ -110,7 +122,7 @@ impl PackItem {
                 rarity: Rarity::Common,
             }))
             .chain([Self::PointBundle {
-                points: COMMON_POINTS,
+                points: bundle_points(Rarity::Common),
                 rarity: Rarity::Common,
             }])
             .collect()
@@ -135,7 +147,7 @@ impl PackItem {
                 rarity: Rarity::Rare,
             }))
             .chain([Self::PointBundle {
-                points: RARE_POINTS,
+                points: bundle_points(Rarity::Rare),
                 rarity: Rarity::Rare,
             }]);
 
@@ -177,7 +189,7 @@ impl PackItem {
                 rarity: Rarity::Epic,
             }))
             .chain([Self::PointBundle {
-                points: EPIC_POINTS,
+                points: bundle_points(Rarity::Epic),
                 rarity: Rarity::Epic,
             }]);
 
@@ -211,13 +223,13 @@ impl PackItem {
                     rarity: Rarity::Legendary,
                 })
                 .chain([Self::PointBundle {
-                    points: LEGENDARY_POINTS,
+                    points: bundle_points(Rarity::Legendary),
                     rarity: Rarity::Legendary,
                 }])
                 .collect()
         } else {
             Vec::from([Self::PointBundle {
-                points: LEGENDARY_POINTS,
+                points: bundle_points(Rarity::Legendary),
                 rarity: Rarity::Legendary,
             }])
         }
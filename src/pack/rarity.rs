@@ -1,5 +1,7 @@
 use rand::RngExt;
 
+use crate::balance::balance;
+
 /// how uncommon a pack item is
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Rarity {
@@ -19,6 +21,16 @@ impl Rarity {
         }
     }
 
+    /// unique identifier for the balance file
+    pub fn id(&self) -> &'static str {
+        match self {
+            Rarity::Common => "common",
+            Rarity::Rare => "rare",
+            Rarity::Epic => "epic",
+            Rarity::Legendary => "legendary",
+        }
+    }
+
     /// gets colors in HSL
     pub fn color(&self) -> (f32, f32, f32) {
         match self {
@@ -74,6 +86,7 @@ impl<R: rand::Rng> RngUpgrader<R> {
 //   epic every 4 packs
 //   legendary every 18 packs
 //   one in four packs contains epic/legendary
+// (unless the balance file says otherwise)
 const COMMON_TO_RARE_PROB: f64 = 0.10;
 const RARE_TO_EPIC_PROB: f64 = 0.20;
 const EPIC_TO_LEGENDARY_PROB: f64 = 0.20;
@@ -81,11 +94,12 @@ const EPIC_TO_LEGENDARY_PROB: f64 = 0.20;
 impl<R: rand::Rng> RarityUpgrader for RngUpgrader<R> {
     fn should_upgrade(&mut self, rarity: &Rarity) -> bool {
         let roll = self.rng.random_range(0.0..1.0);
-        match rarity {
-            Rarity::Common => roll < COMMON_TO_RARE_PROB,
-            Rarity::Rare => roll < RARE_TO_EPIC_PROB,
-            Rarity::Epic => roll < EPIC_TO_LEGENDARY_PROB,
-            Rarity::Legendary => false,
-        }
+        let default = match rarity {
+            Rarity::Common => COMMON_TO_RARE_PROB,
+            Rarity::Rare => RARE_TO_EPIC_PROB,
+            Rarity::Epic => EPIC_TO_LEGENDARY_PROB,
+            Rarity::Legendary => return false,
+        };
+        roll < balance().rarity_upgrade_chance(*rarity, default)
     }
 }
@@ -1,11 +1,12 @@
 use crate::{
+    git::Push,
     party::{PartyEntry, PartyInfo, PartyRenderer},
-    scoring::AppliedBonus,
+    scoring::{AppliedBonus, PointsBreakdown},
 };
 
 use super::{
     Palette, RenderContext,
-    style::{bold, bright_magenta, bright_yellow, dim, green, magenta, yellow},
+    style::{bold, bright_magenta, bright_yellow, dim, green, magenta, red, yellow},
 };
 
 /// shows how the total points were calculated for a push, including bonuses
@@ -21,18 +22,64 @@ pub static BREAKDOWN_PARTY: PartyEntry = PartyEntry {
 };
 
 fn render(ctx: &RenderContext, _palette: &Palette) -> bool {
-    let breakdown = ctx.breakdown;
+    print_breakdown(ctx.push, ctx.breakdown);
+    true
+}
+
+/// prints how a push's points add up
+pub fn print_breakdown(push: &Push, breakdown: &PointsBreakdown) {
+    // commits by other authors don't earn anything, but say that they were seen
+    let skipped = push.skipped_commits();
+    if skipped > 0 {
+        let commit_word = if skipped == 1 { "commit" } else { "commits" };
+        println!(
+            "   {}",
+            dim(format!(
+                "({skipped} {commit_word} by other authors skipped.)"
+            ))
+        );
+    }
+
+    // same for commits made by bots
+    let automated = push.automated_commits();
+    if automated > 0 {
+        let commit_word = if automated == 1 { "commit" } else { "commits" };
+        println!(
+            "   {}",
+            dim(format!("({automated} automated {commit_word} skipped.)"))
+        );
+    }
+
+    // previously credited commits thrown away by a force push
+    let discarded = breakdown.discarded;
+    if discarded > 0 {
+        let commit_word = if discarded == 1 { "commit" } else { "commits" };
+        let note = if breakdown.clawed_back > 0 {
+            format!(
+                "({discarded} previously credited {commit_word} discarded, {} P clawed back.)",
+                breakdown.clawed_back
+            )
+        } else {
+            format!("({discarded} previously credited {commit_word} discarded.)")
+        };
+        println!("   {}", dim(note));
+    }
 
-    // if no points were earned, just say so
-    if breakdown.total == 0 {
+    // if no points were earned, just say so (unless penalties explain why)
+    let penalized = breakdown
+        .applied
+        .iter()
+        .any(|b| matches!(b, AppliedBonus::Penalty { .. }));
+    if breakdown.total == 0 && !penalized {
         println!("   {}", dim("(No points earned.)"));
-        return true;
+        return;
     }
 
     let commits = breakdown.commits;
     let points_per_commit = breakdown.points_per_commit;
 
     let plus = dim(magenta("+"));
+    let minus = dim(magenta("-"));
     let times = dim(magenta("×"));
 
     //  N commits × M points per commit
@@ -47,7 +94,22 @@ fn render(ctx: &RenderContext, _palette: &Palette) -> bool {
     let points_per_commit = bold(bright_magenta(points_per_commit));
     println!("  {commits} {commit_word} {times} {points_per_commit} {point_word} per commit",);
 
-    // flat bonuses first (they add to base)
+    // penalties come off the base
+    for bonus in &breakdown.applied {
+        if let AppliedBonus::Penalty {
+            name,
+            points,
+            count,
+        } = bonus
+        {
+            let commit_word = if *count == 1 { "commit" } else { "commits" };
+            let extra_words = dim(format!(" ({count} {commit_word})"));
+            let points = bold(red(points));
+            println!("   {minus} {points} {name}{extra_words}");
+        }
+    }
+
+    // flat bonuses next (they add to base)
     for bonus in &breakdown.applied {
         if let AppliedBonus::FlatBonus {
             name,
@@ -78,6 +140,4 @@ fn render(ctx: &RenderContext, _palette: &Palette) -> bool {
     let total = bold(bright_yellow(breakdown.total));
     let p = yellow("P");
     println!("   {equals} {total} {p}");
-
-    true
 }
@@ -11,8 +11,9 @@ pub(crate) mod stats;
 mod style;
 
 use anyhow::Result;
+pub use breakdown::print_breakdown;
 pub use context::RenderContext;
-pub use palette::Palette;
+pub use palette::{PRESTIGE_PALETTES, Palette};
 
 pub use fireworks::FIREWORKS_PARTY;
 
@@ -72,6 +73,13 @@ pub struct PartyEntry {
     pub renderer: PartyRenderer,
 }
 
+impl PartyEntry {
+    /// unlock cost, as tuned by the balance file
+    pub fn cost(&self) -> u64 {
+        crate::balance::balance().party_cost(self.info.id, self.info.cost)
+    }
+}
+
 // all parties in order
 pub static ALL_PARTIES: &[&PartyEntry] = &[
     &base::BASE_PARTY,
@@ -30,6 +30,7 @@ const BLUE: Color = Color::Rgb(60, 60, 220);
 const BROWN: Color = Color::Rgb(179, 117, 50);
 const CYAN: Color = Color::Rgb(15, 245, 245);
 const DARK_RED: Color = Color::Rgb(167, 12, 12);
+const GOLD: Color = Color::Rgb(255, 191, 0);
 const GREEN: Color = Color::Rgb(50, 200, 50);
 const ICE_BLUE: Color = Color::Rgb(191, 208, 232);
 const LIGHT_GRAY: Color = Color::Rgb(207, 207, 207);
@@ -43,6 +44,7 @@ const ORANGE: Color = Color::Rgb(255, 144, 31);
 const PALE_BLUE: Color = Color::Rgb(138, 222, 255);
 const PALE_PINK: Color = Color::Rgb(245, 169, 184);
 const PALE_YELLOW: Color = Color::Rgb(252, 253, 190);
+const PLATINUM: Color = Color::Rgb(229, 228, 226);
 const RED: Color = Color::Rgb(244, 26, 26);
 const TEAL: Color = Color::Rgb(49, 168, 177);
 const VIOLET: Color = Color::Rgb(138, 43, 226);
@@ -165,8 +167,25 @@ impl Palette {
     // thematic
     pub const FIRE: Self = Self::new("fire", "Fire", &[RED, YELLOW, ORANGE, LIGHT_GRAY]);
     pub const AURORA: Self = Self::new("aurora", "Aurora", &[GREEN, TEAL, VIOLET, MAGENTA]);
+
+    // prestige, never found in packs
+    pub const GILDED: Self = Self::new("gilded", "Gilded", &[GOLD, YELLOW, PALE_YELLOW]);
+    pub const PLATINUM: Self = Self::new(
+        "platinum",
+        "Platinum",
+        &[PLATINUM, ICE_BLUE, WHITE, MID_GRAY],
+    );
+    pub const COSMIC: Self = Self::new(
+        "cosmic",
+        "Cosmic",
+        &[VIOLET, NEON_PINK, PALE_BLUE, GOLD, WHITE],
+    );
 }
 
+/// earned by prestiging, one more each time
+pub static PRESTIGE_PALETTES: &[&Palette] =
+    &[&Palette::GILDED, &Palette::PLATINUM, &Palette::COSMIC];
+
 pub static ALL_PALETTES: &[&Palette] = &[
     &Palette::WHITE_ANSI,
     &Palette::RED_ANSI,
@@ -198,4 +217,7 @@ pub static ALL_PALETTES: &[&Palette] = &[
     &Palette::SUNSET,
     &Palette::RAINBOW,
     &Palette::RAINBOW_ANSI,
+    &Palette::GILDED,
+    &Palette::PLATINUM,
+    &Palette::COSMIC,
 ];
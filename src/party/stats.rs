@@ -2,7 +2,7 @@ use crate::party::{PartyEntry, PartyInfo, PartyRenderer};
 
 use super::{
     Palette, RenderContext,
-    style::{bold, cyan, gray, green, yellow},
+    style::{bold, cyan, gray, green, red, yellow},
 };
 
 /// computes and shows stats about pushes and commits and points
@@ -23,12 +23,9 @@ pub fn render(ctx: &RenderContext, _palette: &Palette) -> bool {
     // this push
     let push_commits = ctx.push.commits();
     let push_commit_count = push_commits.len();
-    let push_lines = ctx
-        .push
-        .commits()
-        .iter()
-        .map(|c| c.lines_changed())
-        .sum::<u64>();
+    let push_automated = ctx.push.automated_commits();
+    let push_insertions = push_commits.iter().map(|c| c.insertions()).sum::<u64>();
+    let push_deletions = push_commits.iter().map(|c| c.deletions()).sum::<u64>();
     let push_points = ctx.breakdown.total;
 
     // today
@@ -46,7 +43,9 @@ pub fn render(ctx: &RenderContext, _palette: &Palette) -> bool {
 
     // daily_average
     let daily_avg_commit_count = all_time_stats.commits / all_time_stats.active_days;
-    let daily_avg_lines = all_time_stats.lines / all_time_stats.active_days;
+    let daily_avg_automated = all_time_stats.automated / all_time_stats.active_days;
+    let daily_avg_insertions = all_time_stats.insertions / all_time_stats.active_days;
+    let daily_avg_deletions = all_time_stats.deletions / all_time_stats.active_days;
     let daily_avg_points = all_time_stats.points / all_time_stats.active_days;
 
     // calculate max widths for each column
@@ -56,11 +55,23 @@ pub fn render(ctx: &RenderContext, _palette: &Palette) -> bool {
         daily_avg_commit_count,
         all_time_stats.commits,
     ]);
-    let w_lines = col_width([
-        push_lines,
-        today_stats.lines,
-        daily_avg_lines,
-        all_time_stats.lines,
+    let w_automated = col_width([
+        push_automated,
+        today_stats.automated,
+        daily_avg_automated,
+        all_time_stats.automated,
+    ]);
+    let w_insertions = col_width([
+        push_insertions,
+        today_stats.insertions,
+        daily_avg_insertions,
+        all_time_stats.insertions,
+    ]);
+    let w_deletions = col_width([
+        push_deletions,
+        today_stats.deletions,
+        daily_avg_deletions,
+        all_time_stats.deletions,
     ]);
     let w_points = col_width([
         push_points,
@@ -69,23 +80,44 @@ pub fn render(ctx: &RenderContext, _palette: &Palette) -> bool {
         all_time_stats.points,
     ]);
 
+    // bot commits get their own column, but only once there have been any
+    let show_automated = all_time_stats.automated > 0 || push_automated > 0;
+
     // helper function to print each row of output
-    let print_row = |header: &str, commits: u64, lines: u64, points: u64, extra: Option<String>| {
+    let print_row = |header: &str,
+                     (commits, automated): (u64, u64),
+                     (insertions, deletions): (u64, u64),
+                     points: u64,
+                     extra: Option<String>| {
         let commit_word = if commits == 1 { "commit" } else { "commits" };
         let point_word = if points == 1 { "point" } else { "points" };
-        let line_word = if lines == 1 { "line" } else { "lines" };
 
         let header = bold(format!("{header:>wh$}", wh = 10));
         let commits = format!("{} {commit_word},", green(commits));
-        let lines = format!("{} {line_word} changed,", cyan(lines));
+        let automated = if show_automated {
+            let automated = format!("{} automated,", gray(automated));
+            format!("{automated:<wa$} ", wa = w_automated + 21)
+        } else {
+            String::new()
+        };
+        let insertions = cyan(format!(
+            "{:>wi$}",
+            format!("+{insertions}"),
+            wi = w_insertions + 1
+        ));
+        let deletions = red(format!(
+            "{:<wd$}",
+            format!("-{deletions}"),
+            wd = w_deletions + 1
+        ));
+        let lines = format!("{insertions} {deletions} lines,");
         let points = format!("{} {point_word}", yellow(points));
 
         let extra = extra.unwrap_or_default();
 
         println!(
-            "{header}: {commits:<wc$} {lines:<wl$} {points:<wp$}{extra}",
+            "{header}: {commits:<wc$} {automated}{lines} {points:<wp$}{extra}",
             wc = w_commits + 19,
-            wl = w_lines + 24,
             wp = w_points + 6
         );
     };
@@ -99,8 +131,8 @@ pub fn render(ctx: &RenderContext, _palette: &Palette) -> bool {
             (push_points >= all_time_stats.max_points).then_some(yellow(" (biggest push ever!)"));
         print_row(
             "This Push",
-            push_commit_count as u64,
-            push_lines,
+            (push_commit_count as u64, push_automated),
+            (push_insertions, push_deletions),
             push_points,
             most_pts_ever,
         );
@@ -108,22 +140,22 @@ pub fn render(ctx: &RenderContext, _palette: &Palette) -> bool {
 
     print_row(
         "Today",
-        today_stats.commits,
-        today_stats.lines,
+        (today_stats.commits, today_stats.automated),
+        (today_stats.insertions, today_stats.deletions),
         today_stats.points,
         None,
     );
     print_row(
         "Daily Avg",
-        daily_avg_commit_count,
-        daily_avg_lines,
+        (daily_avg_commit_count, daily_avg_automated),
+        (daily_avg_insertions, daily_avg_deletions),
         daily_avg_points,
         None,
     );
     print_row(
         "All Time",
-        all_time_stats.commits,
-        all_time_stats.lines,
+        (all_time_stats.commits, all_time_stats.automated),
+        (all_time_stats.insertions, all_time_stats.deletions),
         all_time_stats.points,
         None,
     );
@@ -9,7 +9,6 @@ const RESET_ALL: &str = "\x1b[0m";
 const RESET_FONT: &str = "\x1b[22m";
 pub const RESET_COLOR: &str = "\x1b[39m";
 
-#[expect(dead_code)]
 const RED: &str = "\x1b[31m";
 const GREEN: &str = "\x1b[32m";
 const YELLOW: &str = "\x1b[33m";
@@ -48,6 +47,10 @@ pub fn white(s: impl Display) -> String {
     format!("{RESET_COLOR}{s}")
 }
 
+pub fn red(s: impl Display) -> String {
+    format!("{RED}{s}{RESET_COLOR}")
+}
+
 pub fn green(s: impl Display) -> String {
     format!("{GREEN}{s}{RESET_COLOR}")
 }
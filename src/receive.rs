@@ -0,0 +1,145 @@
+use std::io::Read;
+
+use anyhow::{Context, Result};
+
+use crate::{
+    cli::Attribution,
+    clock::Clock,
+    git::{self, Commit, Push, PushedBranch, receive::ReceivedCommit},
+    scoring,
+    state::State,
+    storage::{DbConnection, PatchIdStore, PushEntry, PushHistory},
+};
+
+/// where git servers put the name of whoever pushed, most specific first
+const PUSHER_ENVS: &[&str] = &[
+    // for servers party doesn't know, set by the hook itself
+    "PARTY_PUSHER",
+    // gitolite
+    "GL_USER",
+    // gitlab
+    "GL_USERNAME",
+    // gitea and forgejo
+    "GITEA_PUSHER_EMAIL",
+    "GITEA_PUSHER_NAME",
+    // smart http behind a web server
+    "REMOTE_USER",
+];
+
+/// the user who pushed, if the server says
+fn pusher() -> Option<String> {
+    PUSHER_ENVS
+        .iter()
+        .filter_map(|key| std::env::var(key).ok())
+        .map(|user| user.trim().to_string())
+        .find(|user| !user.is_empty())
+}
+
+/// called from a bare repo's `post-receive` hook, with git's list of updated refs on
+/// stdin. every user whose commits came in has them credited in their own ledger
+pub fn run(attribution: Attribution) -> Result<()> {
+    let mut input = String::new();
+    std::io::stdin().read_to_string(&mut input)?;
+
+    let repo_path = std::env::current_dir().context("could not get current directory")?;
+    let repo_path = repo_path.canonicalize().unwrap_or(repo_path);
+    let repo_id = git::remote::repo_id(&repo_path.to_string_lossy());
+
+    let received = git::receive::get_received_commits(&repo_path, &input);
+    if received.is_empty() {
+        return Ok(());
+    }
+
+    let pusher = match attribution {
+        Attribution::Pusher => {
+            let pusher = pusher();
+            if pusher.is_none() {
+                println!("party: no idea who pushed, so crediting commit authors instead");
+            }
+            pusher
+        }
+        Attribution::Author => None,
+    };
+
+    // by pusher, everything is theirs. by author, bots don't get a ledger
+    let mut by_user: Vec<(String, Vec<ReceivedCommit>)> = Vec::new();
+    for commit in received {
+        let user = match &pusher {
+            Some(pusher) => pusher.clone(),
+            None if commit.automated => continue,
+            None => commit.commit.author_email().to_lowercase(),
+        };
+        match by_user.iter_mut().find(|(u, _)| *u == user) {
+            Some((_, commits)) => commits.push(commit),
+            None => by_user.push((user, vec![commit])),
+        }
+    }
+
+    for (user, commits) in by_user {
+        if let Err(e) = credit(&user, &repo_id, commits) {
+            crate::debug_log!("receive: could not credit {user}: {e:#}");
+            println!("party: could not credit {user}: {e:#}");
+        }
+    }
+
+    Ok(())
+}
+
+/// scores one user's share of a push and records it in their ledger
+fn credit(user: &str, repo_id: &str, commits: Vec<ReceivedCommit>) -> Result<()> {
+    let conn = DbConnection::create_ledger(user)?;
+    let mut state = State::load(&conn)?;
+    let history = PushHistory::new(&conn);
+    let patch_ids = PatchIdStore::new(&conn);
+
+    // a commit already credited to this user in this repo, e.g. on another branch,
+    // doesn't count again
+    let mut branch_commits: Vec<(String, Vec<Commit>)> = Vec::new();
+    let mut automated_commits = 0;
+    for received in commits {
+        if patch_ids.contains(repo_id, &received.patch_id)? {
+            continue;
+        }
+        patch_ids.record(repo_id, &received.patch_id)?;
+
+        if received.automated {
+            automated_commits += 1;
+            continue;
+        }
+        match branch_commits
+            .iter_mut()
+            .find(|(b, _)| *b == received.branch)
+        {
+            Some((_, commits)) => commits.push(received.commit),
+            None => branch_commits.push((received.branch, vec![received.commit])),
+        }
+    }
+
+    let branches: Vec<PushedBranch> = branch_commits
+        .into_iter()
+        .map(|(name, commits)| PushedBranch::new(name, commits))
+        .collect();
+    let push = Push::from_branches(branches, repo_id, 0).with_automated(automated_commits);
+    if push.commits().is_empty() && push.automated_commits() == 0 {
+        return Ok(());
+    }
+
+    let clock = Clock::from_now();
+    let breakdown = scoring::calculate_points(&push, &state, &history, &clock);
+    state.earn_points(breakdown.total);
+    history.record(
+        &PushEntry::from_push(clock.now(), &push, breakdown.total)
+            .with_commit_points(breakdown.commit_points),
+    )?;
+    state.save(&conn)?;
+
+    if !push.commits().is_empty() {
+        println!(
+            "🎉 {user} earned {} party points for {} commits",
+            breakdown.total,
+            push.commits().len()
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,96 @@
+use std::path::Path;
+
+use anyhow::Result;
+
+use crate::{
+    init::{self, HookHealth},
+    storage::{BranchRefsStore, JjStore, RepoRegistry},
+};
+
+/// prints every registered repo, whether its hook is still in place and whether
+/// `party watch` polls it
+pub fn list(repos: &RepoRegistry) -> Result<()> {
+    let entries = repos.all()?;
+    if entries.is_empty() {
+        println!(
+            "no repos registered yet. run `party init` in a repo (or `party watch --add <path>`) to add it"
+        );
+        return Ok(());
+    }
+
+    for entry in entries {
+        let health = init::hook_health(Path::new(&entry.path), entry.vcs);
+        // a watched-only repo has no hook to check, but it can still be gone
+        let status = if entry.installed || health == HookHealth::Gone {
+            health.label()
+        } else {
+            "-"
+        };
+        println!(
+            "{:<8} {:<4} {:<7} {}",
+            status,
+            entry.vcs.as_str(),
+            if entry.watched { "watched" } else { "" },
+            entry.path
+        );
+    }
+
+    Ok(())
+}
+
+/// forgets repos that aren't there anymore
+pub fn prune(repos: &RepoRegistry) -> Result<()> {
+    let mut removed = 0;
+    for entry in repos.all()? {
+        if init::hook_health(Path::new(&entry.path), entry.vcs) == HookHealth::Gone {
+            repos.forget(&entry.path)?;
+            println!("removed {}", entry.path);
+            removed += 1;
+        }
+    }
+    println!("removed {removed} stale repos");
+
+    Ok(())
+}
+
+/// snapshots every registered repo again, so nothing pushed so far gets credited
+pub fn snapshot(repos: &RepoRegistry, branch_refs: &BranchRefsStore, jj: &JjStore) -> Result<()> {
+    let mut snapshotted = 0;
+    for entry in repos.all()? {
+        let path = Path::new(&entry.path);
+        if init::hook_health(path, entry.vcs) == HookHealth::Gone {
+            continue;
+        }
+        match init::snapshot(path, branch_refs, jj) {
+            Ok(()) => snapshotted += 1,
+            Err(e) => println!("could not snapshot {}: {e:#}", entry.path),
+        }
+    }
+    println!("snapshotted {snapshotted} repos");
+
+    Ok(())
+}
+
+/// removes party's hook from every registered repo and stops watching them all. repos
+/// it can't be removed from (e.g. because the hook was edited) stay registered
+pub fn uninstall(repos: &RepoRegistry) -> Result<()> {
+    let mut uninstalled = 0;
+    for entry in repos.all()? {
+        let path = Path::new(&entry.path);
+        let result = match init::hook_health(path, entry.vcs) {
+            _ if !entry.installed => Ok(()),
+            HookHealth::Gone | HookHealth::Missing => Ok(()),
+            HookHealth::Installed | HookHealth::Partial => init::uninstall_party_hook(path),
+        };
+        match result {
+            Ok(()) => {
+                repos.forget(&entry.path)?;
+                uninstalled += 1;
+            }
+            Err(e) => println!("could not uninstall from {}: {e:#}", entry.path),
+        }
+    }
+    println!("removed party from {uninstalled} repos");
+
+    Ok(())
+}
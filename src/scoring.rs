@@ -1,8 +1,12 @@
 //! Point calculation for pushes, applying all bonus tracks.
 
 use crate::{
-    bonus_track::{ALL_TRACKS, Clock, PushContext, Reward},
-    git::Push,
+    bonus_track::{BonusTrack, Clock, PushContext, Reward, all_tracks},
+    git::{
+        Push,
+        classify::{self, CommitKind},
+        rewrite::RewritePolicy,
+    },
     state::State,
     storage::PushHistory,
 };
@@ -19,6 +23,32 @@ pub enum AppliedBonus {
         points: u64,
         count: u32,
     },
+    /// base points taken off for commits that aren't real work
+    Penalty {
+        name: &'static str,
+        points: u64,
+        count: u32,
+    },
+}
+
+/// how much of its base points a commit keeps, in percent, by what kind of commit it is.
+/// anything less than substantive also doesn't count towards any bonus track
+fn commit_value_percent(kind: CommitKind) -> u64 {
+    match kind {
+        CommitKind::Substantive => 100,
+        CommitKind::Revert => 50,
+        CommitKind::Empty | CommitKind::WhitespaceOnly | CommitKind::RevertPair => 0,
+    }
+}
+
+fn penalty_name(kind: CommitKind) -> &'static str {
+    match kind {
+        CommitKind::Substantive => "",
+        CommitKind::Empty => "Empty commits",
+        CommitKind::WhitespaceOnly => "Whitespace-only commits",
+        CommitKind::Revert => "Reverts",
+        CommitKind::RevertPair => "Reverted in the same push",
+    }
 }
 
 /// Breakdown of points earned for a push.
@@ -28,6 +58,73 @@ pub struct PointsBreakdown {
     pub points_per_commit: u64,
     pub total: u64,
     pub applied: Vec<AppliedBonus>,
+
+    /// previously credited commits the push threw away
+    pub discarded: u64,
+
+    /// points taken back for those commits, under the clawback policy
+    pub clawed_back: u64,
+
+    /// what each commit earned by patch-id, and what was taken back from discarded ones
+    /// as negative amounts. a later clawback takes back no more than this
+    pub commit_points: Vec<(String, i64)>,
+}
+
+/// the push with only the commits that count for bonus tracks, or None if they all do
+fn substantive_push(push: &Push, kinds: &[CommitKind]) -> Option<Push> {
+    if kinds.iter().all(|k| k.is_substantive()) {
+        return None;
+    }
+    let paired: Vec<&str> = push
+        .commits()
+        .iter()
+        .zip(kinds)
+        .filter(|(_, kind)| **kind == CommitKind::RevertPair)
+        .map(|(c, _)| c.sha())
+        .collect();
+    Some(
+        push.filter_commits(|c| {
+            classify::classify(c).is_substantive() && !paired.contains(&c.sha())
+        }),
+    )
+}
+
+/// how many times a track applies to a push, whatever its level
+fn track_count(track: &dyn BonusTrack, ctx: &PushContext) -> u32 {
+    // commit_value is handled separately
+    if track.id() == "commit_value" {
+        return 0;
+    }
+
+    // a push without new commits (only tags, or only commits thrown away) can only
+    // earn for its release tags, so pushing throwaway tags can't farm other bonuses
+    if ctx.push.commits().is_empty() && track.id() != "ship_it" {
+        return 0;
+    }
+
+    track.applies(ctx)
+}
+
+/// Every bonus track that would apply to a push, with how many times, whether or not
+/// it has been unlocked.
+pub fn applicable_tracks(
+    push: &Push,
+    history: &PushHistory,
+    clock: &Clock,
+) -> Vec<(&'static dyn BonusTrack, u32)> {
+    let kinds = classify::classify_commits(push.commits());
+    let filtered = substantive_push(push, &kinds);
+    let ctx = PushContext {
+        push: filtered.as_ref().unwrap_or(push),
+        history,
+        clock,
+    };
+
+    all_tracks()
+        .iter()
+        .map(|&track| (track, track_count(track, &ctx)))
+        .filter(|(_, count)| *count > 0)
+        .collect()
 }
 
 /// Calculate points earned for a push.
@@ -44,24 +141,61 @@ pub fn calculate_points(
     let mut flat_bonus_total: u64 = 0;
     let mut applied = Vec::new();
 
+    // discarded commits give back what they earned when they were pushed, not what a
+    // commit is worth now
+    let discarded = push.discarded_commits().count() as u64;
+    let mut commit_points = Vec::new();
+    if push.rewrite_policy() == RewritePolicy::ClawBack {
+        for patch_id in push.discarded_commits().filter_map(|c| c.patch_id()) {
+            let earned = history
+                .commit_points(push.remote_url(), patch_id)
+                .unwrap_or_default();
+            if earned > 0 {
+                commit_points.push((patch_id.to_string(), -(earned as i64)));
+            }
+        }
+    }
+    let clawed_back = commit_points.iter().map(|(_, p)| p.unsigned_abs()).sum();
+
+    // commits that aren't real work earn less base points, and don't count for bonuses
+    let kinds = classify::classify_commits(push.commits());
+    let mut penalty_total: u64 = 0;
+    for kind in [
+        CommitKind::Empty,
+        CommitKind::WhitespaceOnly,
+        CommitKind::Revert,
+        CommitKind::RevertPair,
+    ] {
+        let count = kinds.iter().filter(|&&k| k == kind).count() as u32;
+        if count == 0 {
+            continue;
+        }
+        let full = count as u64 * points_per_commit;
+        let points = full - full * commit_value_percent(kind) / 100;
+        penalty_total += points;
+        applied.push(AppliedBonus::Penalty {
+            name: penalty_name(kind),
+            points,
+            count,
+        });
+    }
+
+    let filtered = substantive_push(push, &kinds);
+    let push_for_tracks = filtered.as_ref().unwrap_or(push);
+
     let ctx = PushContext {
-        push,
+        push: push_for_tracks,
         history,
         clock,
     };
 
-    for track in ALL_TRACKS.iter() {
-        // skip commit_value, it's handled separately
-        if track.id() == "commit_value" {
-            continue;
-        }
-
+    for track in all_tracks().iter() {
         let level = state.bonus_level(track.id());
         if level == 0 {
             continue;
         }
 
-        let count = track.applies(&ctx);
+        let count = track_count(*track, &ctx);
         if count == 0 {
             continue;
         }
@@ -87,21 +221,46 @@ pub fn calculate_points(
         }
     }
 
-    // formula: final_points = (base_points + flat_bonus_total) * total_multiplier
-    let total = (base_points + flat_bonus_total) * total_multiplier;
+    // prestige is earned rather than bought, so it isn't a bonus track
+    let prestige = state.prestige_multiplier();
+    if prestige > 1 {
+        total_multiplier *= prestige as u64;
+        applied.push(AppliedBonus::Multiplier {
+            name: "Prestige",
+            value: prestige,
+        });
+    }
+
+    // formula: final_points = (base_points - penalties + flat_bonus_total) * total_multiplier
+    let total = (base_points - penalty_total + flat_bonus_total) * total_multiplier;
+
+    // flat bonuses belong to the push as a whole, so a commit only keeps its base points
+    for (commit, kind) in push.commits().iter().zip(&kinds) {
+        if let Some(patch_id) = commit.patch_id() {
+            let points = points_per_commit * commit_value_percent(*kind) / 100 * total_multiplier;
+            commit_points.push((patch_id.to_string(), points as i64));
+        }
+    }
 
     PointsBreakdown {
         commits: push.commits().len() as u64,
         points_per_commit,
         total,
         applied,
+        discarded,
+        clawed_back,
+        commit_points,
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{git::Commit, storage::DbConnection};
+    use crate::{
+        bonus_track::ALL_TRACKS,
+        git::{Commit, PushedBranch, PushedTag},
+        storage::{DbConnection, PushEntry},
+    };
 
     fn get_multiplier(id: &str, level: u32) -> u64 {
         ALL_TRACKS
@@ -143,6 +302,29 @@ mod tests {
         assert_eq!(result.total, 3); // 3 commits × 1 point
     }
 
+    #[test]
+    fn prestige_multiplies_everything() {
+        let conn = DbConnection::create_in_memory().unwrap();
+        let history = PushHistory::new(&conn);
+        let push = Push::new(vec![Commit::with_lines(10), Commit::with_lines(20)]);
+
+        let state = State {
+            prestige_level: 2,
+            ..State::default()
+        };
+
+        let result = calculate_points(&push, &state, &history, &Clock::at(1000));
+
+        assert_eq!(result.total, 2 * 3); // 2 commits × 1 point, × 3 for prestige level 2
+        assert!(result.applied.iter().any(|b| matches!(
+            b,
+            AppliedBonus::Multiplier {
+                name: "Prestige",
+                value: 3
+            }
+        )));
+    }
+
     #[test]
     fn formula_applies_flat_before_multiplier() {
         let conn = DbConnection::create_in_memory().unwrap();
@@ -187,4 +369,112 @@ mod tests {
         // 4 base points + (3 sniper commits × flat_per)
         assert_eq!(result.total, 4 + 3 * flat_per);
     }
+
+    #[test]
+    fn whitespace_only_commits_earn_nothing_and_get_no_bonuses() {
+        let conn = DbConnection::create_in_memory().unwrap();
+        let history = PushHistory::new(&conn);
+        let mut state = State::default();
+        state.set_bonus_level("one_line_change", 1);
+
+        let push = Push::new(vec![Commit::with_lines(10), Commit::whitespace_only(1)]);
+        let result = calculate_points(&push, &state, &history, &Clock::at(1000));
+
+        assert_eq!(result.total, 1);
+        assert!(matches!(
+            result.applied.as_slice(),
+            [AppliedBonus::Penalty {
+                points: 1,
+                count: 1,
+                ..
+            }]
+        ));
+    }
+
+    #[test]
+    fn reverts_earn_half_and_revert_pairs_earn_nothing() {
+        let conn = DbConnection::create_in_memory().unwrap();
+        let history = PushHistory::new(&conn);
+        let mut state = State::default();
+        state.set_bonus_level("commit_value", 4); // 4 points per commit
+
+        // a lone revert of an older commit keeps half its points
+        let push = Push::new(vec![Commit::reverting("b", "a")]);
+        let result = calculate_points(&push, &state, &history, &Clock::at(1000));
+        assert_eq!(result.total, 2);
+
+        // a commit and its revert cancel out
+        let push = Push::new(vec![
+            Commit::reverting("c", "b"),
+            Commit::with_sha("b"),
+            Commit::with_sha("d"),
+        ]);
+        let result = calculate_points(&push, &state, &history, &Clock::at(1000));
+        assert_eq!(result.total, 4);
+    }
+
+    #[test]
+    fn clawback_takes_back_what_commits_earned_when_pushed() {
+        let conn = DbConnection::create_in_memory().unwrap();
+        let history = PushHistory::new(&conn);
+        let mut state = State::default();
+        state.set_bonus_level("commit_value", 2);
+
+        let commits = vec![
+            Commit::with_sha("a").with_patch_id("pa"),
+            Commit::with_sha("b").with_patch_id("pb"),
+        ];
+        let push = Push::with_repo(commits.clone(), "url/repo.git");
+        let result = calculate_points(&push, &state, &history, &Clock::at(1000));
+        assert_eq!(
+            result.commit_points,
+            vec![("pa".to_string(), 2), ("pb".to_string(), 2)]
+        );
+        history
+            .record(
+                &PushEntry::from_push(1000, &push, result.total)
+                    .with_commit_points(result.commit_points),
+            )
+            .unwrap();
+
+        // commits are worth more by the time they're thrown away
+        state.set_bonus_level("commit_value", 5);
+        let discard = Push::from_branches(
+            vec![PushedBranch::new("main", vec![]).with_discarded(commits)],
+            "url/repo.git",
+            0,
+        )
+        .with_rewrite_policy(RewritePolicy::ClawBack);
+        let result = calculate_points(&discard, &state, &history, &Clock::at(2000));
+        assert_eq!((result.discarded, result.clawed_back), (2, 4));
+        history
+            .record(
+                &PushEntry::from_push(2000, &discard, 0).with_commit_points(result.commit_points),
+            )
+            .unwrap();
+
+        // and there's nothing left to take back if they're thrown away again
+        let result = calculate_points(&discard, &state, &history, &Clock::at(3000));
+        assert_eq!(result.clawed_back, 0);
+    }
+
+    #[test]
+    fn tag_only_push_earns_only_for_release_tags() {
+        let conn = DbConnection::create_in_memory().unwrap();
+        let history = PushHistory::new(&conn);
+        let mut state = State::default();
+        state.set_bonus_level("first_push", 1);
+        state.set_bonus_level("ship_it", 1);
+
+        let push = Push::from_tags(
+            vec![
+                PushedTag::new("v1.0.0", "abc", true, false),
+                PushedTag::new("scratch", "def", false, false),
+            ],
+            "url/repo.git",
+        );
+        let result = calculate_points(&push, &state, &history, &Clock::at(1000));
+
+        assert_eq!(result.total, get_flat("ship_it", 1));
+    }
 }
@@ -0,0 +1,254 @@
+use anyhow::{Context, Result, bail};
+
+use crate::{
+    bonus_track::Reward,
+    cli::Weekday,
+    clock::Clock,
+    git::{self, Commit, Push, PushedBranch},
+    party, scoring,
+    state::State,
+    storage::{DbConnection, PatchIdStore, PushEntry, PushHistory},
+};
+
+/// the push to preview, and when. anything left out is taken from the current repo, the
+/// current time and the real push history
+pub struct Scenario {
+    pub commits: Option<u64>,
+    pub lines: Option<Vec<u64>>,
+    pub branch: Option<String>,
+    pub time: Option<u32>,
+    pub day: Option<Weekday>,
+    pub pushes_today: Option<u32>,
+    pub streak: Option<u32>,
+}
+
+/// where simulated pushes are made to, unless in a repo with a remote
+const SIMULATED_REPO: &str = "simulated/repo";
+
+/// prints what a push would earn and which bonus tracks it would trigger. reads the
+/// state and history but never writes them
+pub fn run(
+    scenario: Scenario,
+    state: &State,
+    history: &PushHistory,
+    patch_ids: &PatchIdStore,
+) -> Result<()> {
+    let clock = scenario_clock(&Clock::from_now(), scenario.day, scenario.time);
+
+    let push = match scenario.commits {
+        Some(count) => simulated_push(
+            count,
+            scenario.lines.as_deref(),
+            scenario.branch.as_deref().unwrap_or("main"),
+            &clock,
+        ),
+        None => {
+            let cwd = std::env::current_dir().context("could not get current directory")?;
+            let Some(repo_path) = git::repo::git_work_dir(&cwd) else {
+                bail!("not in a git repo. pass --commits to simulate a push anywhere");
+            };
+            git::get_unpushed_commits(&repo_path, patch_ids)?
+        }
+    };
+
+    // a made-up history lives in memory, so it can't leak into the real one
+    let synthetic_conn;
+    let synthetic_history;
+    let history = if scenario.pushes_today.is_some() || scenario.streak.is_some() {
+        synthetic_conn = DbConnection::create_in_memory()?;
+        synthetic_history = PushHistory::new(&synthetic_conn);
+        record_synthetic_history(
+            &synthetic_history,
+            &clock,
+            push.remote_url(),
+            scenario.pushes_today.unwrap_or(0),
+            scenario.streak.unwrap_or(0),
+        )?;
+        &synthetic_history
+    } else {
+        history
+    };
+
+    let commit_word = if push.commits().len() == 1 {
+        "commit"
+    } else {
+        "commits"
+    };
+    let branches: Vec<&str> = push.branches().iter().map(|b| b.name()).collect();
+    println!(
+        "pushing {} {commit_word} to {} on {} at {}:",
+        push.commits().len(),
+        branches.join(", "),
+        day_name(clock.day_of_week()),
+        time_of_day(&clock),
+    );
+
+    let breakdown = scoring::calculate_points(&push, state, history, &clock);
+    party::print_breakdown(&push, &breakdown);
+
+    let tracks = scoring::applicable_tracks(&push, history, &clock);
+    if !tracks.is_empty() {
+        println!();
+        println!("bonus tracks that apply:");
+    }
+    for (track, count) in tracks {
+        let level = state.bonus_level(track.id());
+        let current = match track.reward_at_level(level) {
+            Some(reward) => format!("level {level}, {}", describe(reward, count)),
+            None => "locked".to_string(),
+        };
+        let next = match track.tiers().get(level as usize) {
+            Some(tier) => format!(
+                "next tier for {} P: {}",
+                tier.cost,
+                describe(tier.reward, count)
+            ),
+            None => "maxed out".to_string(),
+        };
+        println!("  {:<24} {current} -> {next}", track.name());
+    }
+
+    println!();
+    println!("would earn {} party points", breakdown.total);
+
+    Ok(())
+}
+
+/// now, or the most recent `day` (today included) at `time`
+fn scenario_clock(now: &Clock, day: Option<Weekday>, time: Option<u32>) -> Clock {
+    let days_back = day.map_or(0, |day| {
+        (now.day_of_week() - day_of_week(day)).rem_euclid(7)
+    });
+    let seconds = time.map_or(now.local_seconds_since_midnight(), i64::from) as u64;
+    let timestamp = now.day_start(now.today_id() - days_back) + seconds;
+    Clock::with_offset(timestamp, now.tz_offset_secs())
+}
+
+/// the number `Clock::day_of_week` gives a day
+fn day_of_week(day: Weekday) -> i64 {
+    match day {
+        Weekday::Thu => 0,
+        Weekday::Fri => 1,
+        Weekday::Sat => 2,
+        Weekday::Sun => 3,
+        Weekday::Mon => 4,
+        Weekday::Tue => 5,
+        Weekday::Wed => 6,
+    }
+}
+
+fn day_name(day_of_week: i64) -> &'static str {
+    [
+        "thursday",
+        "friday",
+        "saturday",
+        "sunday",
+        "monday",
+        "tuesday",
+        "wednesday",
+    ][day_of_week as usize]
+}
+
+fn time_of_day(clock: &Clock) -> String {
+    let seconds = clock.local_seconds_since_midnight();
+    format!("{:02}:{:02}", seconds / 3600, seconds % 3600 / 60)
+}
+
+/// `count` commits made just now, with `lines` changed in each (10 if not given)
+fn simulated_push(count: u64, lines: Option<&[u64]>, branch: &str, clock: &Clock) -> Push {
+    let commits: Vec<Commit> = (0..count as usize)
+        .map(|i| {
+            let lines_changed = lines
+                .filter(|l| !l.is_empty())
+                .map(|l| l[i % l.len()])
+                .unwrap_or(10);
+            Commit::new(format!("simulated{i}"), lines_changed, clock.now())
+        })
+        .collect();
+    Push::from_branches(vec![PushedBranch::new(branch, commits)], SIMULATED_REPO, 0)
+}
+
+/// `pushes_today` pushes at the start of the day, and one push a day on each of the
+/// `streak` days before it
+fn record_synthetic_history(
+    history: &PushHistory,
+    clock: &Clock,
+    remote_url: &str,
+    pushes_today: u32,
+    streak: u32,
+) -> Result<()> {
+    let push_at = |timestamp: u64| {
+        PushEntry::new(
+            timestamp,
+            remote_url.to_string(),
+            vec![("main".to_string(), 1)],
+            1,
+            10,
+            0,
+            0,
+        )
+    };
+
+    for day in 1..=streak {
+        let day_start = clock.day_start(clock.today_id() - day as i64);
+        history.record(&push_at(day_start + 12 * 3600))?;
+    }
+    for _ in 0..pushes_today {
+        history.record(&push_at(clock.today_start()))?;
+    }
+    Ok(())
+}
+
+fn describe(reward: Reward, count: u32) -> String {
+    match reward {
+        Reward::Multiplier(m) => format!("×{m}"),
+        Reward::FlatPoints(p) if count > 1 => format!("+{} P ({count} × {p})", p * count as u64),
+        Reward::FlatPoints(p) => format!("+{p} P"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scenario_clock_goes_back_to_the_requested_day_and_time() {
+        // a saturday at 15:00, two hours ahead of utc
+        let now = Clock::with_offset(2 * 86400 + 13 * 3600, 2 * 3600);
+
+        let clock = scenario_clock(&now, None, None);
+        assert_eq!(clock.now(), now.now());
+
+        let clock = scenario_clock(&now, Some(Weekday::Fri), Some(17 * 3600 + 30 * 60));
+        assert_eq!(day_name(clock.day_of_week()), "friday");
+        assert_eq!(time_of_day(&clock), "17:30");
+        assert_eq!(clock.today_id(), now.today_id() - 1);
+
+        let clock = scenario_clock(&now, Some(Weekday::Sat), Some(9 * 3600));
+        assert_eq!(clock.today_id(), now.today_id());
+        assert_eq!(time_of_day(&clock), "09:00");
+    }
+
+    #[test]
+    fn synthetic_history_makes_pushes_today_and_a_streak() {
+        let conn = DbConnection::create_in_memory().unwrap();
+        let history = PushHistory::new(&conn);
+        let clock = Clock::at(10 * 86400 + 15 * 3600);
+
+        record_synthetic_history(&history, &clock, "github.com/me/repo", 2, 3).unwrap();
+
+        assert_eq!(history.count_since(clock.today_start()).unwrap(), 2);
+        assert_eq!(
+            history
+                .count_since(clock.day_start(clock.today_id() - 3))
+                .unwrap(),
+            5
+        );
+        assert_eq!(
+            history
+                .count_since(clock.day_start(clock.today_id() - 4))
+                .unwrap(),
+            5
+        );
+    }
+}
@@ -1,10 +1,11 @@
 use std::collections::{HashMap, HashSet};
 
 use crate::{
-    bonus_track::{ALL_TRACKS, Reward},
+    balance::balance,
+    bonus_track::{ALL_TRACKS, BonusTrack, Reward, all_tracks},
     game::GameRef,
     pack::{Pack, PackItem},
-    party::{ALL_PARTIES, Palette, PartyEntry},
+    party::{ALL_PARTIES, PRESTIGE_PALETTES, Palette, PartyEntry},
     storage::PushHistory,
 };
 
@@ -17,8 +18,22 @@ use crate::{
 ///     25 + 2 * 25 = 75
 ///     75 + 3 * 25 = 150
 ///     ... etc
+///
+/// can be overridden by the balance file
 const PACK_ACCRUAL_RATE: u64 = 25;
 
+/// how much of their spendable points, in percent, a player keeps when they prestige
+pub const PRESTIGE_POINTS_KEPT_PERCENT: u64 = 10;
+
+/// how much of a bonus tier's cost, in percent, is refunded when it's sold back.
+/// can be overridden by the balance file
+const RESPEC_REFUND_PERCENT: u64 = 50;
+
+/// how long after selling back a bonus level until another can be sold, so a level
+/// can't be bought for one push and sold right after it. can be overridden by the
+/// balance file
+const RESPEC_COOLDOWN_HOURS: u64 = 24;
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct State {
     pub party_points: u64,
@@ -53,6 +68,36 @@ pub struct State {
     /// how many game tokens the player has
     /// refers to games by their ids
     pub games: HashMap<String, u32>,
+
+    /// how many times the player has maxed out every bonus track and started over
+    pub prestige_level: u32,
+
+    /// bonus track levels sold back, oldest first
+    pub respecs: Vec<Respec>,
+
+    /// what was paid for each bonus track level, by track id and level, so selling one
+    /// back refunds part of that rather than of what it costs now
+    pub level_prices: HashMap<(String, u32), u64>,
+}
+
+/// a bonus track level that was sold back for points
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Respec {
+    pub timestamp: u64,
+    pub track_id: String,
+
+    /// the level that was sold, so the track went down to one below it
+    pub level: u32,
+    pub refund: u64,
+}
+
+/// why a bonus track level can't be sold back
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RespecError {
+    /// the track is at its lowest level
+    NothingToSell,
+    /// the last respec was too recent, with this many seconds left to wait
+    CoolingDown(u64),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -91,6 +136,9 @@ impl Default for State {
             packs: HashMap::new(),
             games: HashMap::new(),
             lifetime_packs_earned: 0,
+            prestige_level: 0,
+            respecs: Vec::new(),
+            level_prices: HashMap::new(),
         }
     }
 }
@@ -108,6 +156,9 @@ impl State {
         active_palettes: HashMap<String, PaletteSelection>,
         packs: HashMap<Pack, u32>,
         games: HashMap<String, u32>,
+        prestige_level: u32,
+        respecs: Vec<Respec>,
+        level_prices: HashMap<(String, u32), u64>,
     ) -> Self {
         Self {
             party_points,
@@ -120,6 +171,9 @@ impl State {
             packs,
             games,
             lifetime_packs_earned,
+            prestige_level,
+            respecs,
+            level_prices,
         }
     }
 
@@ -136,23 +190,30 @@ impl State {
         // grant packs. the thresholds values are
         //   PACK_ACCRUAL_RATE * (n+1) * (n+2) / 2
         // where n is the number of packs earned in this way so far
+        let rate = balance().pack_accrual_rate(PACK_ACCRUAL_RATE);
         let mut threshold =
-            PACK_ACCRUAL_RATE * (self.lifetime_packs_earned + 1) * (self.lifetime_packs_earned + 2)
-                / 2;
+            rate * (self.lifetime_packs_earned + 1) * (self.lifetime_packs_earned + 2) / 2;
         while threshold <= self.lifetime_points_earned {
             self.lifetime_packs_earned += 1;
             self.add_pack(Pack::Basic);
             thresholds.push(threshold);
 
-            threshold = PACK_ACCRUAL_RATE
-                * (self.lifetime_packs_earned + 1)
-                * (self.lifetime_packs_earned + 2)
-                / 2
+            threshold =
+                rate * (self.lifetime_packs_earned + 1) * (self.lifetime_packs_earned + 2) / 2
         }
 
         thresholds
     }
 
+    /// takes back points credited for commits that were later thrown away. only spendable
+    /// points are affected, so packs already earned stay earned and can't be earned again.
+    /// returns how many points were actually taken
+    pub fn claw_back(&mut self, amount: u64) -> u64 {
+        let taken = amount.min(self.party_points);
+        self.party_points -= taken;
+        taken
+    }
+
     pub fn bonus_level(&self, id: &str) -> u32 {
         self.bonus_tracks.get(id).copied().unwrap_or(0)
     }
@@ -161,13 +222,20 @@ impl State {
         self.bonus_tracks.insert(id.to_string(), level);
     }
 
+    /// raises a track to `level` for `cost` points, keeping the price for selling it back
+    pub fn buy_bonus_level(&mut self, id: &str, level: u32, cost: u64) {
+        self.party_points -= cost;
+        self.set_bonus_level(id, level);
+        self.level_prices.insert((id.to_string(), level), cost);
+    }
+
     pub fn points_per_commit(&self) -> u64 {
         let level = self.bonus_level("commit_value");
         if level == 0 {
             return 1;
         }
         // find commit_value track and get reward
-        for track in ALL_TRACKS.iter() {
+        for track in all_tracks().iter() {
             if track.id() == "commit_value"
                 && let Some(Reward::FlatPoints(n)) = track.reward_at_level(level)
             {
@@ -177,6 +245,103 @@ impl State {
         1
     }
 
+    /// what selling back the current level of a track would refund, or None if it's at
+    /// its lowest level (which for commit value is the free first level). it's part of
+    /// what the level was bought for, or of what it costs now if that wasn't kept
+    pub fn respec_refund(&self, track: &dyn BonusTrack) -> Option<u64> {
+        let level = self.bonus_level(track.id());
+        if level <= State::default().bonus_level(track.id()) {
+            return None;
+        }
+        let tier = track.tiers().get(level as usize - 1)?;
+        let price = self
+            .level_prices
+            .get(&(track.id().to_string(), level))
+            .copied()
+            .unwrap_or(tier.cost);
+        let percent = balance().respec_refund_percent(RESPEC_REFUND_PERCENT);
+        Some(price * percent / 100)
+    }
+
+    /// seconds until another bonus level can be sold back
+    pub fn respec_cooldown(&self, now: u64) -> u64 {
+        let cooldown = balance().respec_cooldown_hours(RESPEC_COOLDOWN_HOURS) * 3600;
+        self.respecs
+            .last()
+            .map_or(0, |last| (last.timestamp + cooldown).saturating_sub(now))
+    }
+
+    /// lowers a track by one level, refunding part of what that level cost.
+    /// returns the refund
+    pub fn respec(&mut self, track: &dyn BonusTrack, now: u64) -> Result<u64, RespecError> {
+        let refund = self
+            .respec_refund(track)
+            .ok_or(RespecError::NothingToSell)?;
+        let cooldown = self.respec_cooldown(now);
+        if cooldown > 0 {
+            return Err(RespecError::CoolingDown(cooldown));
+        }
+
+        let level = self.bonus_level(track.id());
+        self.set_bonus_level(track.id(), level - 1);
+        self.level_prices.remove(&(track.id().to_string(), level));
+        self.party_points += refund;
+        self.respecs.push(Respec {
+            timestamp: now,
+            track_id: track.id().to_string(),
+            level,
+            refund,
+        });
+        Ok(refund)
+    }
+
+    /// whether every built-in bonus track is at its last tier
+    pub fn can_prestige(&self) -> bool {
+        ALL_TRACKS
+            .iter()
+            .all(|track| self.bonus_level(track.id()) as usize >= track.tiers().len())
+    }
+
+    /// multiplies the points of every push, one more for each time the player prestiged
+    pub fn prestige_multiplier(&self) -> u32 {
+        self.prestige_level + 1
+    }
+
+    /// starts over with every bonus track back at its first level and only some of the
+    /// player's points, for a bigger prestige multiplier and the next prestige palette.
+    /// parties, palettes, packs and game tokens are kept. does nothing (and returns
+    /// false) unless every bonus track is maxed out
+    pub fn prestige(&mut self) -> bool {
+        if !self.can_prestige() {
+            return false;
+        }
+
+        self.bonus_tracks = State::default().bonus_tracks;
+        self.level_prices.clear();
+        self.party_points = self.party_points * PRESTIGE_POINTS_KEPT_PERCENT / 100;
+        self.prestige_level += 1;
+        self.grant_prestige_palettes();
+        true
+    }
+
+    /// unlocks the palettes earned by prestiging for every party that can use them
+    fn grant_prestige_palettes(&mut self) {
+        let earned = PRESTIGE_PALETTES.iter().take(self.prestige_level as usize);
+        let parties: Vec<&str> = self
+            .unlocked_parties()
+            .filter(|party| party.info.supports_color)
+            .map(|party| party.info.id)
+            .collect();
+
+        for palette in earned {
+            for party_id in &parties {
+                if !self.is_palette_unlocked(party_id, palette.id()) {
+                    self.unlock_palette(party_id, palette.id());
+                }
+            }
+        }
+    }
+
     pub fn unlocked_parties(&self) -> impl Iterator<Item = &'static PartyEntry> + use<'_> {
         ALL_PARTIES
             .iter()
@@ -204,6 +369,9 @@ impl State {
             self.active_palettes
                 .insert(id.to_string(), PaletteSelection::Specific(white));
         }
+
+        // parties unlocked after prestiging get the palettes earned so far too
+        self.grant_prestige_palettes();
     }
 
     pub fn toggle_party(&mut self, id: &str) {
@@ -340,6 +508,9 @@ pub fn stats(state: &State, history: &PushHistory) {
         points_per_commit: 0,
         total: 0,
         applied: vec![],
+        discarded: 0,
+        clawed_back: 0,
+        commit_points: Vec::new(),
     };
 
     let ctx =
@@ -351,6 +522,7 @@ pub fn dump(state: &State) {
     println!("party_points: {}", state.party_points);
     println!("lifetime_points_earned: {}", state.lifetime_points_earned);
     println!("lifetime_packs_earned: {}", state.lifetime_packs_earned);
+    println!("prestige_level: {}", state.prestige_level);
     println!("points_per_commit: {}", state.points_per_commit());
     println!("bonus_levels: {:?}", state.bonus_tracks);
     println!("unlocked_parties: {:?}", state.unlocked_parties);
@@ -490,4 +662,150 @@ mod tests {
         assert_eq!(state.lifetime_packs_earned, 3);
         assert_eq!(state.pack_count(&Pack::Basic), 3);
     }
+
+    fn max_every_track(state: &mut State) {
+        for track in ALL_TRACKS {
+            state.set_bonus_level(track.id(), track.tiers().len() as u32);
+        }
+    }
+
+    #[test]
+    fn prestige_needs_every_track_maxed() {
+        let mut state = State {
+            party_points: 1000,
+            ..State::default()
+        };
+        max_every_track(&mut state);
+        state.set_bonus_level("streak", 1);
+
+        assert!(!state.can_prestige());
+        assert!(!state.prestige());
+        assert_eq!(state.prestige_level, 0);
+        assert_eq!(state.party_points, 1000);
+    }
+
+    #[test]
+    fn prestige_resets_bonuses_and_most_points() {
+        let mut state = State {
+            party_points: 1000,
+            ..State::default()
+        };
+        state.unlock_party("exclamation");
+        state.add_pack(Pack::Basic);
+        max_every_track(&mut state);
+
+        assert!(state.prestige());
+
+        assert_eq!(state.prestige_level, 1);
+        assert_eq!(state.prestige_multiplier(), 2);
+        assert_eq!(state.bonus_tracks, State::default().bonus_tracks);
+        assert_eq!(
+            state.party_points,
+            1000 * PRESTIGE_POINTS_KEPT_PERCENT / 100
+        );
+        assert!(state.is_party_unlocked("exclamation"));
+        assert_eq!(state.pack_count(&Pack::Basic), 1);
+        assert!(!state.can_prestige());
+    }
+
+    #[test]
+    fn prestige_palettes_go_to_every_party_that_supports_color() {
+        let mut state = State::default();
+        state.unlock_party("exclamation");
+        max_every_track(&mut state);
+        state.prestige();
+
+        let gilded = PRESTIGE_PALETTES[0].id();
+        assert!(state.is_palette_unlocked("exclamation", gilded));
+        assert!(!state.is_palette_unlocked("exclamation", PRESTIGE_PALETTES[1].id()));
+
+        // parties unlocked later get them too
+        state.unlock_party("big_text");
+        assert!(state.is_palette_unlocked("big_text", gilded));
+
+        // but only once, however many times they're granted
+        max_every_track(&mut state);
+        state.prestige();
+        let palettes = state.unlocked_palettes("exclamation").unwrap();
+        assert_eq!(palettes.iter().filter(|&p| p == gilded).count(), 1);
+        assert!(state.is_palette_unlocked("exclamation", PRESTIGE_PALETTES[1].id()));
+    }
+
+    fn track(id: &str) -> &'static dyn BonusTrack {
+        *ALL_TRACKS.iter().find(|t| t.id() == id).unwrap()
+    }
+
+    #[test]
+    fn respec_refunds_part_of_the_level_and_records_it() {
+        let mut state = State::default();
+        let early = track("early_push");
+        state.set_bonus_level("early_push", 2);
+
+        let refund = state.respec(early, 1000).unwrap();
+
+        assert_eq!(refund, early.tiers()[1].cost * RESPEC_REFUND_PERCENT / 100);
+        assert_eq!(state.party_points, refund);
+        assert_eq!(state.bonus_level("early_push"), 1);
+        assert_eq!(
+            state.respecs,
+            vec![Respec {
+                timestamp: 1000,
+                track_id: "early_push".to_string(),
+                level: 2,
+                refund,
+            }]
+        );
+    }
+
+    #[test]
+    fn respec_refunds_part_of_what_the_level_was_bought_for() {
+        let mut state = State {
+            party_points: 10,
+            ..State::default()
+        };
+        let early = track("early_push");
+        state.buy_bonus_level("early_push", 1, 10);
+        assert_eq!(state.party_points, 0);
+
+        // a level bought when it was cheaper doesn't refund more than was paid
+        assert_ne!(early.tiers()[0].cost, 10);
+        assert_eq!(
+            state.respec(early, 1000),
+            Ok(10 * RESPEC_REFUND_PERCENT / 100)
+        );
+        assert!(state.level_prices.is_empty());
+    }
+
+    #[test]
+    fn respec_has_a_cooldown() {
+        let mut state = State::default();
+        state.set_bonus_level("early_push", 2);
+        state.set_bonus_level("late_push", 1);
+        let cooldown = RESPEC_COOLDOWN_HOURS * 3600;
+
+        state.respec(track("early_push"), 1000).unwrap();
+
+        // not even another track can be sold back until it's over
+        assert_eq!(
+            state.respec(track("late_push"), 1000 + 60),
+            Err(RespecError::CoolingDown(cooldown - 60))
+        );
+        assert_eq!(state.bonus_level("late_push"), 1);
+        assert!(state.respec(track("late_push"), 1000 + cooldown).is_ok());
+        assert_eq!(state.respec_cooldown(1000 + cooldown), cooldown);
+    }
+
+    #[test]
+    fn respec_stops_at_the_lowest_level() {
+        let mut state = State::default();
+
+        // never bought
+        assert_eq!(
+            state.respec(track("streak"), 1000),
+            Err(RespecError::NothingToSell)
+        );
+        // commit value's first level is free and can't be sold
+        assert_eq!(state.respec_refund(track("commit_value")), None);
+        assert!(state.respecs.is_empty());
+    }
 }
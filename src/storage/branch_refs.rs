@@ -29,6 +29,25 @@ impl<'a> BranchRefsStore<'a> {
             )
             .optional()
     }
+
+    /// marks a remote as seen, even if it has no branches yet
+    pub fn mark_known(&self, repo: &str) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR IGNORE INTO known_remotes (remote_url) VALUES (?1)",
+            (repo,),
+        )?;
+
+        Ok(())
+    }
+
+    /// whether a remote's refs have ever been snapshotted
+    pub fn is_known(&self, repo: &str) -> Result<bool> {
+        self.conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM known_remotes WHERE remote_url = ?1)",
+            (repo,),
+            |r| r.get(0),
+        )
+    }
 }
 
 #[cfg(test)]
@@ -60,4 +79,18 @@ mod tests {
         assert_eq!(ref2, Some("sha-3".to_string()));
         assert_eq!(ref3, None);
     }
+
+    #[test]
+    fn remotes_are_known_once_marked() {
+        let conn = DbConnection::create_in_memory().unwrap();
+        let branch_refs = BranchRefsStore::new(&conn);
+
+        assert!(!branch_refs.is_known("repo-url").unwrap());
+
+        branch_refs.mark_known("repo-url").unwrap();
+        branch_refs.mark_known("repo-url").unwrap();
+
+        assert!(branch_refs.is_known("repo-url").unwrap());
+        assert!(!branch_refs.is_known("other-url").unwrap());
+    }
 }
@@ -1,4 +1,4 @@
-use anyhow::Result;
+use anyhow::{Result, anyhow};
 use rusqlite::Connection;
 use std::path::PathBuf;
 
@@ -17,15 +17,49 @@ impl std::ops::Deref for DbConnection {
 
 const DB_FILE_NAME: &str = "party.db";
 
+/// where `party receive` keeps a db per user
+const LEDGERS_DIR_NAME: &str = "ledgers";
+
 fn db_path() -> Result<PathBuf> {
     super::storage_dir().map(|p| p.join(DB_FILE_NAME))
 }
 
+/// a user's ledger, named after them with anything unusual in the name replaced
+fn ledger_path(user: &str) -> Result<PathBuf> {
+    let dir = super::storage_dir()?.join(LEDGERS_DIR_NAME);
+    std::fs::create_dir_all(&dir)?;
+
+    let name: String = user
+        .trim()
+        .to_lowercase()
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | '0'..='9' | '.' | '@' | '+' | '-' | '_' => c,
+            _ => '_',
+        })
+        .collect();
+    let name = name.trim_start_matches('.');
+    if name.is_empty() {
+        return Err(anyhow!("can't keep a ledger for user {user:?}"));
+    }
+    Ok(dir.join(format!("{name}.db")))
+}
+
 impl DbConnection {
     /// creates a connection to the sqlite db in the user's data storage directory,
     /// then runs migrations
     pub fn create() -> Result<Self> {
-        let db_path = db_path()?;
+        Self::open(db_path()?)
+    }
+
+    /// creates a connection to one user's ledger of pushes received by a server (see
+    /// `party receive`), then runs migrations. a ledger is a db like any other, with
+    /// that user's points and push history
+    pub fn create_ledger(user: &str) -> Result<Self> {
+        Self::open(ledger_path(user)?)
+    }
+
+    fn open(db_path: PathBuf) -> Result<Self> {
         let conn = Connection::open(db_path)?;
 
         conn.pragma_update(None, "journal_mode", "WAL")?;
@@ -38,7 +72,6 @@ impl DbConnection {
     }
 
     /// creates a connection to a fresh db in memory, then runs all migrations
-    #[cfg(test)]
     pub fn create_in_memory() -> Result<Self> {
         let conn = Connection::open_in_memory()?;
 
@@ -51,12 +84,30 @@ impl DbConnection {
         Ok(conn)
     }
 
-    fn run_migrations(&self) -> Result<()> {
+    /// creates a connection to a fresh db in memory, then runs migrations up to and
+    /// including `version`. used to test migrations against data in an older schema
+    #[cfg(test)]
+    pub fn create_in_memory_at(version: u32) -> Result<Self> {
+        let conn = Connection::open_in_memory()?;
+        conn.pragma_update(None, "foreign_keys", "ON")?;
+
+        let conn = Self(conn);
+        conn.run_migrations_to(version)?;
+
+        Ok(conn)
+    }
+
+    /// runs any migrations that haven't been applied yet
+    pub(super) fn run_migrations(&self) -> Result<()> {
+        self.run_migrations_to(MIGRATIONS.len() as u32)
+    }
+
+    fn run_migrations_to(&self, target_version: u32) -> Result<()> {
         let current_version: u32 = self.pragma_query_value(None, "user_version", |r| r.get(0))?;
 
         for (i, migration) in MIGRATIONS.iter().enumerate() {
             let version = i as u32 + 1;
-            if version > current_version {
+            if version > current_version && version <= target_version {
                 let tx = self.unchecked_transaction()?;
 
                 migration(self)?;
@@ -0,0 +1,88 @@
+use rusqlite::{OptionalExtension, Result};
+
+use crate::storage::DbConnection;
+
+/// what party remembers about jj repos: which changes have been credited, and how far
+/// into each repo's operation log it has looked
+pub struct JjStore<'a> {
+    conn: &'a DbConnection,
+}
+
+impl<'a> JjStore<'a> {
+    pub fn new(conn: &'a DbConnection) -> Self {
+        Self { conn }
+    }
+
+    pub fn record_change(&self, remote_url: &str, change_id: &str) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR IGNORE INTO change_ids (remote_url, change_id) VALUES (?1, ?2)",
+            (remote_url, change_id),
+        )?;
+        Ok(())
+    }
+
+    pub fn contains_change(&self, remote_url: &str, change_id: &str) -> Result<bool> {
+        self.conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM change_ids WHERE remote_url = ?1 AND change_id = ?2)",
+            (remote_url, change_id),
+            |r| r.get(0),
+        )
+    }
+
+    /// the newest operation already looked at in this repo, if it's been seen at all
+    pub fn last_operation(&self, repo_path: &str) -> Result<Option<String>> {
+        self.conn
+            .query_one(
+                "SELECT op_id FROM jj_operations WHERE repo_path = ?1",
+                (repo_path,),
+                |row| row.get(0),
+            )
+            .optional()
+    }
+
+    pub fn set_last_operation(&self, repo_path: &str, op_id: &str) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO jj_operations (repo_path, op_id) VALUES (?1, ?2)",
+            (repo_path, op_id),
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn changes_roundtrip() {
+        let conn = DbConnection::create_in_memory().unwrap();
+        let store = JjStore::new(&conn);
+
+        store.record_change("url/a.git", "zzzk").unwrap();
+
+        assert!(store.contains_change("url/a.git", "zzzk").unwrap());
+        assert!(!store.contains_change("url/b.git", "zzzk").unwrap());
+        assert!(!store.contains_change("url/a.git", "kkkz").unwrap());
+    }
+
+    #[test]
+    fn last_operation_is_per_repo() {
+        let conn = DbConnection::create_in_memory().unwrap();
+        let store = JjStore::new(&conn);
+
+        assert_eq!(store.last_operation("/repo/a").unwrap(), None);
+
+        store.set_last_operation("/repo/a", "op1").unwrap();
+        store.set_last_operation("/repo/a", "op2").unwrap();
+        store.set_last_operation("/repo/b", "op3").unwrap();
+
+        assert_eq!(
+            store.last_operation("/repo/a").unwrap().as_deref(),
+            Some("op2")
+        );
+        assert_eq!(
+            store.last_operation("/repo/b").unwrap().as_deref(),
+            Some("op3")
+        );
+    }
+}
@@ -3,7 +3,23 @@ use anyhow::Result;
 
 pub type Migration = fn(&DbConnection) -> Result<()>;
 
-pub const MIGRATIONS: &[Migration] = &[migrate_v1, migrate_v2, migrate_v3];
+pub const MIGRATIONS: &[Migration] = &[
+    migrate_v1,
+    migrate_v2,
+    migrate_v3,
+    migrate_v4,
+    migrate_v5,
+    migrate_v6,
+    migrate_v7,
+    migrate_v8,
+    migrate_v9,
+    migrate_v10,
+    migrate_v11,
+    migrate_v12,
+    migrate_v13,
+    migrate_v14,
+    migrate_v15,
+];
 
 /// initial table construction and state population
 fn migrate_v1(conn: &DbConnection) -> Result<()> {
@@ -175,3 +191,557 @@ fn migrate_v3(conn: &DbConnection) -> Result<()> {
 
     Ok(())
 }
+
+/// record every branch touched by a push in a child table.
+/// `pushes` is rebuilt with an explicit id so that branches can refer to it
+fn migrate_v4(conn: &DbConnection) -> Result<()> {
+    // NOTE: `push_branches` initially references `pushes_new`; the rename at the end
+    // rewrites that reference to `pushes`. dropping the old table first would trip
+    // the foreign key instead
+    conn.execute_batch(
+        "
+        CREATE TABLE pushes_new (
+            id             INTEGER PRIMARY KEY,
+            timestamp      INTEGER NOT NULL,
+            remote_url     TEXT NOT NULL,
+            commits        INTEGER NOT NULL,
+            lines_changed  INTEGER NOT NULL,
+            points_earned  INTEGER NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS push_branches (
+            push_id  INTEGER NOT NULL REFERENCES pushes_new(id) ON DELETE CASCADE,
+            branch   TEXT NOT NULL,
+            commits  INTEGER NOT NULL,
+            PRIMARY KEY (push_id, branch)
+        );
+
+        INSERT INTO pushes_new (id, timestamp, remote_url, commits, lines_changed, points_earned)
+        SELECT rowid, timestamp, remote_url, commits, lines_changed, points_earned
+        FROM pushes;
+
+        INSERT INTO push_branches (push_id, branch, commits)
+        SELECT rowid, branch, commits
+        FROM pushes
+        WHERE branch != '';
+
+        DROP TABLE pushes;
+        ALTER TABLE pushes_new RENAME TO pushes;
+        ",
+    )?;
+
+    Ok(())
+}
+
+/// remember which remotes have had their refs snapshotted, so that repos party has
+/// never seen can be seeded instead of having their whole history credited
+fn migrate_v5(conn: &DbConnection) -> Result<()> {
+    conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS known_remotes (
+            remote_url  TEXT PRIMARY KEY
+        );
+
+        INSERT OR IGNORE INTO known_remotes (remote_url)
+        SELECT remote_url FROM branch_refs
+        UNION
+        SELECT remote_url FROM patch_ids;
+        ",
+    )?;
+
+    Ok(())
+}
+
+/// key everything by canonical repo id instead of the remote url as it was written in
+/// each clone's config, merging rows that turn out to belong to the same repo
+fn migrate_v6(conn: &DbConnection) -> Result<()> {
+    let urls: Vec<String> = conn
+        .prepare(
+            "
+            SELECT remote_url FROM pushes
+            UNION SELECT remote_url FROM patch_ids
+            UNION SELECT remote_url FROM branch_refs
+            UNION SELECT remote_url FROM known_remotes
+            ",
+        )?
+        .query_map([], |r| r.get(0))?
+        .collect::<rusqlite::Result<_>>()?;
+
+    for url in urls {
+        let id = v6_repo_id(&url);
+        if id == url {
+            continue;
+        }
+
+        for table in ["patch_ids", "branch_refs", "known_remotes"] {
+            conn.execute(
+                &format!("UPDATE OR IGNORE {table} SET remote_url = ?2 WHERE remote_url = ?1"),
+                (&url, &id),
+            )?;
+            // anything left over is already stored under the repo id
+            conn.execute(
+                &format!("DELETE FROM {table} WHERE remote_url = ?1"),
+                (&url,),
+            )?;
+        }
+        conn.execute(
+            "UPDATE pushes SET remote_url = ?2 WHERE remote_url = ?1",
+            (&url, &id),
+        )?;
+    }
+
+    Ok(())
+}
+
+/// `git::remote::repo_id` as it was when v6 was written. kept here so that migrating an
+/// old database always gives the same ids, however the live version changes
+fn v6_repo_id(url: &str) -> String {
+    let url = url.trim();
+
+    let Some((authority, path)) = v6_split_authority(url) else {
+        let path = url.strip_prefix("file://").unwrap_or(url);
+        let trimmed = path.trim_end_matches('/');
+        return if trimmed.is_empty() { path } else { trimmed }.to_string();
+    };
+
+    let host = authority.rsplit('@').next().unwrap_or(authority);
+    let host = host.split(':').next().unwrap_or(host);
+
+    let path = path.trim_matches('/');
+    let path = path
+        .strip_suffix(".git")
+        .unwrap_or(path)
+        .trim_end_matches('/');
+
+    let host = host.to_lowercase();
+    if ["github.com", "gitlab.com", "bitbucket.org"].contains(&host.as_str()) {
+        format!("{host}/{}", path.to_lowercase())
+    } else {
+        format!("{host}/{path}")
+    }
+}
+
+fn v6_split_authority(url: &str) -> Option<(&str, &str)> {
+    if let Some((scheme, rest)) = url.split_once("://") {
+        if scheme == "file" {
+            return None;
+        }
+        return Some(rest.split_once('/').unwrap_or((rest, "")));
+    }
+
+    let (authority, path) = url.split_once(':')?;
+    if authority.contains('/') || authority.len() == 1 {
+        return None;
+    }
+    Some((authority, path))
+}
+
+/// record commits thrown away by force pushes, and any points taken back for them.
+/// what each credited commit earned is kept too, so a clawback takes back that rather
+/// than what a commit is worth now
+fn migrate_v7(conn: &DbConnection) -> Result<()> {
+    conn.execute_batch(
+        "
+        ALTER TABLE pushes ADD COLUMN discarded_commits INTEGER NOT NULL DEFAULT 0;
+        ALTER TABLE pushes ADD COLUMN points_clawed_back INTEGER NOT NULL DEFAULT 0;
+
+        CREATE TABLE IF NOT EXISTS commit_points (
+            push_id     INTEGER NOT NULL REFERENCES pushes(id) ON DELETE CASCADE,
+            patch_id    TEXT NOT NULL,
+            points      INTEGER NOT NULL
+        );
+
+        CREATE INDEX IF NOT EXISTS commit_points_patch_id ON commit_points (patch_id);
+        ",
+    )?;
+
+    Ok(())
+}
+
+/// record tags created by a push, like its branches. tags are only credited once
+/// they're on the remote, rather than when they're about to be pushed, so they wait
+/// in pending_tags in between
+fn migrate_v8(conn: &DbConnection) -> Result<()> {
+    conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS push_tags (
+            push_id     INTEGER NOT NULL REFERENCES pushes(id) ON DELETE CASCADE,
+            tag         TEXT NOT NULL,
+            commit_sha  TEXT NOT NULL,
+            PRIMARY KEY (push_id, tag)
+        );
+
+        CREATE INDEX IF NOT EXISTS push_tags_commit ON push_tags (commit_sha);
+
+        CREATE TABLE IF NOT EXISTS pending_tags (
+            repo_path   TEXT NOT NULL,
+            remote      TEXT NOT NULL,
+            remote_url  TEXT NOT NULL,
+            name        TEXT NOT NULL,
+            object_sha  TEXT NOT NULL,
+            commit_sha  TEXT NOT NULL,
+            annotated   INTEGER NOT NULL,
+            pushed_at   INTEGER NOT NULL,
+            PRIMARY KEY (repo_path, remote, name)
+        );
+        ",
+    )?;
+
+    Ok(())
+}
+
+/// keep insertions and deletions apart instead of only their sum. the split was never
+/// stored for older pushes, so all of their changed lines count as insertions
+fn migrate_v9(conn: &DbConnection) -> Result<()> {
+    conn.execute_batch(
+        "
+        ALTER TABLE pushes ADD COLUMN insertions INTEGER NOT NULL DEFAULT 0;
+        ALTER TABLE pushes ADD COLUMN deletions INTEGER NOT NULL DEFAULT 0;
+        UPDATE pushes SET insertions = lines_changed;
+        ALTER TABLE pushes DROP COLUMN lines_changed;
+        ",
+    )?;
+
+    Ok(())
+}
+
+/// count commits made by bots separately, since they don't earn anything
+fn migrate_v10(conn: &DbConnection) -> Result<()> {
+    conn.execute_batch(
+        "
+        ALTER TABLE pushes ADD COLUMN automated_commits INTEGER NOT NULL DEFAULT 0;
+        ",
+    )?;
+
+    Ok(())
+}
+
+/// jj pushes are found in jj's operation log and deduped by change id
+fn migrate_v11(conn: &DbConnection) -> Result<()> {
+    conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS change_ids (
+            remote_url  TEXT NOT NULL,
+            change_id   TEXT NOT NULL,
+            PRIMARY KEY (remote_url, change_id)
+        );
+
+        CREATE TABLE IF NOT EXISTS jj_operations (
+            repo_path  TEXT PRIMARY KEY,
+            op_id      TEXT NOT NULL
+        );
+        ",
+    )?;
+
+    Ok(())
+}
+
+/// `party watch` polls a list of repos and queues the pushes it finds for a party later
+fn migrate_v12(conn: &DbConnection) -> Result<()> {
+    conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS watched_repos (
+            path      TEXT PRIMARY KEY,
+            added_at  INTEGER NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS queued_pushes (
+            id         INTEGER PRIMARY KEY AUTOINCREMENT,
+            timestamp  INTEGER NOT NULL,
+            push       TEXT NOT NULL
+        );
+        ",
+    )?;
+
+    Ok(())
+}
+
+/// every repo party looks after: the ones `party init` installed into, and the ones
+/// `party watch` polls, which move over from their own table. also the repo ids of
+/// their remotes, so that push history can be summarized per repo
+fn migrate_v13(conn: &DbConnection) -> Result<()> {
+    conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS repos (
+            path       TEXT PRIMARY KEY,
+            vcs        TEXT NOT NULL,
+            added_at   INTEGER NOT NULL,
+            installed  INTEGER NOT NULL DEFAULT 1,
+            watched    INTEGER NOT NULL DEFAULT 0
+        );
+
+        CREATE TABLE IF NOT EXISTS repo_remotes (
+            repo_path   TEXT NOT NULL REFERENCES repos(path) ON DELETE CASCADE,
+            remote_url  TEXT NOT NULL,
+            PRIMARY KEY (repo_path, remote_url)
+        );
+
+        INSERT INTO repos (path, vcs, added_at, installed, watched)
+        SELECT path, 'git', added_at, 0, 1 FROM watched_repos;
+
+        DROP TABLE watched_repos;
+        ",
+    )?;
+
+    Ok(())
+}
+
+/// players can prestige once every bonus track is maxed out
+fn migrate_v14(conn: &DbConnection) -> Result<()> {
+    conn.execute_batch(
+        "
+        ALTER TABLE player ADD COLUMN prestige_level INTEGER NOT NULL DEFAULT 0;
+        ",
+    )?;
+
+    Ok(())
+}
+
+/// bonus track levels sold back in the store, for the record and for the cooldown
+/// between them. also what each level was bought for, so selling it back refunds part
+/// of the price paid. levels bought before this are refunded from their current cost
+fn migrate_v15(conn: &DbConnection) -> Result<()> {
+    conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS respecs (
+            timestamp  INTEGER NOT NULL,
+            track_id   TEXT NOT NULL,
+            level      INTEGER NOT NULL,
+            refund     INTEGER NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS level_prices (
+            track_id  TEXT NOT NULL,
+            level     INTEGER NOT NULL,
+            cost      INTEGER NOT NULL,
+            PRIMARY KEY (track_id, level)
+        );
+        ",
+    )?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn v4_moves_branches_into_child_table() {
+        let conn = DbConnection::create_in_memory_at(3).unwrap();
+        conn.execute_batch(
+            "
+            INSERT INTO pushes VALUES (100, 'url/a.git', 'main', 2, 10, 4);
+            INSERT INTO pushes VALUES (200, 'url/b.git', 'feature', 1, 5, 2);
+            ",
+        )
+        .unwrap();
+
+        conn.run_migrations().unwrap();
+
+        let mut stmt = conn
+            .prepare(
+                "
+                SELECT p.timestamp, b.branch, b.commits
+                FROM pushes p JOIN push_branches b ON b.push_id = p.id
+                ORDER BY p.timestamp
+                ",
+            )
+            .unwrap();
+        let rows: Vec<(i64, String, i64)> = stmt
+            .query_map([], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+
+        assert_eq!(
+            rows,
+            vec![
+                (100, "main".to_string(), 2),
+                (200, "feature".to_string(), 1)
+            ]
+        );
+
+        // deleting a push cascades to its branches
+        conn.execute("DELETE FROM pushes WHERE timestamp = 100", ())
+            .unwrap();
+        let remaining: i64 = conn
+            .query_one("SELECT COUNT(*) FROM push_branches", (), |r| r.get(0))
+            .unwrap();
+        assert_eq!(remaining, 1);
+    }
+
+    #[test]
+    fn v5_marks_remotes_with_refs_as_known() {
+        let conn = DbConnection::create_in_memory_at(4).unwrap();
+        conn.execute_batch(
+            "
+            INSERT INTO branch_refs VALUES ('url/a.git', 'main', 'abc');
+            INSERT INTO branch_refs VALUES ('url/a.git', 'feature', 'def');
+            INSERT INTO patch_ids VALUES ('url/b.git', '123');
+            ",
+        )
+        .unwrap();
+
+        conn.run_migrations().unwrap();
+
+        let mut stmt = conn
+            .prepare("SELECT remote_url FROM known_remotes ORDER BY remote_url")
+            .unwrap();
+        let rows: Vec<String> = stmt
+            .query_map([], |r| r.get(0))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+
+        assert_eq!(rows, vec!["url/a.git", "url/b.git"]);
+    }
+
+    #[test]
+    fn v6_merges_urls_of_the_same_repo() {
+        let conn = DbConnection::create_in_memory_at(5).unwrap();
+        conn.execute_batch(
+            "
+            INSERT INTO pushes (timestamp, remote_url, commits, lines_changed, points_earned)
+            VALUES (100, 'git@github.com:me/x.git', 1, 1, 1),
+                   (200, 'https://github.com/me/x', 1, 1, 1),
+                   (300, '/tmp/local', 1, 1, 1),
+                   (400, 'https://Git.Example.com/Me/x.git', 1, 1, 1),
+                   (500, 'git@github.com:Me/X.git', 1, 1, 1);
+            INSERT INTO patch_ids VALUES ('git@github.com:me/x.git', 'p1');
+            INSERT INTO patch_ids VALUES ('https://github.com/me/x', 'p1');
+            INSERT INTO patch_ids VALUES ('https://github.com/me/x', 'p2');
+            INSERT INTO branch_refs VALUES ('git@github.com:me/x.git', 'main', 'abc');
+            INSERT INTO branch_refs VALUES ('ssh://git@github.com/me/x.git', 'main', 'def');
+            INSERT INTO known_remotes VALUES ('git@github.com:me/x.git');
+            INSERT INTO known_remotes VALUES ('https://github.com/me/x');
+            ",
+        )
+        .unwrap();
+
+        conn.run_migrations().unwrap();
+
+        let strings = |sql: &str| -> Vec<String> {
+            conn.prepare(sql)
+                .unwrap()
+                .query_map([], |r| r.get(0))
+                .unwrap()
+                .collect::<rusqlite::Result<_>>()
+                .unwrap()
+        };
+
+        assert_eq!(
+            strings("SELECT remote_url FROM pushes ORDER BY timestamp"),
+            vec![
+                "github.com/me/x",
+                "github.com/me/x",
+                "/tmp/local",
+                // the path keeps its case, except on hosts that ignore it
+                "git.example.com/Me/x",
+                "github.com/me/x"
+            ]
+        );
+        assert_eq!(
+            strings("SELECT remote_url || ' ' || patch_id FROM patch_ids ORDER BY patch_id"),
+            vec!["github.com/me/x p1", "github.com/me/x p2"]
+        );
+        assert_eq!(
+            strings("SELECT remote_url || ' ' || branch FROM branch_refs"),
+            vec!["github.com/me/x main"]
+        );
+        assert_eq!(
+            strings("SELECT remote_url FROM known_remotes"),
+            vec!["github.com/me/x"]
+        );
+    }
+
+    #[test]
+    fn v7_adds_discard_columns_with_zero_defaults() {
+        let conn = DbConnection::create_in_memory_at(6).unwrap();
+        conn.execute_batch(
+            "
+            INSERT INTO pushes (timestamp, remote_url, commits, lines_changed, points_earned)
+            VALUES (100, 'github.com/me/x', 2, 10, 4);
+            ",
+        )
+        .unwrap();
+
+        conn.run_migrations().unwrap();
+
+        let row: (i64, i64) = conn
+            .query_one(
+                "SELECT discarded_commits, points_clawed_back FROM pushes",
+                (),
+                |r| Ok((r.get(0)?, r.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(row, (0, 0));
+    }
+
+    #[test]
+    fn v9_splits_lines_changed_into_insertions() {
+        let conn = DbConnection::create_in_memory_at(8).unwrap();
+        conn.execute_batch(
+            "
+            INSERT INTO pushes (timestamp, remote_url, commits, lines_changed, points_earned)
+            VALUES (100, 'github.com/me/x', 2, 10, 4);
+            ",
+        )
+        .unwrap();
+
+        conn.run_migrations().unwrap();
+
+        let row: (i64, i64) = conn
+            .query_one("SELECT insertions, deletions FROM pushes", (), |r| {
+                Ok((r.get(0)?, r.get(1)?))
+            })
+            .unwrap();
+        assert_eq!(row, (10, 0));
+    }
+
+    #[test]
+    fn v10_adds_automated_commits_with_zero_default() {
+        let conn = DbConnection::create_in_memory_at(9).unwrap();
+        conn.execute_batch(
+            "
+            INSERT INTO pushes (timestamp, remote_url, commits, insertions, points_earned)
+            VALUES (100, 'github.com/me/x', 2, 10, 4);
+            ",
+        )
+        .unwrap();
+
+        conn.run_migrations().unwrap();
+
+        let automated: i64 = conn
+            .query_one("SELECT automated_commits FROM pushes", (), |r| r.get(0))
+            .unwrap();
+        assert_eq!(automated, 0);
+    }
+
+    #[test]
+    fn v13_moves_watched_repos_into_the_registry() {
+        let conn = DbConnection::create_in_memory_at(12).unwrap();
+        conn.execute_batch(
+            "
+            INSERT INTO watched_repos (path, added_at) VALUES ('/code/watched', 400);
+            ",
+        )
+        .unwrap();
+
+        conn.run_migrations().unwrap();
+
+        let rows: Vec<(String, String, i64, bool, bool)> = conn
+            .prepare("SELECT path, vcs, added_at, installed, watched FROM repos")
+            .unwrap()
+            .query_map([], |r| {
+                Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?, r.get(4)?))
+            })
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        assert_eq!(
+            rows,
+            vec![("/code/watched".into(), "git".into(), 400, false, true)]
+        );
+    }
+}
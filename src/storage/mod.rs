@@ -4,20 +4,43 @@ use std::path::PathBuf;
 mod branch_refs;
 mod connection;
 pub mod game_state;
+mod jj;
 mod logs;
 mod migrations;
 mod patch_ids;
 mod pushes;
+mod repos;
 mod state;
+mod tags;
+mod watch;
 
 pub use branch_refs::BranchRefsStore;
 pub use connection::DbConnection;
+pub use jj::JjStore;
 pub use logs::log;
 pub use patch_ids::PatchIdStore;
-pub use pushes::{PushEntry, PushHistory};
+pub use pushes::{PushEntry, PushHistory, RepoStats};
+pub use repos::{RepoEntry, RepoRegistry, Vcs};
+pub use tags::{PendingTag, TagStore};
+pub use watch::WatchStore;
 
 const APP_DIR_NAME: &str = "post-push-party";
 
+/// where `party init --global` puts the hooks that `core.hooksPath` points to
+pub fn global_hooks_dir() -> Result<PathBuf> {
+    storage_dir().map(|p| p.join("hooks"))
+}
+
+/// where custom bonus tracks are declared, see `bonus_track::custom`
+pub fn custom_bonuses_path() -> Result<PathBuf> {
+    storage_dir().map(|p| p.join("bonuses.toml"))
+}
+
+/// where economy numbers are tuned, see `balance`
+pub fn balance_path() -> Result<PathBuf> {
+    storage_dir().map(|p| p.join("balance.json"))
+}
+
 fn storage_dir() -> Result<PathBuf> {
     // allow overriding with env var (mostly for e2e tests)
     if let Ok(dir) = std::env::var("PARTY_STATE_DIR") {
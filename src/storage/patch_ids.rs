@@ -26,6 +26,15 @@ impl<'a> PatchIdStore<'a> {
             |r| r.get(0),
         )
     }
+
+    /// lets a commit be credited again, once the points it earned were taken back
+    pub fn forget(&self, remote_url: &str, patch_id: &str) -> Result<()> {
+        let _ = self.conn.execute(
+            "DELETE FROM patch_ids WHERE remote_url = ?1 AND patch_id = ?2",
+            (remote_url, patch_id),
+        )?;
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -44,4 +53,17 @@ mod tests {
         assert!(store.contains("remote-url-2", "patch-id-2").unwrap());
         assert!(!store.contains("remote-url-1", "patch-id-2").unwrap());
     }
+
+    #[test]
+    fn forgotten_patch_ids_can_be_recorded_again() {
+        let conn = DbConnection::create_in_memory().unwrap();
+        let store = PatchIdStore::new(&conn);
+
+        store.record("remote-url-1", "patch-id-1").unwrap();
+        store.record("remote-url-2", "patch-id-1").unwrap();
+        store.forget("remote-url-1", "patch-id-1").unwrap();
+
+        assert!(!store.contains("remote-url-1", "patch-id-1").unwrap());
+        assert!(store.contains("remote-url-2", "patch-id-1").unwrap());
+    }
 }
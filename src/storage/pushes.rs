@@ -1,18 +1,42 @@
+use std::collections::HashMap;
+
 use rusqlite::Result;
 
-use crate::{clock::Clock, storage::DbConnection};
+use crate::{clock::Clock, git::Push, storage::DbConnection};
+
+/// which rows of `pushes` were the player pushing their own commits. everything else,
+/// pushes of only bot commits, tags or discarded commits, is kept for the totals but
+/// isn't a push
+const COUNTS_AS_PUSH: &str = "commits > 0";
 
 #[derive(Debug, Clone)]
 pub struct PushEntry {
     timestamp: u64, // unix timestamp
     remote_url: String,
 
-    #[allow(dead_code)]
-    branch: String,
+    /// every branch touched by the push, with how many new commits it received
+    branches: Vec<(String, u64)>,
 
     commits: u64,
-    lines_changed: u64,
+
+    /// commits made by bots, which earn nothing and don't count as pushing
+    automated_commits: u64,
+
+    /// lines added and removed across all text files in the push's commits
+    insertions: u64,
+    deletions: u64,
+
     points_earned: u64,
+
+    /// previously credited commits that the push threw away, and the points taken back
+    discarded_commits: u64,
+    points_clawed_back: u64,
+
+    /// tags created by the push, with the commit each points to
+    tags: Vec<(String, String)>,
+
+    /// points each commit earned by patch-id, negative for ones clawed back
+    commit_points: Vec<(String, i64)>,
 }
 
 impl Default for PushEntry {
@@ -20,10 +44,16 @@ impl Default for PushEntry {
         Self {
             timestamp: 0,
             remote_url: "git@github.com:user/repo.git".to_string(),
-            branch: "main".to_string(),
+            branches: vec![("main".to_string(), 1)],
             commits: 1,
-            lines_changed: 0,
+            automated_commits: 0,
+            insertions: 0,
+            deletions: 0,
             points_earned: 0,
+            discarded_commits: 0,
+            points_clawed_back: 0,
+            tags: Vec::new(),
+            commit_points: Vec::new(),
         }
     }
 }
@@ -32,26 +62,61 @@ impl PushEntry {
     pub fn new(
         timestamp: u64,
         remote_url: String,
-        branch: String,
+        branches: Vec<(String, u64)>,
         commits: u64,
-        lines_changed: u64,
+        insertions: u64,
+        deletions: u64,
         points_earned: u64,
     ) -> Self {
         Self {
             timestamp,
             remote_url,
-            branch,
+            branches,
             commits,
-            lines_changed,
+            automated_commits: 0,
+            insertions,
+            deletions,
             points_earned,
+            discarded_commits: 0,
+            points_clawed_back: 0,
+            tags: Vec::new(),
+            commit_points: Vec::new(),
         }
     }
 
+    pub fn with_tags(self, tags: Vec<(String, String)>) -> Self {
+        Self { tags, ..self }
+    }
+
+    pub fn with_commit_points(self, commit_points: Vec<(String, i64)>) -> Self {
+        Self {
+            commit_points,
+            ..self
+        }
+    }
+
+    pub fn with_automated(self, automated_commits: u64) -> Self {
+        Self {
+            automated_commits,
+            ..self
+        }
+    }
+
+    pub fn with_discarded(self, discarded_commits: u64, points_clawed_back: u64) -> Self {
+        Self {
+            discarded_commits,
+            points_clawed_back,
+            ..self
+        }
+    }
+
+    #[cfg(any(test, feature = "dev"))]
     pub fn with_current_time(
         remote_url: String,
-        branch: String,
+        branches: Vec<(String, u64)>,
         commits: u64,
-        lines_changed: u64,
+        insertions: u64,
+        deletions: u64,
         points_earned: u64,
     ) -> Self {
         let timestamp = std::time::SystemTime::now()
@@ -62,13 +127,40 @@ impl PushEntry {
         Self::new(
             timestamp,
             remote_url,
-            branch,
+            branches,
             commits,
-            lines_changed,
+            insertions,
+            deletions,
             points_earned,
         )
     }
 
+    /// summarizes a push that happened at `timestamp`
+    pub fn from_push(timestamp: u64, push: &Push, points_earned: u64) -> Self {
+        let branches = push
+            .branches()
+            .iter()
+            .map(|b| (b.name().to_string(), b.commits().len() as u64))
+            .collect();
+
+        Self::new(
+            timestamp,
+            push.remote_url().to_string(),
+            branches,
+            push.commits().len() as u64,
+            push.commits().iter().map(|c| c.insertions()).sum(),
+            push.commits().iter().map(|c| c.deletions()).sum(),
+            points_earned,
+        )
+        .with_automated(push.automated_commits())
+        .with_tags(
+            push.tags()
+                .iter()
+                .map(|t| (t.name().to_string(), t.commit_sha().to_string()))
+                .collect(),
+        )
+    }
+
     #[cfg(test)]
     pub fn at(timestamp: u64) -> Self {
         Self {
@@ -95,8 +187,8 @@ impl PushEntry {
     }
 
     #[cfg(test)]
-    pub fn branch(&self) -> &str {
-        &self.branch
+    pub fn branches(&self) -> &[(String, u64)] {
+        &self.branches
     }
 
     #[cfg(test)]
@@ -104,15 +196,42 @@ impl PushEntry {
         self.commits
     }
 
+    /// whether this was the player pushing. a push with none of their own commits, only
+    /// bot commits, tags or discarded commits, doesn't count. keep in line with
+    /// `COUNTS_AS_PUSH`
+    pub fn counts_as_push(&self) -> bool {
+        self.commits > 0
+    }
+
+    #[cfg(test)]
+    pub fn insertions(&self) -> u64 {
+        self.insertions
+    }
+
     #[cfg(test)]
-    pub fn lines_changed(&self) -> u64 {
-        self.lines_changed
+    pub fn deletions(&self) -> u64 {
+        self.deletions
     }
 
     #[cfg(test)]
     pub fn points_earned(&self) -> u64 {
         self.points_earned
     }
+
+    #[cfg(test)]
+    pub fn tags(&self) -> &[(String, String)] {
+        &self.tags
+    }
+
+    #[cfg(test)]
+    pub fn discarded_commits(&self) -> u64 {
+        self.discarded_commits
+    }
+
+    #[cfg(test)]
+    pub fn points_clawed_back(&self) -> u64 {
+        self.points_clawed_back
+    }
 }
 
 /// numerical summary of activity during a given time period
@@ -120,8 +239,12 @@ pub struct HistoryStats {
     /// how many commits were pushed in total
     pub commits: u64,
 
-    /// how many lines were changed across all commits
-    pub lines: u64,
+    /// how many bot commits came along with them
+    pub automated: u64,
+
+    /// how many lines were added and removed across all commits
+    pub insertions: u64,
+    pub deletions: u64,
 
     /// how many points were earned (from pushes)
     pub points: u64,
@@ -134,10 +257,20 @@ pub struct HistoryStats {
 }
 
 impl HistoryStats {
-    pub fn new(commits: u64, lines: u64, points: u64, active_days: u64, max_points: u64) -> Self {
+    pub fn new(
+        commits: u64,
+        automated: u64,
+        insertions: u64,
+        deletions: u64,
+        points: u64,
+        active_days: u64,
+        max_points: u64,
+    ) -> Self {
         Self {
             commits,
-            lines,
+            automated,
+            insertions,
+            deletions,
             points,
             active_days,
             max_points,
@@ -145,6 +278,18 @@ impl HistoryStats {
     }
 }
 
+/// lifetime totals for the pushes to one repo
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RepoStats {
+    /// pushes of nothing but bot commits aren't counted
+    pub pushes: u64,
+    pub commits: u64,
+    pub insertions: u64,
+    pub deletions: u64,
+    pub points: u64,
+    pub last_push: u64,
+}
+
 pub struct PushHistory<'a> {
     conn: &'a DbConnection,
 }
@@ -161,118 +306,253 @@ impl<'a> PushHistory<'a> {
     }
 
     pub fn record(&self, entry: &PushEntry) -> Result<()> {
-        self.conn.execute(
+        let tx = self.conn.unchecked_transaction()?;
+
+        tx.execute(
             "
                 INSERT INTO pushes
-                    (timestamp, remote_url, branch, commits, lines_changed, points_earned)
-                    VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+                    (timestamp, remote_url, commits, insertions, deletions, points_earned,
+                     discarded_commits, points_clawed_back, automated_commits)
+                    VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
             ",
             (
                 entry.timestamp as i64,
                 &entry.remote_url,
-                &entry.branch,
                 entry.commits as i64,
-                entry.lines_changed as i64,
+                entry.insertions as i64,
+                entry.deletions as i64,
                 entry.points_earned as i64,
+                entry.discarded_commits as i64,
+                entry.points_clawed_back as i64,
+                entry.automated_commits as i64,
             ),
         )?;
+        let push_id = tx.last_insert_rowid();
+
+        {
+            let mut stmt = tx.prepare(
+                "INSERT OR REPLACE INTO push_branches (push_id, branch, commits) VALUES (?1, ?2, ?3)",
+            )?;
+            for (branch, commits) in &entry.branches {
+                stmt.execute((push_id, branch, *commits as i64))?;
+            }
+
+            let mut stmt = tx.prepare(
+                "INSERT OR REPLACE INTO push_tags (push_id, tag, commit_sha) VALUES (?1, ?2, ?3)",
+            )?;
+            for (tag, commit_sha) in &entry.tags {
+                stmt.execute((push_id, tag, commit_sha))?;
+            }
+
+            let mut stmt = tx.prepare(
+                "INSERT INTO commit_points (push_id, patch_id, points) VALUES (?1, ?2, ?3)",
+            )?;
+            for (patch_id, points) in &entry.commit_points {
+                stmt.execute((push_id, patch_id, points))?;
+            }
+        }
 
-        Ok(())
+        tx.commit()
     }
 
     pub fn entries_since(&self, timestamp: u64) -> Result<Vec<PushEntry>> {
+        // branches for every push in range, grouped by push id
+        let mut stmt = self.conn.prepare(
+            "
+                SELECT b.push_id, b.branch, b.commits
+                FROM push_branches b
+                JOIN pushes p ON p.id = b.push_id
+                WHERE p.timestamp >= ?1
+                ORDER BY b.rowid
+                ",
+        )?;
+        let mut branches: HashMap<i64, Vec<(String, u64)>> = HashMap::new();
+        let rows = stmt.query_map((timestamp as i64,), |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, i64>(2)? as u64,
+            ))
+        })?;
+        for (push_id, branch, commits) in rows.filter_map(|row| row.ok()) {
+            branches.entry(push_id).or_default().push((branch, commits));
+        }
+
+        // same for tags
+        let mut stmt = self.conn.prepare(
+            "
+                SELECT t.push_id, t.tag, t.commit_sha
+                FROM push_tags t
+                JOIN pushes p ON p.id = t.push_id
+                WHERE p.timestamp >= ?1
+                ORDER BY t.rowid
+                ",
+        )?;
+        let mut tags: HashMap<i64, Vec<(String, String)>> = HashMap::new();
+        let rows = stmt.query_map((timestamp as i64,), |row| {
+            Ok((row.get::<_, i64>(0)?, row.get(1)?, row.get(2)?))
+        })?;
+        for (push_id, tag, commit_sha) in rows.filter_map(|row| row.ok()) {
+            tags.entry(push_id).or_default().push((tag, commit_sha));
+        }
+
         let mut stmt = self.conn.prepare(
             "
                 SELECT 
-                    timestamp, remote_url, branch, commits, lines_changed, points_earned
+                    id, timestamp, remote_url, commits, insertions, deletions, points_earned,
+                    discarded_commits, points_clawed_back, automated_commits
                 FROM pushes
                 WHERE timestamp >= ?1
+                ORDER BY id
                 ",
         )?;
 
         let map = stmt.query_map((timestamp as i64,), |row| {
+            let push_id: i64 = row.get(0)?;
             Ok(PushEntry::new(
-                row.get::<_, i64>(0)? as u64,
-                row.get(1)?,
+                row.get::<_, i64>(1)? as u64,
                 row.get(2)?,
+                branches.remove(&push_id).unwrap_or_default(),
                 row.get::<_, i64>(3)? as u64,
                 row.get::<_, i64>(4)? as u64,
                 row.get::<_, i64>(5)? as u64,
-            ))
+                row.get::<_, i64>(6)? as u64,
+            )
+            .with_discarded(row.get::<_, i64>(7)? as u64, row.get::<_, i64>(8)? as u64)
+            .with_automated(row.get::<_, i64>(9)? as u64)
+            .with_tags(tags.remove(&push_id).unwrap_or_default()))
         })?;
 
         let entries = map.filter_map(|entry| entry.ok()).collect();
         Ok(entries)
     }
 
-    pub fn count_since(&self, timestamp: u64) -> Result<u32> {
+    /// whether any tag on this commit has been pushed to the repo before
+    pub fn is_tagged(&self, remote_url: &str, commit_sha: &str) -> Result<bool> {
         self.conn.query_one(
             "
+                SELECT EXISTS (
+                    SELECT 1
+                    FROM push_tags t
+                    JOIN pushes p ON p.id = t.push_id
+                    WHERE p.remote_url = ?1 AND t.commit_sha = ?2
+                )
+                ",
+            (remote_url, commit_sha),
+            |r| r.get(0),
+        )
+    }
+
+    /// points a commit earned when it was pushed to the repo, less any already
+    /// clawed back
+    pub fn commit_points(&self, remote_url: &str, patch_id: &str) -> Result<u64> {
+        let points: i64 = self.conn.query_one(
+            "
+                SELECT COALESCE( SUM(c.points), 0 )
+                FROM commit_points c
+                JOIN pushes p ON p.id = c.push_id
+                WHERE p.remote_url = ?1 AND c.patch_id = ?2
+                ",
+            (remote_url, patch_id),
+            |r| r.get(0),
+        )?;
+        Ok(points.max(0) as u64)
+    }
+
+    /// how many pushes the player made since the given time. see `COUNTS_AS_PUSH`
+    pub fn count_since(&self, timestamp: u64) -> Result<u32> {
+        self.conn.query_one(
+            &format!(
+                "
                 SELECT COUNT (*)
                 FROM pushes
-                WHERE timestamp >= ?1
-                ",
+                WHERE timestamp >= ?1 AND {COUNTS_AS_PUSH}
+                "
+            ),
             (timestamp as i64,),
             |r| r.get(0),
         )
     }
 
     pub fn stats_since(&self, timestamp: u64, tz_offset_secs: i32) -> Result<HistoryStats> {
-        let (commits, lines, points, days, max_pts) = self.conn.query_one(
-            "
+        let (commits, automated, insertions, deletions, points, days, max_pts) =
+            self.conn.query_one(
+                &format!(
+                    "
                 SELECT 
                     COALESCE( SUM(commits), 0 ),
-                    COALESCE( SUM(lines_changed), 0 ),
+                    COALESCE( SUM(automated_commits), 0 ),
+                    COALESCE( SUM(insertions), 0 ),
+                    COALESCE( SUM(deletions), 0 ),
                     COALESCE( SUM(points_earned), 0 ),
-                    COUNT( DISTINCT (timestamp + ?2) / ?3 ),
+                    COUNT( DISTINCT CASE WHEN {COUNTS_AS_PUSH} THEN (timestamp + ?2) / ?3 END ),
                     COALESCE( MAX(points_earned), 0 )
                 FROM pushes
                 WHERE timestamp >= ?1
-                ",
-            (timestamp as i64, tz_offset_secs, Clock::SECONDS_PER_DAY),
-            |r| {
-                Ok((
-                    r.get::<_, i64>(0)?,
-                    r.get::<_, i64>(1)?,
-                    r.get::<_, i64>(2)?,
-                    r.get::<_, i64>(3)?,
-                    r.get::<_, i64>(4)?,
-                ))
-            },
-        )?;
+                "
+                ),
+                (timestamp as i64, tz_offset_secs, Clock::SECONDS_PER_DAY),
+                |r| {
+                    Ok((
+                        r.get::<_, i64>(0)?,
+                        r.get::<_, i64>(1)?,
+                        r.get::<_, i64>(2)?,
+                        r.get::<_, i64>(3)?,
+                        r.get::<_, i64>(4)?,
+                        r.get::<_, i64>(5)?,
+                        r.get::<_, i64>(6)?,
+                    ))
+                },
+            )?;
 
         Ok(HistoryStats::new(
             commits as u64,
-            lines as u64,
+            automated as u64,
+            insertions as u64,
+            deletions as u64,
             points as u64,
             days.max(1) as u64,
             max_pts as u64,
         ))
     }
 
-    #[cfg(test)]
-    pub fn with_entries(self, entries: impl IntoIterator<Item = PushEntry>) -> Self {
-        let mut stmt = self
-            .conn
-            .prepare(
+    /// totals for every repo that has been pushed to, by repo id
+    pub fn stats_by_repo(&self) -> Result<HashMap<String, RepoStats>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "
+                SELECT
+                    remote_url,
+                    COUNT( CASE WHEN {COUNTS_AS_PUSH} THEN 1 END ),
+                    SUM(commits),
+                    SUM(insertions),
+                    SUM(deletions),
+                    SUM(points_earned),
+                    MAX(timestamp)
+                FROM pushes
+                GROUP BY remote_url
                 "
-                INSERT INTO pushes
-                    (timestamp, remote_url, branch, commits, lines_changed, points_earned)
-                    VALUES (?1, ?2, ?3, ?4, ?5, ?6)
-            ",
-            )
-            .expect("sql should work in tests");
+        ))?;
+        let rows = stmt.query_map([], |r| {
+            Ok((
+                r.get::<_, String>(0)?,
+                RepoStats {
+                    pushes: r.get::<_, i64>(1)? as u64,
+                    commits: r.get::<_, i64>(2)? as u64,
+                    insertions: r.get::<_, i64>(3)? as u64,
+                    deletions: r.get::<_, i64>(4)? as u64,
+                    points: r.get::<_, i64>(5)? as u64,
+                    last_push: r.get::<_, i64>(6)? as u64,
+                },
+            ))
+        })?;
 
+        rows.collect()
+    }
+
+    #[cfg(test)]
+    pub fn with_entries(self, entries: impl IntoIterator<Item = PushEntry>) -> Self {
         for entry in entries {
-            stmt.execute((
-                entry.timestamp() as i64,
-                entry.remote_url(),
-                entry.branch(),
-                entry.commits() as i64,
-                entry.lines_changed() as i64,
-                entry.points_earned() as i64,
-            ))
-            .expect("sql should work in tests");
+            self.record(&entry).expect("sql should work in tests");
         }
 
         self
@@ -290,9 +570,10 @@ mod tests {
 
         let entry = PushEntry::with_current_time(
             "url/repo.git".to_string(),
-            "main".to_string(),
+            vec![("main".to_string(), 5)],
             5,
-            120,
+            100,
+            20,
             42,
         );
         pushes.record(&entry).unwrap();
@@ -301,9 +582,10 @@ mod tests {
 
         assert_eq!(entries.len(), 1);
         assert_eq!(entries[0].remote_url(), "url/repo.git");
-        assert_eq!(entries[0].branch(), "main");
+        assert_eq!(entries[0].branches(), &[("main".to_string(), 5)]);
         assert_eq!(entries[0].commits(), 5);
-        assert_eq!(entries[0].lines_changed(), 120);
+        assert_eq!(entries[0].insertions(), 100);
+        assert_eq!(entries[0].deletions(), 20);
         assert_eq!(entries[0].points_earned(), 42);
 
         // reset to clear entries
@@ -312,15 +594,122 @@ mod tests {
         assert_eq!(entries.len(), 0);
     }
 
+    #[test]
+    fn discarded_commits_round_trip() {
+        let conn = DbConnection::create_in_memory().unwrap();
+        let pushes = PushHistory::new(&conn);
+
+        pushes
+            .record(&PushEntry::at(100).with_discarded(3, 12))
+            .unwrap();
+
+        let entries = pushes.entries_since(0).unwrap();
+        assert_eq!(entries[0].discarded_commits(), 3);
+        assert_eq!(entries[0].points_clawed_back(), 12);
+    }
+
+    #[test]
+    fn automated_only_pushes_are_counted_in_stats_but_not_as_pushes() {
+        let conn = DbConnection::create_in_memory().unwrap();
+        let bot_push = PushEntry::new(100, "url/repo.git".to_string(), vec![], 0, 0, 0, 0);
+        let pushes = PushHistory::new(&conn).with_entries([
+            PushEntry::at(100).with_automated(1),
+            bot_push.with_automated(3),
+        ]);
+
+        let entries = pushes.entries_since(0).unwrap();
+        assert!(entries[0].counts_as_push());
+        assert!(!entries[1].counts_as_push());
+
+        assert_eq!(pushes.count_since(0).unwrap(), 1);
+        let stats = pushes.stats_since(0, 0).unwrap();
+        assert_eq!((stats.commits, stats.automated), (1, 4));
+    }
+
+    #[test]
+    fn automated_only_days_are_not_active_days() {
+        let conn = DbConnection::create_in_memory().unwrap();
+        let bot_push = PushEntry::new(200_000, "url/repo.git".to_string(), vec![], 0, 0, 0, 0);
+        let pushes =
+            PushHistory::new(&conn).with_entries([PushEntry::at(100), bot_push.with_automated(2)]);
+
+        assert_eq!(pushes.stats_since(0, 0).unwrap().active_days, 1);
+    }
+
+    #[test]
+    fn commit_points_add_up_per_repo() {
+        let conn = DbConnection::create_in_memory().unwrap();
+        let pushes = PushHistory::new(&conn).with_entries([
+            PushEntry::with_repo(100, "url/a.git")
+                .with_commit_points(vec![("p1".to_string(), 6), ("p2".to_string(), 3)]),
+            PushEntry::with_repo(200, "url/b.git").with_commit_points(vec![("p1".to_string(), 9)]),
+            PushEntry::with_repo(300, "url/a.git").with_commit_points(vec![("p2".to_string(), -3)]),
+        ]);
+
+        assert_eq!(pushes.commit_points("url/a.git", "p1").unwrap(), 6);
+        assert_eq!(pushes.commit_points("url/b.git", "p1").unwrap(), 9);
+        assert_eq!(pushes.commit_points("url/a.git", "p2").unwrap(), 0);
+        assert_eq!(pushes.commit_points("url/a.git", "p3").unwrap(), 0);
+    }
+
+    #[test]
+    fn tag_only_pushes_are_not_counted_as_pushes() {
+        let conn = DbConnection::create_in_memory().unwrap();
+        let tags = vec![("v1.0.0".to_string(), "abc".to_string())];
+        let tag_push = PushEntry::new(200_000, "url/repo.git".to_string(), vec![], 0, 0, 0, 5);
+        let pushes = PushHistory::new(&conn)
+            .with_entries([PushEntry::at(100), tag_push.with_tags(tags.clone())]);
+
+        let entries = pushes.entries_since(0).unwrap();
+        assert!(!entries[1].counts_as_push());
+        assert_eq!(pushes.count_since(0).unwrap(), 1);
+        assert_eq!(pushes.count_since(200_000).unwrap(), 0);
+
+        let stats = pushes.stats_since(0, 0).unwrap();
+        assert_eq!((stats.points, stats.active_days), (5, 1));
+        assert_eq!(pushes.stats_by_repo().unwrap()["url/repo.git"].pushes, 0);
+    }
+
+    #[test]
+    fn discard_only_pushes_are_not_counted_as_pushes() {
+        let conn = DbConnection::create_in_memory().unwrap();
+        let force_push = PushEntry::new(200_000, "url/repo.git".to_string(), vec![], 0, 0, 0, 0);
+        let pushes = PushHistory::new(&conn)
+            .with_entries([PushEntry::at(100), force_push.with_discarded(2, 8)]);
+
+        let entries = pushes.entries_since(0).unwrap();
+        assert!(!entries[1].counts_as_push());
+        assert_eq!(pushes.count_since(200_000).unwrap(), 0);
+        assert_eq!(pushes.stats_since(0, 0).unwrap().active_days, 1);
+        assert_eq!(pushes.stats_by_repo().unwrap()["url/repo.git"].pushes, 0);
+    }
+
+    #[test]
+    fn tags_round_trip_and_mark_commits_as_tagged() {
+        let conn = DbConnection::create_in_memory().unwrap();
+        let pushes = PushHistory::new(&conn);
+
+        let tags = vec![("v1.0.0".to_string(), "abc".to_string())];
+        pushes
+            .record(&PushEntry::with_repo(100, "url/a.git").with_tags(tags.clone()))
+            .unwrap();
+
+        assert_eq!(pushes.entries_since(0).unwrap()[0].tags(), &tags[..]);
+        assert!(pushes.is_tagged("url/a.git", "abc").unwrap());
+        assert!(!pushes.is_tagged("url/b.git", "abc").unwrap());
+        assert!(!pushes.is_tagged("url/a.git", "def").unwrap());
+    }
+
     #[test]
     fn with_entries_and_entries_since() {
         let conn = DbConnection::create_in_memory().unwrap();
 
         let entry = PushEntry::with_current_time(
             "url/repo.git".to_string(),
-            "main".to_string(),
+            vec![("main".to_string(), 5)],
             5,
-            120,
+            100,
+            20,
             42,
         );
         let pushes = PushHistory::new(&conn).with_entries([entry]);
@@ -329,9 +718,10 @@ mod tests {
 
         assert_eq!(entries.len(), 1);
         assert_eq!(entries[0].remote_url(), "url/repo.git");
-        assert_eq!(entries[0].branch(), "main");
+        assert_eq!(entries[0].branches(), &[("main".to_string(), 5)]);
         assert_eq!(entries[0].commits(), 5);
-        assert_eq!(entries[0].lines_changed(), 120);
+        assert_eq!(entries[0].insertions(), 100);
+        assert_eq!(entries[0].deletions(), 20);
         assert_eq!(entries[0].points_earned(), 42);
     }
 
@@ -340,9 +730,30 @@ mod tests {
         let conn = DbConnection::create_in_memory().unwrap();
 
         let entries = [
-            PushEntry::with_current_time("url/repo.git".to_string(), "main".to_string(), 1, 2, 3),
-            PushEntry::with_current_time("url/repo.git".to_string(), "main".to_string(), 4, 5, 6),
-            PushEntry::with_current_time("url/repo.git".to_string(), "main".to_string(), 7, 8, 9),
+            PushEntry::with_current_time(
+                "url/repo.git".to_string(),
+                vec![("main".to_string(), 1)],
+                1,
+                2,
+                1,
+                3,
+            ),
+            PushEntry::with_current_time(
+                "url/repo.git".to_string(),
+                vec![("main".to_string(), 1)],
+                4,
+                5,
+                2,
+                6,
+            ),
+            PushEntry::with_current_time(
+                "url/repo.git".to_string(),
+                vec![("main".to_string(), 1)],
+                7,
+                8,
+                3,
+                9,
+            ),
         ];
         let pushes = PushHistory::new(&conn).with_entries(entries);
 
@@ -356,18 +767,114 @@ mod tests {
         let conn = DbConnection::create_in_memory().unwrap();
 
         let entries = [
-            PushEntry::with_current_time("url/repo.git".to_string(), "main".to_string(), 1, 2, 3),
-            PushEntry::with_current_time("url/repo.git".to_string(), "main".to_string(), 4, 5, 6),
-            PushEntry::with_current_time("url/repo.git".to_string(), "main".to_string(), 7, 8, 9),
+            PushEntry::with_current_time(
+                "url/repo.git".to_string(),
+                vec![("main".to_string(), 1)],
+                1,
+                2,
+                1,
+                3,
+            ),
+            PushEntry::with_current_time(
+                "url/repo.git".to_string(),
+                vec![("main".to_string(), 1)],
+                4,
+                5,
+                2,
+                6,
+            ),
+            PushEntry::with_current_time(
+                "url/repo.git".to_string(),
+                vec![("main".to_string(), 1)],
+                7,
+                8,
+                3,
+                9,
+            ),
         ];
         let pushes = PushHistory::new(&conn).with_entries(entries);
 
         let stats = pushes.stats_since(0, 0).unwrap();
 
         assert_eq!(stats.commits, 12);
-        assert_eq!(stats.lines, 15);
+        assert_eq!(stats.insertions, 15);
+        assert_eq!(stats.deletions, 6);
         assert_eq!(stats.points, 18);
         assert_eq!(stats.active_days, 1);
         assert_eq!(stats.max_points, 9);
     }
+
+    #[test]
+    fn records_every_branch_of_a_push() {
+        let conn = DbConnection::create_in_memory().unwrap();
+        let pushes = PushHistory::new(&conn).with_entries([
+            PushEntry::with_current_time(
+                "url/repo.git".to_string(),
+                vec![
+                    ("main".to_string(), 2),
+                    ("feature".to_string(), 3),
+                    ("docs".to_string(), 0),
+                ],
+                5,
+                10,
+                0,
+                5,
+            ),
+            PushEntry::with_current_time(
+                "url/repo.git".to_string(),
+                vec![("main".to_string(), 1)],
+                1,
+                1,
+                0,
+                1,
+            ),
+        ]);
+
+        let entries = pushes.entries_since(0).unwrap();
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(
+            entries[0].branches(),
+            &[
+                ("main".to_string(), 2),
+                ("feature".to_string(), 3),
+                ("docs".to_string(), 0)
+            ]
+        );
+        assert_eq!(entries[1].branches(), &[("main".to_string(), 1)]);
+
+        // branches go away with their pushes
+        pushes.reset().unwrap();
+        let orphans: i64 = conn
+            .query_one("SELECT COUNT(*) FROM push_branches", (), |r| r.get(0))
+            .unwrap();
+        assert_eq!(orphans, 0);
+    }
+
+    #[test]
+    fn stats_by_repo_sums_each_repo() {
+        let conn = DbConnection::create_in_memory().unwrap();
+        let pushes = PushHistory::new(&conn).with_entries([
+            PushEntry::new(100, "url/a".to_string(), vec![], 2, 10, 1, 4),
+            PushEntry::new(300, "url/a".to_string(), vec![], 1, 5, 0, 2),
+            PushEntry::new(400, "url/a".to_string(), vec![], 0, 0, 0, 0).with_automated(3),
+            PushEntry::new(200, "url/b".to_string(), vec![], 3, 7, 7, 6),
+        ]);
+
+        let stats = pushes.stats_by_repo().unwrap();
+
+        assert_eq!(
+            stats["url/a"],
+            RepoStats {
+                pushes: 2,
+                commits: 3,
+                insertions: 15,
+                deletions: 1,
+                points: 6,
+                last_push: 400,
+            }
+        );
+        assert_eq!(stats["url/b"].pushes, 1);
+        assert_eq!(stats["url/b"].last_push, 200);
+    }
 }
@@ -0,0 +1,303 @@
+use std::collections::HashMap;
+
+use rusqlite::{OptionalExtension, Result};
+
+use crate::storage::DbConnection;
+
+/// how party is installed in a repo
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Vcs {
+    /// git hooks
+    Git,
+
+    /// a `jj push` alias
+    Jj,
+}
+
+impl Vcs {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Vcs::Git => "git",
+            Vcs::Jj => "jj",
+        }
+    }
+
+    fn parse(s: &str) -> Self {
+        match s {
+            "jj" => Vcs::Jj,
+            _ => Vcs::Git,
+        }
+    }
+}
+
+/// a repo that party was installed into, or that `party watch` polls (or both)
+#[derive(Debug, Clone, PartialEq)]
+pub struct RepoEntry {
+    /// root of the working tree (or jj workspace)
+    pub path: String,
+    pub vcs: Vcs,
+
+    /// repo ids of its remotes, as of when it was registered
+    pub remote_urls: Vec<String>,
+    pub added_at: u64,
+
+    /// `party init` installed the hook (or jj alias)
+    pub installed: bool,
+
+    /// `party watch` polls it for pushes
+    pub watched: bool,
+}
+
+/// every repo `party init` has installed into or `party watch` has been told about.
+/// a repo is forgotten once it's neither
+pub struct RepoRegistry<'a> {
+    conn: &'a DbConnection,
+}
+
+impl<'a> RepoRegistry<'a> {
+    pub fn new(conn: &'a DbConnection) -> Self {
+        Self { conn }
+    }
+
+    /// marks a repo as installed into, adding it or refreshing it if it's already known
+    pub fn register(&self, entry: &RepoEntry) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+
+        tx.execute(
+            "
+                INSERT INTO repos (path, vcs, added_at, installed, watched)
+                VALUES (?1, ?2, ?3, 1, 0)
+                ON CONFLICT (path) DO UPDATE SET vcs = excluded.vcs, installed = 1
+            ",
+            (&entry.path, entry.vcs.as_str(), entry.added_at as i64),
+        )?;
+        Self::set_remotes(&tx, entry)?;
+
+        tx.commit()
+    }
+
+    /// marks a repo as watched, adding it if it isn't known yet.
+    /// returns false if it was already being watched
+    pub fn watch(&self, entry: &RepoEntry) -> Result<bool> {
+        let tx = self.conn.unchecked_transaction()?;
+
+        let was_watched = tx
+            .query_row(
+                "SELECT watched FROM repos WHERE path = ?1",
+                (&entry.path,),
+                |r| r.get(0),
+            )
+            .optional()?
+            .unwrap_or(false);
+        tx.execute(
+            "
+                INSERT INTO repos (path, vcs, added_at, installed, watched)
+                VALUES (?1, ?2, ?3, 0, 1)
+                ON CONFLICT (path) DO UPDATE SET watched = 1
+            ",
+            (&entry.path, entry.vcs.as_str(), entry.added_at as i64),
+        )?;
+        Self::set_remotes(&tx, entry)?;
+
+        tx.commit()?;
+        Ok(!was_watched)
+    }
+
+    fn set_remotes(tx: &rusqlite::Transaction, entry: &RepoEntry) -> Result<()> {
+        tx.execute(
+            "DELETE FROM repo_remotes WHERE repo_path = ?1",
+            (&entry.path,),
+        )?;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT OR IGNORE INTO repo_remotes (repo_path, remote_url) VALUES (?1, ?2)",
+            )?;
+            for remote_url in &entry.remote_urls {
+                stmt.execute((&entry.path, remote_url))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// party was removed from the repo. returns false if it wasn't installed
+    pub fn unregister(&self, path: &str) -> Result<bool> {
+        self.clear_flag(path, "installed")
+    }
+
+    /// returns false if the repo wasn't being watched
+    pub fn unwatch(&self, path: &str) -> Result<bool> {
+        self.clear_flag(path, "watched")
+    }
+
+    fn clear_flag(&self, path: &str, flag: &str) -> Result<bool> {
+        let tx = self.conn.unchecked_transaction()?;
+        let cleared = tx.execute(
+            &format!("UPDATE repos SET {flag} = 0 WHERE path = ?1 AND {flag}"),
+            (path,),
+        )?;
+        tx.execute(
+            "DELETE FROM repos WHERE path = ?1 AND NOT installed AND NOT watched",
+            (path,),
+        )?;
+        tx.commit()?;
+        Ok(cleared > 0)
+    }
+
+    /// drops a repo altogether, whether it's installed into, watched or both
+    pub fn forget(&self, path: &str) -> Result<()> {
+        self.conn
+            .execute("DELETE FROM repos WHERE path = ?1", (path,))?;
+        Ok(())
+    }
+
+    /// the paths of every watched repo, in the order they were added
+    pub fn watched(&self) -> Result<Vec<String>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT path FROM repos WHERE watched ORDER BY added_at, path")?;
+        let paths = stmt.query_map([], |r| r.get(0))?.collect::<Result<_>>()?;
+        Ok(paths)
+    }
+
+    /// every registered repo, in the order they were added
+    pub fn all(&self) -> Result<Vec<RepoEntry>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT repo_path, remote_url FROM repo_remotes ORDER BY rowid")?;
+        let mut remotes: HashMap<String, Vec<String>> = HashMap::new();
+        let rows = stmt.query_map([], |r| Ok((r.get::<_, String>(0)?, r.get(1)?)))?;
+        for (path, remote_url) in rows.filter_map(|row| row.ok()) {
+            remotes.entry(path).or_default().push(remote_url);
+        }
+
+        let mut stmt = self.conn.prepare(
+            "SELECT path, vcs, added_at, installed, watched FROM repos ORDER BY added_at, path",
+        )?;
+        let repos = stmt
+            .query_map([], |r| {
+                let path: String = r.get(0)?;
+                Ok(RepoEntry {
+                    remote_urls: remotes.remove(&path).unwrap_or_default(),
+                    path,
+                    vcs: Vcs::parse(&r.get::<_, String>(1)?),
+                    added_at: r.get::<_, i64>(2)? as u64,
+                    installed: r.get(3)?,
+                    watched: r.get(4)?,
+                })
+            })?
+            .collect::<Result<_>>()?;
+        Ok(repos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(path: &str, vcs: Vcs, remote_urls: &[&str], added_at: u64) -> RepoEntry {
+        RepoEntry {
+            path: path.to_string(),
+            vcs,
+            remote_urls: remote_urls.iter().map(|u| u.to_string()).collect(),
+            added_at,
+            installed: true,
+            watched: false,
+        }
+    }
+
+    #[test]
+    fn register_and_list() {
+        let conn = DbConnection::create_in_memory().unwrap();
+        let repos = RepoRegistry::new(&conn);
+
+        repos
+            .register(&entry("/code/b", Vcs::Jj, &["github.com/me/b"], 200))
+            .unwrap();
+        repos
+            .register(&entry(
+                "/code/a",
+                Vcs::Git,
+                &["github.com/me/a", "github.com/fork/a"],
+                100,
+            ))
+            .unwrap();
+
+        assert_eq!(
+            repos.all().unwrap(),
+            vec![
+                entry(
+                    "/code/a",
+                    Vcs::Git,
+                    &["github.com/me/a", "github.com/fork/a"],
+                    100
+                ),
+                entry("/code/b", Vcs::Jj, &["github.com/me/b"], 200),
+            ]
+        );
+    }
+
+    #[test]
+    fn registering_again_refreshes_remotes_but_keeps_added_at() {
+        let conn = DbConnection::create_in_memory().unwrap();
+        let repos = RepoRegistry::new(&conn);
+
+        repos
+            .register(&entry("/code/a", Vcs::Git, &["old/a"], 100))
+            .unwrap();
+        repos
+            .register(&entry("/code/a", Vcs::Git, &["new/a"], 300))
+            .unwrap();
+
+        assert_eq!(
+            repos.all().unwrap(),
+            vec![entry("/code/a", Vcs::Git, &["new/a"], 100)]
+        );
+    }
+
+    #[test]
+    fn unregister_removes_remotes_too() {
+        let conn = DbConnection::create_in_memory().unwrap();
+        let repos = RepoRegistry::new(&conn);
+
+        repos
+            .register(&entry("/code/a", Vcs::Git, &["github.com/me/a"], 100))
+            .unwrap();
+
+        assert!(repos.unregister("/code/a").unwrap());
+        assert!(!repos.unregister("/code/a").unwrap());
+        assert!(repos.all().unwrap().is_empty());
+
+        let remotes: i64 = conn
+            .query_one("SELECT COUNT(*) FROM repo_remotes", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(remotes, 0);
+    }
+
+    #[test]
+    fn watched_and_installed_share_one_entry() {
+        let conn = DbConnection::create_in_memory().unwrap();
+        let repos = RepoRegistry::new(&conn);
+        let repo = entry("/code/a", Vcs::Git, &["github.com/me/a"], 100);
+
+        assert!(repos.watch(&repo).unwrap());
+        assert!(!repos.watch(&repo).unwrap());
+        assert!(!repos.all().unwrap()[0].installed);
+        repos.register(&repo).unwrap();
+        assert_eq!(
+            repos.all().unwrap(),
+            vec![RepoEntry {
+                watched: true,
+                ..repo.clone()
+            }]
+        );
+        assert_eq!(repos.watched().unwrap(), vec!["/code/a"]);
+
+        // still watched after party is removed, and forgotten once it isn't either
+        assert!(repos.unregister("/code/a").unwrap());
+        assert_eq!(repos.watched().unwrap(), vec!["/code/a"]);
+        assert!(!repos.all().unwrap()[0].installed);
+        assert!(repos.unwatch("/code/a").unwrap());
+        assert!(!repos.unwatch("/code/a").unwrap());
+        assert!(repos.all().unwrap().is_empty());
+    }
+}
@@ -7,20 +7,21 @@ use rusqlite::{
 
 use crate::{
     pack::Pack,
-    state::{PaletteSelection, State},
+    state::{PaletteSelection, Respec, State},
     storage::DbConnection,
 };
 
 impl State {
     pub fn load(conn: &DbConnection) -> RusqliteResult<Self> {
         // player
-        let (party_points, points_earned, packs_earned): (i64, i64, i64) = conn.query_one(
-            "
-            SELECT party_points, points_earned, packs_earned from player WHERE id = 1;
-        ",
-            (),
-            |x| Ok((x.get(0)?, x.get(1)?, x.get(2)?)),
-        )?;
+        let (party_points, points_earned, packs_earned, prestige_level): (i64, i64, i64, u32) =
+            conn.query_one(
+                "
+                SELECT party_points, points_earned, packs_earned, prestige_level from player WHERE id = 1;
+            ",
+                (),
+                |x| Ok((x.get(0)?, x.get(1)?, x.get(2)?, x.get(3)?)),
+            )?;
 
         // bonus tracks
         let mut stmt = conn.prepare("SELECT id, level FROM bonus_tracks")?;
@@ -69,6 +70,29 @@ impl State {
             .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
             .collect::<RusqliteResult<HashMap<String, u32>>>()?;
 
+        // respecs
+        let mut stmt = conn.prepare(
+            "SELECT timestamp, track_id, level, refund FROM respecs ORDER BY timestamp, rowid",
+        )?;
+        let respecs = stmt
+            .query_map([], |row| {
+                Ok(Respec {
+                    timestamp: row.get::<_, i64>(0)? as u64,
+                    track_id: row.get(1)?,
+                    level: row.get(2)?,
+                    refund: row.get::<_, i64>(3)? as u64,
+                })
+            })?
+            .collect::<RusqliteResult<Vec<Respec>>>()?;
+
+        // what each bonus level was bought for
+        let mut stmt = conn.prepare("SELECT track_id, level, cost FROM level_prices")?;
+        let level_prices = stmt
+            .query_map([], |row| {
+                Ok(((row.get(0)?, row.get(1)?), row.get::<_, i64>(2)? as u64))
+            })?
+            .collect::<RusqliteResult<HashMap<(String, u32), u64>>>()?;
+
         let state = Self::new(
             party_points as u64,
             points_earned as u64,
@@ -80,6 +104,9 @@ impl State {
             active_palettes,
             packs,
             games,
+            prestige_level,
+            respecs,
+            level_prices,
         );
         Ok(state)
     }
@@ -93,7 +120,8 @@ impl State {
                  UPDATE player SET
                      party_points = ?1,
                      points_earned = ?2,
-                     packs_earned = ?3
+                     packs_earned = ?3,
+                     prestige_level = ?4
                      WHERE id = 1;
     
             ",
@@ -101,6 +129,7 @@ impl State {
                 self.party_points as i64,
                 self.lifetime_points_earned as i64,
                 self.lifetime_packs_earned as i64,
+                self.prestige_level,
             ),
         )?;
 
@@ -161,6 +190,32 @@ impl State {
             }
         }
 
+        // respecs
+        {
+            tx.execute("DELETE FROM respecs", ())?;
+            let mut stmt = tx.prepare(
+                "INSERT INTO respecs (timestamp, track_id, level, refund) VALUES (?1, ?2, ?3, ?4)",
+            )?;
+            for respec in &self.respecs {
+                stmt.execute((
+                    respec.timestamp as i64,
+                    &respec.track_id,
+                    respec.level,
+                    respec.refund as i64,
+                ))?;
+            }
+        }
+
+        // level prices
+        {
+            tx.execute("DELETE FROM level_prices", ())?;
+            let mut stmt =
+                tx.prepare("INSERT INTO level_prices (track_id, level, cost) VALUES (?1, ?2, ?3)")?;
+            for ((track_id, level), cost) in &self.level_prices {
+                stmt.execute((track_id, level, *cost as i64))?;
+            }
+        }
+
         tx.commit()?;
 
         Ok(())
@@ -180,6 +235,7 @@ mod state_storage_tests {
         let mut state = State {
             lifetime_points_earned: 12,
             party_points: 42,
+            prestige_level: 2,
             ..State::default()
         };
         state.set_bonus_level("commit_value", 3);
@@ -190,6 +246,13 @@ mod state_storage_tests {
         state.set_selected_palette("exclamations", 3);
         state.add_pack(Pack::Basic);
         state.add_game_token(&SNAKE);
+        state.respecs.push(Respec {
+            timestamp: 1000,
+            track_id: "first_push".to_string(),
+            level: 3,
+            refund: 1500,
+        });
+        state.buy_bonus_level("streak", 1, 0);
 
         state.save(&conn).unwrap();
         let loaded = State::load(&conn).unwrap();
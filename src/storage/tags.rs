@@ -0,0 +1,144 @@
+use rusqlite::Result;
+
+use crate::storage::DbConnection;
+
+/// a tag the pre-push hook saw being sent, not yet known to have reached the remote
+#[derive(Debug, Clone, PartialEq)]
+pub struct PendingTag {
+    /// the repo it was pushed from
+    pub repo_path: String,
+
+    /// the remote as given to `git push`, which is a name or a url
+    pub remote: String,
+
+    /// repo id of the remote
+    pub remote_url: String,
+    pub name: String,
+
+    /// what the tag ref points to: a tag object if it's annotated, otherwise the commit
+    pub object_sha: String,
+    pub commit_sha: String,
+    pub annotated: bool,
+    pub pushed_at: u64,
+}
+
+/// tags waiting to be credited until their push is known to have gone through
+pub struct TagStore<'a> {
+    conn: &'a DbConnection,
+}
+
+impl<'a> TagStore<'a> {
+    pub fn new(conn: &'a DbConnection) -> Self {
+        Self { conn }
+    }
+
+    /// adds a pending tag, replacing an earlier attempt to push the same tag
+    pub fn add_pending(&self, tag: &PendingTag) -> Result<()> {
+        self.conn.execute(
+            "
+                INSERT OR REPLACE INTO pending_tags
+                    (repo_path, remote, remote_url, name, object_sha, commit_sha, annotated, pushed_at)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+            ",
+            (
+                &tag.repo_path,
+                &tag.remote,
+                &tag.remote_url,
+                &tag.name,
+                &tag.object_sha,
+                &tag.commit_sha,
+                tag.annotated,
+                tag.pushed_at as i64,
+            ),
+        )?;
+        Ok(())
+    }
+
+    /// pending tags pushed from `repo_path`, or from anywhere if it's None, oldest first
+    pub fn pending(&self, repo_path: Option<&str>) -> Result<Vec<PendingTag>> {
+        let mut stmt = self.conn.prepare(
+            "
+                SELECT repo_path, remote, remote_url, name, object_sha, commit_sha, annotated, pushed_at
+                FROM pending_tags
+                WHERE ?1 IS NULL OR repo_path = ?1
+                ORDER BY pushed_at, name
+            ",
+        )?;
+        stmt.query_map((repo_path,), |r| {
+            Ok(PendingTag {
+                repo_path: r.get(0)?,
+                remote: r.get(1)?,
+                remote_url: r.get(2)?,
+                name: r.get(3)?,
+                object_sha: r.get(4)?,
+                commit_sha: r.get(5)?,
+                annotated: r.get(6)?,
+                pushed_at: r.get::<_, i64>(7)? as u64,
+            })
+        })?
+        .collect()
+    }
+
+    pub fn remove_pending(&self, tag: &PendingTag) -> Result<()> {
+        self.conn.execute(
+            "DELETE FROM pending_tags WHERE repo_path = ?1 AND remote = ?2 AND name = ?3",
+            (&tag.repo_path, &tag.remote, &tag.name),
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tag(repo_path: &str, name: &str, object_sha: &str, pushed_at: u64) -> PendingTag {
+        PendingTag {
+            repo_path: repo_path.to_string(),
+            remote: "origin".to_string(),
+            remote_url: "github.com/me/x".to_string(),
+            name: name.to_string(),
+            object_sha: object_sha.to_string(),
+            commit_sha: "c0ffee".to_string(),
+            annotated: true,
+            pushed_at,
+        }
+    }
+
+    #[test]
+    fn pending_tags_roundtrip() {
+        let conn = DbConnection::create_in_memory().unwrap();
+        let store = TagStore::new(&conn);
+
+        store
+            .add_pending(&tag("/repo/a", "v1.0.0", "aaa", 100))
+            .unwrap();
+        store
+            .add_pending(&tag("/repo/b", "v2.0.0", "bbb", 200))
+            .unwrap();
+        // pushing the same tag again replaces the earlier attempt
+        store
+            .add_pending(&tag("/repo/a", "v1.0.0", "ccc", 300))
+            .unwrap();
+
+        assert_eq!(
+            store.pending(None).unwrap(),
+            vec![
+                tag("/repo/b", "v2.0.0", "bbb", 200),
+                tag("/repo/a", "v1.0.0", "ccc", 300)
+            ]
+        );
+        assert_eq!(
+            store.pending(Some("/repo/a")).unwrap(),
+            vec![tag("/repo/a", "v1.0.0", "ccc", 300)]
+        );
+
+        store
+            .remove_pending(&tag("/repo/a", "v1.0.0", "ccc", 300))
+            .unwrap();
+        assert_eq!(
+            store.pending(None).unwrap(),
+            vec![tag("/repo/b", "v2.0.0", "bbb", 200)]
+        );
+    }
+}
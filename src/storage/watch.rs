@@ -0,0 +1,80 @@
+use anyhow::Result;
+
+use crate::{git::Push, storage::DbConnection};
+
+/// the pushes `party watch` found that are still waiting for their party. the repos it
+/// watches are kept in the `RepoRegistry`
+pub struct WatchStore<'a> {
+    conn: &'a DbConnection,
+}
+
+impl<'a> WatchStore<'a> {
+    pub fn new(conn: &'a DbConnection) -> Self {
+        Self { conn }
+    }
+
+    /// saves a push for a party the next time party runs in a terminal
+    pub fn enqueue(&self, timestamp: u64, push: &Push) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO queued_pushes (timestamp, push) VALUES (?1, ?2)",
+            (timestamp as i64, serde_json::to_string(push)?),
+        )?;
+        Ok(())
+    }
+
+    /// removes and returns every queued push, oldest first
+    pub fn take_queued(&self) -> Result<Vec<(u64, Push)>> {
+        let tx = self.conn.unchecked_transaction()?;
+
+        let rows: Vec<(i64, String)> = {
+            let mut stmt = tx.prepare("SELECT timestamp, push FROM queued_pushes ORDER BY id")?;
+            stmt.query_map([], |r| Ok((r.get(0)?, r.get(1)?)))?
+                .collect::<rusqlite::Result<_>>()?
+        };
+        tx.execute("DELETE FROM queued_pushes", ())?;
+        tx.commit()?;
+
+        // a push saved by an older version of party might not parse anymore
+        let pushes = rows
+            .into_iter()
+            .filter_map(|(timestamp, json)| match serde_json::from_str(&json) {
+                Ok(push) => Some((timestamp as u64, push)),
+                Err(e) => {
+                    crate::debug_log!("watch: dropping queued push that won't parse: {e}");
+                    None
+                }
+            })
+            .collect();
+        Ok(pushes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::Commit;
+
+    #[test]
+    fn queued_pushes_are_taken_once_in_order() {
+        let conn = DbConnection::create_in_memory().unwrap();
+        let store = WatchStore::new(&conn);
+
+        let first = Push::with_repo(vec![Commit::with_sha("aaa")], "url/a.git");
+        let second = Push::with_repo(
+            vec![Commit::with_sha("bbb"), Commit::with_sha("ccc")],
+            "url/b.git",
+        );
+        store.enqueue(100, &first).unwrap();
+        store.enqueue(200, &second).unwrap();
+
+        let queued = store.take_queued().unwrap();
+        assert_eq!(queued.len(), 2);
+        assert_eq!(queued[0].0, 100);
+        assert_eq!(queued[0].1.remote_url(), "url/a.git");
+        assert_eq!(queued[0].1.commits()[0].sha(), "aaa");
+        assert_eq!(queued[1].0, 200);
+        assert_eq!(queued[1].1.commits().len(), 2);
+
+        assert!(store.take_queued().unwrap().is_empty());
+    }
+}
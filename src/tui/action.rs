@@ -11,10 +11,11 @@ pub enum Action {
     NextTab,
     PrevTab,
     Palette,
+    Respec,
     Quit,
 }
 
-pub const NUM_TABS: usize = 4;
+pub const NUM_TABS: usize = 5;
 
 /// navigation targets
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -24,6 +25,7 @@ pub enum Route {
     Packs,
     PackReveal,
     Games,
+    Repos,
 }
 
 impl Default for Route {
@@ -40,6 +42,7 @@ pub enum StoreRoute {
     Bonuses,
     Packs,
     Games,
+    Prestige,
 }
 
 impl Route {
@@ -50,6 +53,7 @@ impl Route {
             Route::Packs => 2,
             Route::PackReveal => 2,
             Route::Games => 3,
+            Route::Repos => 4,
         }
     }
 }
@@ -11,6 +11,7 @@ use super::action::{Action, Route};
 use super::views::games::GamesView;
 use super::views::packs::PacksView;
 use super::views::party::PartyView;
+use super::views::repos::ReposView;
 use super::views::store::StoreView;
 use super::views::{View, ViewResult};
 use super::widgets::{render_footer, render_header};
@@ -25,6 +26,7 @@ pub struct App<'a> {
     packs: PacksView,
     pack_reveal: PackRevealView,
     games: GamesView,
+    repos: ReposView,
 
     state: &'a mut State,
     conn: &'a DbConnection,
@@ -58,6 +60,7 @@ impl<'a> App<'a> {
             packs: PacksView::default(),
             pack_reveal: PackRevealView::default(),
             games: GamesView::default(),
+            repos: ReposView::load(conn),
             conn,
             display_points_offset: 0,
             display_games_offset: 0,
@@ -110,7 +113,8 @@ impl<'a> App<'a> {
                 0 => Route::Store(Default::default()),
                 1 => Route::Party,
                 2 => Route::Packs,
-                _ => Route::Games,
+                3 => Route::Games,
+                _ => Route::Repos,
             };
 
             return true;
@@ -127,6 +131,7 @@ impl<'a> App<'a> {
             Route::Packs => self.packs.handle(action, self.state),
             Route::PackReveal => self.pack_reveal.handle(action, self.state),
             Route::Games => self.games.handle(action, self.state),
+            Route::Repos => self.repos.handle(action, self.state),
         };
 
         match result {
@@ -242,6 +247,7 @@ impl<'a> App<'a> {
                 .pack_reveal
                 .render(frame, chunks[1], self.state, self.tick),
             Route::Games => self.games.render(frame, chunks[1], self.state, self.tick),
+            Route::Repos => self.repos.render(frame, chunks[1], self.state, self.tick),
         }
 
         // footer
@@ -251,6 +257,7 @@ impl<'a> App<'a> {
             Route::Packs => self.packs.key_hints(),
             Route::PackReveal => self.pack_reveal.key_hints(),
             Route::Games => self.games.key_hints(),
+            Route::Repos => self.repos.key_hints(),
         };
         render_footer(
             frame,
@@ -18,11 +18,13 @@ pub fn map_key(key: KeyEvent) -> Option<Action> {
         KeyCode::BackTab => Some(Action::PrevTab),
 
         KeyCode::Char('p') => Some(Action::Palette),
+        KeyCode::Char('r') => Some(Action::Respec),
 
         KeyCode::Char('1') => Some(Action::Tab(0)),
         KeyCode::Char('2') => Some(Action::Tab(1)),
         KeyCode::Char('3') => Some(Action::Tab(2)),
         KeyCode::Char('4') => Some(Action::Tab(3)),
+        KeyCode::Char('5') => Some(Action::Tab(4)),
 
         _ => None,
     }
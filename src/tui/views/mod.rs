@@ -2,6 +2,7 @@ pub mod games;
 pub mod pack_reveal;
 pub mod packs;
 pub mod party;
+pub mod repos;
 pub mod store;
 
 use ratatui::prelude::*;
@@ -0,0 +1,176 @@
+use std::path::Path;
+
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Cell, Paragraph, Row, Table, TableState},
+};
+
+use crate::{
+    clock::Clock,
+    state::State,
+    storage::{DbConnection, PushHistory, RepoRegistry, RepoStats},
+    tui::{
+        action::{Action, Route, StoreRoute},
+        views::{View, ViewResult},
+    },
+};
+
+/// one line of the summary: a registered repo, or a repo that was pushed to without
+/// being registered (e.g. with a global install)
+struct RepoRow {
+    name: String,
+    stats: RepoStats,
+}
+
+pub struct ReposView {
+    rows: Vec<RepoRow>,
+    selection: usize,
+
+    /// for showing how long ago each repo was pushed to
+    clock: Clock,
+}
+
+impl ReposView {
+    /// totals from push history for every registered repo, then for any other repo
+    /// that has been pushed to, most recently pushed first
+    pub fn load(conn: &DbConnection) -> Self {
+        let repos = RepoRegistry::new(conn).all().unwrap_or_default();
+        let mut stats = PushHistory::new(conn).stats_by_repo().unwrap_or_default();
+
+        let mut rows: Vec<RepoRow> = repos
+            .iter()
+            .map(|repo| {
+                let name = Path::new(&repo.path)
+                    .file_name()
+                    .map(|n| n.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| repo.path.clone());
+                let mut total = RepoStats::default();
+                for url in &repo.remote_urls {
+                    if let Some(s) = stats.remove(url) {
+                        total.pushes += s.pushes;
+                        total.commits += s.commits;
+                        total.insertions += s.insertions;
+                        total.deletions += s.deletions;
+                        total.points += s.points;
+                        total.last_push = total.last_push.max(s.last_push);
+                    }
+                }
+                RepoRow { name, stats: total }
+            })
+            .collect();
+        rows.extend(
+            stats
+                .into_iter()
+                .map(|(name, stats)| RepoRow { name, stats }),
+        );
+        rows.sort_by(|a, b| {
+            b.stats
+                .last_push
+                .cmp(&a.stats.last_push)
+                .then_with(|| a.name.cmp(&b.name))
+        });
+
+        Self {
+            rows,
+            selection: 0,
+            clock: Clock::from_now(),
+        }
+    }
+
+    fn last_push_text(&self, timestamp: u64) -> String {
+        if timestamp == 0 {
+            return "never".to_string();
+        }
+        match self.clock.today_id() - self.clock.day_id_of(timestamp) {
+            0 => "today".to_string(),
+            1 => "yesterday".to_string(),
+            days => format!("{days} days ago"),
+        }
+    }
+}
+
+impl View for ReposView {
+    fn render(&self, frame: &mut Frame, area: Rect, _state: &State, _tick: u32) {
+        // split out header
+        let split = Layout::vertical([Constraint::Length(2), Constraint::Fill(1)]).split(area);
+
+        let block = Block::default()
+            .borders(Borders::TOP)
+            .border_style(Style::default().dark_gray());
+        let header = Paragraph::new("Repos")
+            .alignment(Alignment::Center)
+            .style(Style::default().fg(Color::Reset))
+            .block(block);
+        frame.render_widget(header, split[0]);
+
+        // just show a centered message if nothing has been pushed or registered
+        if self.rows.is_empty() {
+            let split = Layout::vertical([
+                Constraint::Fill(2),
+                Constraint::Length(1), // message here
+                Constraint::Fill(3),
+            ])
+            .split(split[1]);
+
+            let text = Text::from("No repos yet. Run `party init` in one and push some code!")
+                .dark_gray()
+                .centered();
+            frame.render_widget(text, split[1]);
+            return;
+        }
+
+        let header = Row::new(["Repo", "Pushes", "Commits", "Lines", "Points", "Last push"])
+            .style(Style::default().dark_gray());
+        let rows = self.rows.iter().map(|row| {
+            let lines = Line::from(vec![
+                Span::raw(format!("+{}", row.stats.insertions)).green(),
+                Span::raw(" "),
+                Span::raw(format!("-{}", row.stats.deletions)).red(),
+            ]);
+            Row::new([
+                Cell::from(row.name.clone()).bold(),
+                Cell::from(row.stats.pushes.to_string()),
+                Cell::from(row.stats.commits.to_string()),
+                Cell::from(lines),
+                Cell::from(row.stats.points.to_string()).yellow(),
+                Cell::from(self.last_push_text(row.stats.last_push)).dark_gray(),
+            ])
+        });
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Fill(2),
+                Constraint::Length(7),
+                Constraint::Length(8),
+                Constraint::Length(16),
+                Constraint::Length(8),
+                Constraint::Length(12),
+            ],
+        )
+        .header(header)
+        .row_highlight_style(Style::default().reversed());
+
+        let mut table_state = TableState::default().with_selected(Some(self.selection));
+        frame.render_stateful_widget(table, split[1].inner(Margin::new(1, 0)), &mut table_state);
+    }
+
+    fn handle(&mut self, action: Action, _state: &mut State) -> ViewResult {
+        let count = self.rows.len();
+        match action {
+            Action::Up if count > 0 => {
+                self.selection = (self.selection + count - 1) % count;
+                ViewResult::Redraw
+            }
+            Action::Down if count > 0 => {
+                self.selection = (self.selection + 1) % count;
+                ViewResult::Redraw
+            }
+            Action::Back => ViewResult::Navigate(Route::Store(StoreRoute::Grid)),
+            _ => ViewResult::None,
+        }
+    }
+
+    fn key_hints(&self) -> Vec<(&'static str, &'static str)> {
+        vec![("↑↓", "select"), ("esc", "back"), ("q", "quit")]
+    }
+}
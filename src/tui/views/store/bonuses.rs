@@ -4,8 +4,9 @@ use ratatui::prelude::*;
 use ratatui::widgets::{Block, Borders, Paragraph, Wrap};
 use tui_scrollview::{ScrollView, ScrollViewState, ScrollbarVisibility};
 
-use crate::bonus_track::{ALL_TRACKS, Reward, Tier};
-use crate::state::State;
+use crate::bonus_track::{ALL_TRACKS, Reward, Tier, all_tracks};
+use crate::clock::Clock;
+use crate::state::{RespecError, State};
 use crate::tui::action::{Action, Route, StoreRoute};
 use crate::tui::views::{MessageType, View, ViewResult};
 use crate::tui::widgets::ShimmerBlock;
@@ -162,14 +163,101 @@ impl<'a> Widget for BonusItem<'a> {
     }
 }
 
+/// the last row of the list, leading to the prestige page
+struct PrestigeItem<'a> {
+    state: &'a State,
+    selected: bool,
+    tick: u32,
+}
+
+impl<'a> Widget for PrestigeItem<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let inner = if self.selected {
+            let block = ShimmerBlock::new(self.tick);
+            let inner = block.inner(area);
+            block.render(area, buf);
+            inner
+        } else {
+            let block = Block::default()
+                .border_style(Style::default().dim())
+                .borders(Borders::ALL);
+            let inner = block.inner(area);
+            block.render(area, buf);
+            inner
+        };
+
+        let split = Layout::horizontal([
+            Constraint::Fill(1),                 // name + description
+            Constraint::Length(COST_INFO_WIDTH), // progress
+        ])
+        .split(inner);
+
+        let name_split = Layout::vertical([
+            Constraint::Length(1), // name
+            Constraint::Fill(1),   // description
+        ])
+        .split(split[0].inner(Margin::new(1, 0)));
+
+        let mut name_spans = vec!["Prestige".reset().bold()];
+        if self.state.prestige_level > 0 {
+            name_spans.extend([
+                " (currently ".dark_gray(),
+                format!("level {}", self.state.prestige_level).magenta(),
+                ")".dark_gray(),
+            ]);
+        }
+        Line::from(name_spans).render(name_split[0], buf);
+
+        Paragraph::new(
+            "Start over with every bonus at its first level, for a bigger multiplier on all \
+             points and an exclusive palette.",
+        )
+        .wrap(Wrap::default())
+        .reset()
+        .dim()
+        .render(name_split[1], buf);
+
+        let block = Block::default()
+            .borders(Borders::LEFT)
+            .border_style(Style::default().dark_gray());
+        let inner = block.inner(split[1]);
+        block.render(split[1], buf);
+        let middle = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Length(1),
+            Constraint::Length(1),
+        ])
+        .split(inner)[1];
+
+        let line = if self.state.can_prestige() {
+            Line::from("Ready to prestige!").green()
+        } else {
+            let maxed = ALL_TRACKS
+                .iter()
+                .filter(|t| self.state.bonus_level(t.id()) as usize >= t.tiers().len())
+                .count();
+            Line::from(format!("{maxed} of {} bonuses maxed", ALL_TRACKS.len())).dark_gray()
+        };
+        line.alignment(Alignment::Center).render(middle, buf);
+    }
+}
+
 #[derive(Default)]
 pub struct BonusesView {
     selection: usize,
+
+    /// the row where respec was pressed once, so pressing it again sells the level
+    respec_pending: Option<usize>,
     scroll_state: ScrollViewState,
     viewport_height: Cell<u16>,
 }
 
 impl BonusesView {
+    /// every bonus track, then prestige
+    fn item_count(&self) -> usize {
+        all_tracks().len() + 1
+    }
+
     fn update_scroll(&mut self) {
         let viewport_height = self.viewport_height.get();
 
@@ -208,12 +296,12 @@ impl View for BonusesView {
         // content area with scrollview
         let content_area = split[1].inner(Margin::new(1, 0));
         let content_width = content_area.width.saturating_sub(1); // leave room for scrollbar
-        let content_height = ALL_TRACKS.len() as u16 * ITEM_HEIGHT;
+        let content_height = self.item_count() as u16 * ITEM_HEIGHT;
 
         let mut scroll_view = ScrollView::new(Size::new(content_width, content_height))
             .horizontal_scrollbar_visibility(ScrollbarVisibility::Never);
 
-        for (i, track) in ALL_TRACKS.iter().enumerate() {
+        for (i, track) in all_tracks().iter().enumerate() {
             let owned_level = state.bonus_level(track.id()) as usize;
             let item = BonusItem::new(
                 track.name(),
@@ -228,24 +316,43 @@ impl View for BonusesView {
             scroll_view.render_widget(item, item_rect);
         }
 
+        let prestige = PrestigeItem {
+            state,
+            selected: self.selection == all_tracks().len(),
+            tick,
+        };
+        let prestige_rect = Rect::new(
+            0,
+            all_tracks().len() as u16 * ITEM_HEIGHT,
+            content_width,
+            ITEM_HEIGHT,
+        );
+        scroll_view.render_widget(prestige, prestige_rect);
+
         frame.render_stateful_widget(scroll_view, content_area, &mut self.scroll_state.clone());
     }
 
     fn handle(&mut self, action: Action, state: &mut State) -> ViewResult {
+        // anything but a second respec on the same row calls the respec off
+        let respec_pending = self.respec_pending.take();
+
         match action {
             Action::Up => {
-                let count = ALL_TRACKS.len();
+                let count = self.item_count();
                 self.selection = (self.selection + count - 1) % count;
                 self.update_scroll();
                 ViewResult::Redraw
             }
             Action::Down => {
-                self.selection = (self.selection + 1) % ALL_TRACKS.len();
+                self.selection = (self.selection + 1) % self.item_count();
                 self.update_scroll();
                 ViewResult::Redraw
             }
+            Action::Select if self.selection == all_tracks().len() => {
+                ViewResult::Navigate(Route::Store(StoreRoute::Prestige))
+            }
             Action::Select => {
-                let track = ALL_TRACKS[self.selection];
+                let track = all_tracks()[self.selection];
                 let current_level = state.bonus_level(track.id());
                 let tiers = track.tiers();
 
@@ -258,13 +365,61 @@ impl View for BonusesView {
 
                 let next_tier = &tiers[current_level as usize];
                 if state.party_points >= next_tier.cost {
-                    state.party_points -= next_tier.cost;
-                    state.set_bonus_level(track.id(), current_level + 1);
+                    state.buy_bonus_level(track.id(), current_level + 1, next_tier.cost);
                     ViewResult::Message(MessageType::Success, format!("Upgraded {}!", track.name()))
                 } else {
                     ViewResult::Message(MessageType::Error, "Not enough points.".to_string())
                 }
             }
+            Action::Respec if self.selection < all_tracks().len() => {
+                let track = all_tracks()[self.selection];
+                let level = state.bonus_level(track.id());
+                let Some(refund) = state.respec_refund(track) else {
+                    return ViewResult::Message(
+                        MessageType::Normal,
+                        "Nothing to sell back.".to_string(),
+                    );
+                };
+
+                let now = Clock::from_now().now();
+                if respec_pending != Some(self.selection) {
+                    let cooldown = state.respec_cooldown(now);
+                    if cooldown > 0 {
+                        return ViewResult::Message(
+                            MessageType::Error,
+                            format!("You can respec again in {}.", format_wait(cooldown)),
+                        );
+                    }
+                    self.respec_pending = Some(self.selection);
+                    return ViewResult::Message(
+                        MessageType::Normal,
+                        format!(
+                            "Press r again to sell back {} level {level} for {}.",
+                            track.name(),
+                            format_cost(refund)
+                        ),
+                    );
+                }
+
+                match state.respec(track, now) {
+                    Ok(refund) => ViewResult::Message(
+                        MessageType::Success,
+                        format!(
+                            "Sold back {} level {level} for {}.",
+                            track.name(),
+                            format_cost(refund)
+                        ),
+                    ),
+                    Err(RespecError::CoolingDown(secs)) => ViewResult::Message(
+                        MessageType::Error,
+                        format!("You can respec again in {}.", format_wait(secs)),
+                    ),
+                    Err(RespecError::NothingToSell) => ViewResult::Message(
+                        MessageType::Normal,
+                        "Nothing to sell back.".to_string(),
+                    ),
+                }
+            }
             Action::Back => ViewResult::Navigate(Route::Store(StoreRoute::Grid)),
             _ => ViewResult::None,
         }
@@ -274,6 +429,7 @@ impl View for BonusesView {
         vec![
             ("↑↓", "select"),
             ("enter", "buy"),
+            ("r", "respec"),
             ("esc", "back"),
             ("q", "quit"),
         ]
@@ -290,6 +446,16 @@ fn format_cost(cost: u64) -> String {
     }
 }
 
+/// e.g. "5h 12m", or just "12m" under an hour
+fn format_wait(secs: u64) -> String {
+    let minutes = secs.div_ceil(60);
+    if minutes >= 60 {
+        format!("{}h {}m", minutes / 60, minutes % 60)
+    } else {
+        format!("{minutes}m")
+    }
+}
+
 fn format_reward(reward: Reward) -> String {
     match reward {
         Reward::Multiplier(n) => format!("{}x", n),
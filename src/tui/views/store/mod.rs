@@ -2,6 +2,7 @@ mod bonuses;
 mod games;
 mod grid;
 mod packs;
+mod prestige;
 mod upgrades;
 
 use ratatui::prelude::*;
@@ -10,6 +11,7 @@ use crate::state::State;
 use crate::tui::action::{Action, Route, StoreRoute};
 use crate::tui::views::store::games::GamesView;
 use crate::tui::views::store::packs::PacksView;
+use crate::tui::views::store::prestige::PrestigeView;
 use crate::tui::views::{View, ViewResult};
 
 pub use bonuses::BonusesView;
@@ -23,6 +25,7 @@ pub struct StoreView {
     bonuses: BonusesView,
     packs: PacksView,
     games: GamesView,
+    prestige: PrestigeView,
 }
 
 impl Default for StoreView {
@@ -34,6 +37,7 @@ impl Default for StoreView {
             bonuses: BonusesView::default(),
             packs: PacksView::default(),
             games: GamesView::default(),
+            prestige: PrestigeView::default(),
         }
     }
 }
@@ -50,6 +54,7 @@ impl StoreView {
             StoreRoute::Bonuses => &self.bonuses,
             StoreRoute::Packs => &self.packs,
             StoreRoute::Games => &self.games,
+            StoreRoute::Prestige => &self.prestige,
         }
     }
 
@@ -60,6 +65,7 @@ impl StoreView {
             StoreRoute::Bonuses => &mut self.bonuses,
             StoreRoute::Packs => &mut self.packs,
             StoreRoute::Games => &mut self.games,
+            StoreRoute::Prestige => &mut self.prestige,
         }
     }
 }
@@ -70,8 +76,10 @@ impl View for StoreView {
     }
 
     fn handle(&mut self, action: Action, state: &mut State) -> ViewResult {
-        // handle Back action to return to grid from sub-pages
-        if action == Action::Back && self.route != StoreRoute::Grid {
+        // handle Back action to return to grid from sub-pages, except for prestige which
+        // is reached from (and goes back to) bonuses
+        if action == Action::Back && !matches!(self.route, StoreRoute::Grid | StoreRoute::Prestige)
+        {
             self.route = StoreRoute::Grid;
             return ViewResult::Redraw;
         }
@@ -80,6 +88,10 @@ impl View for StoreView {
 
         // if sub-view navigates within store, update our route
         if let ViewResult::Navigate(Route::Store(sub_route)) = result {
+            // always arrive at prestige without having confirmed anything
+            if sub_route == StoreRoute::Prestige {
+                self.prestige = PrestigeView::default();
+            }
             self.route = sub_route;
             return ViewResult::Redraw;
         }
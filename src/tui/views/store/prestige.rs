@@ -0,0 +1,128 @@
+use ratatui::prelude::*;
+use ratatui::widgets::{Block, Borders, Paragraph, Wrap};
+
+use crate::party::PRESTIGE_PALETTES;
+use crate::state::{PRESTIGE_POINTS_KEPT_PERCENT, State};
+use crate::tui::action::{Action, Route, StoreRoute};
+use crate::tui::views::{MessageType, View, ViewResult};
+
+/// what prestiging costs and earns, and a second enter to go through with it
+#[derive(Default)]
+pub struct PrestigeView {
+    /// enter has been pressed once, so the next one prestiges
+    confirming: bool,
+}
+
+impl View for PrestigeView {
+    fn render(&self, frame: &mut Frame, area: Rect, state: &State, _tick: u32) {
+        let split = Layout::vertical([Constraint::Length(2), Constraint::Fill(1)]).split(area);
+
+        let block = Block::default()
+            .borders(Borders::TOP)
+            .border_style(Style::default().dark_gray());
+        let header = Paragraph::new("Prestige")
+            .alignment(Alignment::Center)
+            .style(Style::default().fg(Color::Reset))
+            .block(block);
+        frame.render_widget(header, split[0]);
+
+        let kept = state.party_points * PRESTIGE_POINTS_KEPT_PERCENT / 100;
+        let next_multiplier = state.prestige_multiplier() + 1;
+        let palette = PRESTIGE_PALETTES
+            .get(state.prestige_level as usize)
+            .map(|p| p.name());
+
+        let mut lines = vec![
+            Line::from(vec![
+                "Prestige level ".reset(),
+                state.prestige_level.to_string().magenta().bold(),
+                format!(" (all points ×{})", state.prestige_multiplier())
+                    .reset()
+                    .dim(),
+            ]),
+            Line::default(),
+            Line::from(vec![
+                "You lose ".reset(),
+                "every bonus track level".red(),
+                " and ".reset(),
+                format!("{} P", state.party_points - kept).red(),
+                ".".reset(),
+            ]),
+            Line::from(vec![
+                "You keep ".reset(),
+                format!("{} P", kept).green(),
+                ", your parties, palettes, packs and game tokens.".reset(),
+            ]),
+        ];
+        let mut gains = vec![
+            "You gain ".reset(),
+            format!("×{next_multiplier}").magenta(),
+            " on all points".reset(),
+        ];
+        if let Some(palette) = palette {
+            gains.extend([
+                " and the ".reset(),
+                palette.yellow(),
+                " palette for every party".reset(),
+            ]);
+        }
+        gains.push(".".reset());
+        lines.push(Line::from(gains));
+        lines.push(Line::default());
+
+        if !state.can_prestige() {
+            lines.push(Line::from("Max out every bonus track to prestige.").dark_gray());
+        } else if self.confirming {
+            lines.push(Line::from("Press enter again to prestige. There's no undo!").red());
+        } else {
+            lines.push(Line::from("Press enter to prestige.").cyan());
+        }
+
+        let split = Layout::vertical([
+            Constraint::Fill(1),
+            Constraint::Length(lines.len() as u16),
+            Constraint::Fill(2),
+        ])
+        .split(split[1].inner(Margin::new(2, 0)));
+        let body = Paragraph::new(lines)
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true });
+        frame.render_widget(body, split[1]);
+    }
+
+    fn handle(&mut self, action: Action, state: &mut State) -> ViewResult {
+        match action {
+            Action::Select if !state.can_prestige() => ViewResult::Message(
+                MessageType::Error,
+                "Max out every bonus track first.".into(),
+            ),
+            Action::Select if !self.confirming => {
+                self.confirming = true;
+                ViewResult::Redraw
+            }
+            Action::Select => {
+                self.confirming = false;
+                state.prestige();
+                ViewResult::Message(
+                    MessageType::Success,
+                    format!("Welcome to prestige level {}!", state.prestige_level),
+                )
+            }
+            Action::Back => ViewResult::Navigate(Route::Store(StoreRoute::Bonuses)),
+            _ => {
+                // anything else backs out of confirming
+                let was_confirming = self.confirming;
+                self.confirming = false;
+                if was_confirming {
+                    ViewResult::Redraw
+                } else {
+                    ViewResult::None
+                }
+            }
+        }
+    }
+
+    fn key_hints(&self) -> Vec<(&'static str, &'static str)> {
+        vec![("enter", "prestige"), ("esc", "back"), ("q", "quit")]
+    }
+}
@@ -78,7 +78,7 @@ impl Widget for PartyListItem {
         let price_str = if self.unlocked {
             "✓ Owned".to_string()
         } else {
-            format!("{} P", self.party.info.cost)
+            format!("{} P", self.party.cost())
         };
 
         let price_text = Text::from(price_str)
@@ -157,7 +157,7 @@ impl View for UpgradesView {
 
         // render items into scroll view
         for (i, &party) in ALL_PARTIES.iter().enumerate() {
-            let affordable = state.party_points >= party.info.cost;
+            let affordable = state.party_points >= party.cost();
             let selected = self.selection == i;
             let unlocked = state.is_party_unlocked(party.info.id);
 
@@ -191,7 +191,7 @@ impl View for UpgradesView {
                             format!("You already own {}.", party.info.name),
                         )
                     } else {
-                        let cost = party.info.cost;
+                        let cost = party.cost();
                         if state.party_points >= cost {
                             state.party_points -= cost;
                             state.unlock_party(party.info.id);
@@ -9,6 +9,7 @@ enum Tab {
     Party,
     Packs,
     Games,
+    Repos,
 }
 
 impl From<&Route> for Tab {
@@ -18,6 +19,7 @@ impl From<&Route> for Tab {
             Route::Party => Self::Party,
             Route::Packs | Route::PackReveal => Self::Packs,
             Route::Games => Self::Games,
+            Route::Repos => Self::Repos,
         }
     }
 }
@@ -29,11 +31,12 @@ impl Tab {
             Tab::Party => "Party",
             Tab::Packs => "Packs",
             Tab::Games => "Games",
+            Tab::Repos => "Repos",
         }
     }
 }
 
-const TABS: [Tab; 4] = [Tab::Store, Tab::Party, Tab::Packs, Tab::Games];
+const TABS: [Tab; 5] = [Tab::Store, Tab::Party, Tab::Packs, Tab::Games, Tab::Repos];
 
 pub fn render_header(
     frame: &mut Frame,
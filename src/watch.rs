@@ -0,0 +1,190 @@
+//! `party watch`: noticing pushes in repos where the hook never runs, such as repos
+//! that forbid client hooks, or GUIs that push without going through
+//! `reference-transaction`.
+//!
+//! The watcher polls each registered repo's remote-tracking refs. Once a moved ref has
+//! stayed put for a whole interval, it runs the same detection as the hook. Detection
+//! only credits commits whose refs and patch-ids haven't been recorded yet, so a push
+//! the hook already celebrated is found to be empty. Waiting for refs to settle gives
+//! the hook time to finish first.
+//!
+//! Pushes the watcher finds are queued rather than scored, and get their party the
+//! next time party runs in a terminal.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{Context, Result, anyhow};
+
+use crate::{
+    clock::Clock,
+    git::{self, Push},
+    storage::{BranchRefsStore, PatchIdStore, RepoEntry, RepoRegistry, Vcs, WatchStore},
+};
+
+/// where a repo's remote branches point
+type Positions = Vec<(String, String, String)>;
+
+/// the repo containing `path`, as it's stored in the watch list
+fn resolve_repo(path: &Path) -> Result<PathBuf> {
+    let repo_path =
+        git::repo::git_work_dir(path).ok_or_else(|| anyhow!("not a git or jj repository"))?;
+    Ok(repo_path.canonicalize().unwrap_or(repo_path))
+}
+
+/// whether a push is worth a party
+fn is_eventful(push: &Push) -> bool {
+    !push.commits().is_empty()
+        || push.automated_commits() > 0
+        || push.discarded_commits().next().is_some()
+}
+
+/// runs detection in one repo, and queues anything found
+fn check_repo(
+    repo_path: &Path,
+    branch_refs: &BranchRefsStore,
+    patch_ids: &PatchIdStore,
+    watch: &WatchStore,
+) -> Result<()> {
+    let Some(push) = git::get_pushed_commits(repo_path, branch_refs, patch_ids) else {
+        return Ok(());
+    };
+    if !is_eventful(&push) {
+        return Ok(());
+    }
+
+    watch.enqueue(Clock::from_now().now(), &push)?;
+    println!(
+        "queued a party for {} commits pushed from {}",
+        push.commits().len(),
+        repo_path.display()
+    );
+
+    Ok(())
+}
+
+/// registers a repo to be watched. any push made there since party last looked is
+/// queued right away, and a repo party has never seen is snapshotted instead
+pub fn add(
+    path: &Path,
+    branch_refs: &BranchRefsStore,
+    patch_ids: &PatchIdStore,
+    watch: &WatchStore,
+    repos: &RepoRegistry,
+) -> Result<()> {
+    let repo_path = resolve_repo(path)?;
+    let key = repo_path.to_string_lossy();
+
+    let entry = RepoEntry {
+        path: key.to_string(),
+        vcs: Vcs::Git,
+        remote_urls: git::detection::get_remote_ids(&repo_path)
+            .into_iter()
+            .map(|(_, url)| url)
+            .collect(),
+        added_at: Clock::from_now().now(),
+        installed: false,
+        watched: true,
+    };
+    if !repos.watch(&entry)? {
+        println!("already watching {key}");
+        return Ok(());
+    }
+    check_repo(&repo_path, branch_refs, patch_ids, watch)?;
+    println!("now watching {key}");
+    println!("run `party watch` to start watching");
+
+    Ok(())
+}
+
+pub fn remove(path: &Path, repos: &RepoRegistry) -> Result<()> {
+    // the repo might be gone by now, so fall back to the path as given
+    let repo_path = resolve_repo(path)
+        .or_else(|_| path.canonicalize())
+        .unwrap_or_else(|_| path.to_path_buf());
+    let key = repo_path.to_string_lossy();
+
+    if repos.unwatch(&key)? {
+        println!("stopped watching {key}");
+    } else {
+        println!("{key} isn't being watched");
+    }
+
+    Ok(())
+}
+
+pub fn list(repos: &RepoRegistry) -> Result<()> {
+    let repos = repos.watched()?;
+    if repos.is_empty() {
+        println!("no repos are being watched. add one with `party watch --add <path>`");
+    }
+    for repo in repos {
+        println!("{repo}");
+    }
+
+    Ok(())
+}
+
+/// watches every registered repo until interrupted, or just checks each one once
+pub fn run(
+    interval: Duration,
+    once: bool,
+    branch_refs: &BranchRefsStore,
+    patch_ids: &PatchIdStore,
+    watch: &WatchStore,
+    registry: &RepoRegistry,
+) -> Result<()> {
+    let repos = registry.watched()?;
+    if repos.is_empty() {
+        return Err(anyhow!(
+            "no repos to watch. add one with `party watch --add <path>`"
+        ));
+    }
+
+    // catch up on anything pushed while the watcher wasn't running
+    for repo in &repos {
+        if let Err(e) = check_repo(Path::new(repo), branch_refs, patch_ids, watch) {
+            crate::debug_log!("watch: could not check {repo}: {e:#}");
+        }
+    }
+    if once {
+        return Ok(());
+    }
+
+    println!(
+        "watching {} repos for pushes (every {}s, ctrl-c to stop)",
+        repos.len(),
+        interval.as_secs()
+    );
+
+    let mut positions: HashMap<String, Positions> = HashMap::new();
+    let mut moved: HashSet<String> = HashSet::new();
+    loop {
+        // read every time around, so repos added in the meantime get watched too
+        for repo in registry.watched().context("could not read watched repos")? {
+            let path = Path::new(&repo);
+            if !path.exists() {
+                continue;
+            }
+
+            let current = git::remote_ref_positions(path);
+            match positions.get(&repo) {
+                Some(previous) if *previous != current => {
+                    crate::debug_log!("watch: remote refs moved in {repo}");
+                    moved.insert(repo.clone());
+                }
+                // settled since it moved, so any hook has had its turn
+                Some(_) if moved.remove(&repo) => {
+                    if let Err(e) = check_repo(path, branch_refs, patch_ids, watch) {
+                        crate::debug_log!("watch: could not check {repo}: {e:#}");
+                    }
+                }
+                _ => {}
+            }
+            positions.insert(repo, current);
+        }
+
+        std::thread::sleep(interval);
+    }
+}
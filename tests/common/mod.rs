@@ -42,6 +42,11 @@ fn path_with_party() -> String {
     format!("{}:{}", party_dir.display(), path)
 }
 
+/// a global git config private to the test, so tests neither see nor touch the real one
+fn global_git_config(state_dir: &Path) -> std::path::PathBuf {
+    state_dir.join("gitconfig")
+}
+
 pub trait Vcs {
     /// initialize repo and add remote
     fn init(repo_dir: &Path, remote_dir: &Path);
@@ -102,6 +107,62 @@ impl<V: Vcs> TestEnv<V> {
         run(Command::new(party_bin())
             .args(args)
             .env("PARTY_STATE_DIR", self.state_dir.path())
+            .env(
+                "GIT_CONFIG_GLOBAL",
+                global_git_config(self.state_dir.path()),
+            )
+            .current_dir(self.repo_dir.path()))
+    }
+
+    /// runs `party` with subcommands from somewhere other than the repo root
+    pub fn party_in(&self, dir: &Path, args: &[&str]) -> String {
+        run(Command::new(party_bin())
+            .args(args)
+            .env("PARTY_STATE_DIR", self.state_dir.path())
+            .env(
+                "GIT_CONFIG_GLOBAL",
+                global_git_config(self.state_dir.path()),
+            )
+            .current_dir(dir))
+    }
+
+    /// runs `party` with subcommands, possibly failing
+    pub fn try_party(&self, args: &[&str]) -> Result<String> {
+        try_run(
+            Command::new(party_bin())
+                .args(args)
+                .env("PARTY_STATE_DIR", self.state_dir.path())
+                .env(
+                    "GIT_CONFIG_GLOBAL",
+                    global_git_config(self.state_dir.path()),
+                )
+                .current_dir(self.repo_dir.path()),
+        )
+    }
+
+    /// runs `party` with subcommands and extra environment variables
+    pub fn party_with_env(&self, args: &[&str], envs: &[(&str, &str)]) -> String {
+        run(Command::new(party_bin())
+            .args(args)
+            .envs(envs.iter().copied())
+            .env("PARTY_STATE_DIR", self.state_dir.path())
+            .env(
+                "GIT_CONFIG_GLOBAL",
+                global_git_config(self.state_dir.path()),
+            )
+            .current_dir(self.repo_dir.path()))
+    }
+
+    /// runs git in the repo, e.g. to push from a colocated jj repo without jj
+    pub fn git(&self, args: &[&str]) -> String {
+        run(Command::new("git")
+            .args(args)
+            .env("PATH", path_with_party())
+            .env("PARTY_STATE_DIR", self.state_dir.path())
+            .env(
+                "GIT_CONFIG_GLOBAL",
+                global_git_config(self.state_dir.path()),
+            )
             .current_dir(self.repo_dir.path()))
     }
 
@@ -175,6 +236,7 @@ impl Vcs for Git<'_> {
             .args(args)
             .env("PATH", path_with_party())
             .env("PARTY_STATE_DIR", self.state_dir)
+            .env("GIT_CONFIG_GLOBAL", global_git_config(self.state_dir))
             .current_dir(self.repo_dir))
     }
 
@@ -184,6 +246,7 @@ impl Vcs for Git<'_> {
                 .args(args)
                 .env("PATH", path_with_party())
                 .env("PARTY_STATE_DIR", self.state_dir)
+                .env("GIT_CONFIG_GLOBAL", global_git_config(self.state_dir))
                 .current_dir(self.repo_dir),
         )
     }
@@ -1,5 +1,7 @@
 mod common;
 
+use std::{os::unix::fs::PermissionsExt, path::PathBuf};
+
 use common::{Vcs, git_env};
 
 #[test]
@@ -28,6 +30,80 @@ fn rebase_and_force_push_awards_no_points() {
     );
 }
 
+/// pushes main with three commits, then throws the last two away with a force push
+fn discard_two_commits(env: &common::TestEnv<common::Git<'static>>) -> u64 {
+    env.vcs.commit_file("README.md", "# Test", "initial commit");
+    env.vcs.ensure_main();
+    env.vcs.commit_file("a.rs", "// a", "add a");
+    env.vcs.commit_file("b.rs", "// b", "add b");
+    env.vcs.push();
+    let points_after_push = env.get_points();
+
+    env.vcs.cmd(&["reset", "--hard", "HEAD~2"]);
+    env.vcs.cmd(&["push", "--force", "origin", "main"]);
+
+    points_after_push
+}
+
+#[test]
+fn force_push_discarding_commits_keeps_points_by_default() {
+    let env = git_env();
+    env.party(&["init"]);
+
+    let points_after_push = discard_two_commits(&env);
+
+    assert_eq!(env.get_points(), points_after_push);
+}
+
+#[test]
+fn force_push_discarding_commits_claws_back_points_when_configured() {
+    let env = git_env();
+    env.party(&["init"]);
+    env.vcs.cmd(&["config", "party.rewritePolicy", "clawback"]);
+
+    let points_after_push = discard_two_commits(&env);
+
+    assert_eq!(
+        env.get_points(),
+        points_after_push - 2,
+        "the two discarded commits should be taken back"
+    );
+
+    // once taken back, pushing the same changes again earns them back
+    env.vcs.cmd(&["cherry-pick", "ORIG_HEAD~1", "ORIG_HEAD"]);
+    env.vcs.push();
+    assert_eq!(env.get_points(), points_after_push);
+}
+
+#[test]
+fn rebase_and_force_push_claws_back_nothing() {
+    let env = git_env();
+    env.party(&["init"]);
+    env.vcs.cmd(&["config", "party.rewritePolicy", "clawback"]);
+
+    env.vcs.commit_file("README.md", "# Test", "initial commit");
+    env.vcs.ensure_main();
+    env.vcs.push();
+
+    env.vcs.create_feature_branch("feature");
+    env.vcs
+        .commit_file("feature.rs", "// feature", "feature work");
+    env.vcs.push_branch("feature");
+    let points_after_feature = env.get_points();
+
+    env.vcs.cmd(&["checkout", "main"]);
+    env.vcs.commit_file("main.rs", "// main", "main work");
+    env.vcs.push();
+    let points_after_main = env.get_points();
+    assert_eq!(points_after_main, points_after_feature + 1);
+
+    env.vcs.cmd(&["checkout", "feature"]);
+    env.vcs.cmd(&["rebase", "main"]);
+    env.vcs.cmd(&["push", "--force", "origin", "feature"]);
+
+    assert_eq!(env.get_points(), points_after_main);
+}
+
 #[test]
 #[cfg(feature = "dev")]
 fn first_push_of_day_bonus_applies() {
@@ -50,3 +126,1100 @@ fn first_push_of_day_bonus_applies() {
         "first push of day should apply 2x multiplier"
     );
 }
+
+#[test]
+#[cfg(feature = "dev")]
+fn custom_bonus_tracks_are_scored_like_built_in_ones() {
+    let env = git_env();
+    std::fs::write(
+        env.state_dir.path().join("bonuses.toml"),
+        r#"
+        [[bonus]]
+        id = "release_push"
+        name = "Release Push"
+        description = "Points for pushing to a release branch."
+
+        [bonus.when]
+        branches = ["release/*"]
+
+        [[bonus.tier]]
+        cost = 100
+        points = 30
+        "#,
+    )
+    .unwrap();
+    env.party(&["init"]);
+    env.party(&["bonus", "release_push", "1"]);
+
+    env.vcs.commit_file("README.md", "# Test", "initial commit");
+    env.vcs.ensure_main();
+    env.vcs.push();
+    let points_after_main = env.get_points();
+
+    env.vcs
+        .commit_file_on_branch("fix.rs", "// fix", "fix", "release/1.0");
+    env.vcs.push_branch("release/1.0");
+    assert_eq!(env.get_points(), points_after_main + 1 + 30);
+}
+
+#[test]
+fn invalid_custom_bonuses_are_ignored_with_a_warning_outside_hooks() {
+    let env = git_env();
+    std::fs::write(
+        env.state_dir.path().join("bonuses.toml"),
+        "[[bonus]]\nid = \"streak\"\n",
+    )
+    .unwrap();
+    env.party(&["init"]);
+
+    env.vcs.commit_file("README.md", "# Test", "initial commit");
+    env.vcs.ensure_main();
+    let output = env.vcs.cmd(&["push", "-u", "origin", "main"]);
+    assert!(!output.contains("ignoring custom bonuses"), "{output}");
+    assert_eq!(env.get_points(), 11);
+
+    let output = env.party(&["points"]);
+    assert!(output.contains("ignoring custom bonuses"), "{output}");
+}
+
+#[test]
+fn pushing_tags_without_ship_it_earns_nothing() {
+    let env = git_env();
+    env.party(&["init"]);
+
+    env.vcs.commit_file("README.md", "# Test", "initial commit");
+    env.vcs.ensure_main();
+    env.vcs.push();
+    let points_after_push = env.get_points();
+
+    env.vcs.cmd(&["tag", "-a", "v1.0.0", "-m", "first release"]);
+    env.vcs.cmd(&["push", "origin", "v1.0.0"]);
+    env.party(&["points"]);
+
+    assert_eq!(env.get_points(), points_after_push);
+}
+
+#[test]
+#[cfg(feature = "dev")]
+fn ship_it_rewards_each_released_commit_once() {
+    let env = git_env();
+    env.party(&["init"]);
+    env.party(&["bonus", "ship_it", "1"]);
+
+    env.vcs.commit_file("README.md", "# Test", "initial commit");
+    env.vcs.ensure_main();
+    env.vcs.push();
+    let points_after_push = env.get_points();
+
+    // 25 points for the release at ship_it level 1, once party sees it reached the remote
+    env.vcs.cmd(&["tag", "-a", "v1.0.0", "-m", "first release"]);
+    env.vcs.cmd(&["push", "origin", "v1.0.0"]);
+    assert_eq!(env.get_points(), points_after_push);
+    let output = env.party(&["points"]);
+    assert!(output.contains("credited 1 pushed tag "), "{output}");
+    assert_eq!(env.get_points(), points_after_push + 25);
+
+    // more tags on the same commit, or deleting and pushing it again, earn nothing
+    env.vcs.cmd(&["tag", "v1.0.1"]);
+    env.vcs.cmd(&["push", "origin", "v1.0.1"]);
+    env.party(&["points"]);
+    env.vcs.cmd(&["push", "origin", ":refs/tags/v1.0.0"]);
+    env.vcs.cmd(&["push", "origin", "v1.0.0"]);
+    env.party(&["points"]);
+    assert_eq!(env.get_points(), points_after_push + 25);
+
+    // plain tags aren't releases
+    env.vcs.commit_file("a.rs", "// a", "add a");
+    env.vcs.push();
+    env.vcs.cmd(&["tag", "scratch"]);
+    env.vcs.cmd(&["push", "origin", "scratch"]);
+    env.party(&["points"]);
+    assert_eq!(env.get_points(), points_after_push + 26);
+
+    // a semver tag on a new commit, pushed along with it, is credited right away
+    env.vcs.commit_file("b.rs", "// b", "add b");
+    env.vcs.cmd(&["tag", "v1.1.0"]);
+    env.vcs.cmd(&["push", "origin", "main", "v1.1.0"]);
+    assert_eq!(env.get_points(), points_after_push + 52);
+}
+
+#[test]
+#[cfg(feature = "dev")]
+fn rejected_tag_push_earns_nothing_until_it_goes_through() {
+    let env = git_env();
+    env.party(&["init"]);
+    env.party(&["bonus", "ship_it", "1"]);
+
+    env.vcs.commit_file("README.md", "# Test", "initial commit");
+    env.vcs.ensure_main();
+    env.vcs.push();
+    let points_after_push = env.get_points();
+
+    // the remote turns tags away for now
+    let hook = env.remote_dir.path().join("hooks/pre-receive");
+    std::fs::write(
+        &hook,
+        "#!/bin/sh\nif grep -q refs/tags/; then echo 'no tags today' >&2; exit 1; fi\n",
+    )
+    .unwrap();
+    std::fs::set_permissions(&hook, std::os::unix::fs::PermissionsExt::from_mode(0o755)).unwrap();
+
+    env.vcs.cmd(&["tag", "-a", "v1.0.0", "-m", "first release"]);
+    assert!(env.vcs.try_cmd(&["push", "origin", "v1.0.0"]).is_err());
+    env.party(&["points"]);
+    assert_eq!(env.get_points(), points_after_push);
+
+    // trying again once the remote takes it earns the release
+    std::fs::remove_file(&hook).unwrap();
+    env.vcs.cmd(&["push", "origin", "v1.0.0"]);
+    env.party(&["points"]);
+    assert_eq!(env.get_points(), points_after_push + 25);
+}
+
+#[test]
+#[cfg(feature = "dev")]
+fn ignored_paths_do_not_count_towards_line_counts() {
+    let env = git_env();
+    env.party(&["init"]);
+    env.party(&["bonus", "one_line_change", "1"]);
+    env.vcs
+        .cmd(&["config", "--add", "party.ignore", "generated/"]);
+
+    env.vcs.commit_file("README.md", "# Test", "initial commit");
+    env.vcs.ensure_main();
+    env.vcs.push();
+    let points_after_push = env.get_points();
+
+    // a one-line change that also regenerates a lockfile and some generated code
+    // still counts as a one-line change (1 point + 5 for one_line_change at level 1)
+    let lockfile = "[[package]]\n".repeat(100);
+    std::fs::write(env.repo_dir.path().join("Cargo.lock"), lockfile).unwrap();
+    std::fs::create_dir(env.repo_dir.path().join("generated")).unwrap();
+    std::fs::write(
+        env.repo_dir.path().join("generated/api.rs"),
+        "// a\n// b\n// c\n",
+    )
+    .unwrap();
+    env.vcs.cmd(&["add", "Cargo.lock", "generated"]);
+    env.vcs.commit_file("notes.txt", "one line", "bump deps");
+    env.vcs.push();
+
+    assert_eq!(env.get_points(), points_after_push + 6);
+}
+
+#[test]
+fn trivial_commits_earn_nothing() {
+    let env = git_env();
+    env.party(&["init"]);
+
+    env.vcs.commit_file("a.rs", "fn a() {}\n", "initial commit");
+    env.vcs.ensure_main();
+    env.vcs.push();
+    let points_after_push = env.get_points();
+
+    // re-indenting a file only changes whitespace
+    env.vcs.commit_file("a.rs", "fn a() {\n}\n", "reformat");
+    env.vcs.push();
+    assert_eq!(env.get_points(), points_after_push);
+
+    // a commit and its revert cancel each other out
+    env.vcs.commit_file("b.rs", "fn b() {}\n", "add b");
+    env.vcs.cmd(&["revert", "--no-edit", "HEAD"]);
+    env.vcs.push();
+    assert_eq!(env.get_points(), points_after_push);
+
+    // real work still counts
+    env.vcs.commit_file("c.rs", "fn c() {}\n", "add c");
+    env.vcs.push();
+    assert_eq!(env.get_points(), points_after_push + 1);
+}
+
+#[test]
+fn bot_commits_earn_nothing() {
+    let env = git_env();
+    env.party(&["init"]);
+
+    env.vcs.commit_file("README.md", "# Test", "initial commit");
+    env.vcs.ensure_main();
+    env.vcs.push();
+    let points_after_push = env.get_points();
+
+    // a release commit that CI made on my behalf, landed alongside one of mine
+    std::fs::write(env.repo_dir.path().join("CHANGELOG.md"), "## v1.0.0").unwrap();
+    env.vcs.cmd(&["add", "CHANGELOG.md"]);
+    env.vcs.cmd(&[
+        "-c",
+        "user.name=github-actions[bot]",
+        "-c",
+        "user.email=41898282+github-actions[bot]@users.noreply.github.com",
+        "commit",
+        "--author",
+        "Test <test@example.com>",
+        "-m",
+        "Update changelog",
+    ]);
+    env.vcs.commit_file("a.rs", "fn a() {}", "add a");
+    env.vcs.push();
+
+    assert_eq!(env.get_points(), points_after_push + 1);
+}
+
+/// creates a bare repo and adds it to the test repo as a remote with the given name
+fn add_bare_remote(env: &common::TestEnv<common::Git<'static>>, name: &str) -> tempfile::TempDir {
+    let dir = tempfile::TempDir::new().expect("failed to create remote tempdir");
+    std::process::Command::new("git")
+        .args(["init", "--bare"])
+        .current_dir(dir.path())
+        .output()
+        .expect("failed to init bare remote");
+    env.vcs
+        .cmd(&["remote", "add", name, dir.path().to_str().unwrap()]);
+    dir
+}
+
+#[test]
+fn pushing_to_non_origin_remote_awards_points() {
+    let env = git_env();
+    env.party(&["init"]);
+
+    env.vcs.commit_file("README.md", "# Test", "initial commit");
+    env.vcs.ensure_main();
+    env.vcs.push();
+    let points_after_origin = env.get_points();
+
+    let _upstream = add_bare_remote(&env, "upstream");
+    env.vcs
+        .commit_file("src.rs", "fn main() {}", "add source file");
+    env.vcs.cmd(&["push", "upstream", "main"]);
+
+    assert_eq!(
+        env.get_points(),
+        points_after_origin + 1,
+        "pushing to a remote other than origin should award points"
+    );
+}
+
+#[test]
+fn pushing_same_commit_to_two_remotes_awards_points_once() {
+    let env = git_env();
+    let _upstream = add_bare_remote(&env, "upstream");
+    env.party(&["init"]);
+
+    env.vcs.commit_file("README.md", "# Test", "initial commit");
+    env.vcs.ensure_main();
+    env.vcs.push();
+    env.vcs.cmd(&["push", "upstream", "main"]);
+
+    // 10 starter + 1 commit, credited once
+    assert_eq!(env.get_points(), 11);
+
+    // same thing, but pushing to the non-origin remote first
+    env.vcs
+        .commit_file("src.rs", "fn main() {}", "add source file");
+    env.vcs.cmd(&["push", "upstream", "main"]);
+    env.vcs.push();
+
+    assert_eq!(
+        env.get_points(),
+        12,
+        "a commit pushed to two remotes should only be credited once"
+    );
+}
+
+/// appends `count` commits to `branch` using `git fast-import`, each adding a
+/// new file with a unique number of lines
+fn generate_commits(env: &common::TestEnv<common::Git<'static>>, branch: &str, count: usize) {
+    use std::io::Write;
+
+    let mut stream = String::new();
+    for i in 0..count {
+        let content: String = (0..=i % 20).map(|n| format!("line {n} of {i}\n")).collect();
+        stream.push_str(&format!(
+            "commit refs/heads/{branch}\ncommitter Test User <test@example.com> {} +0000\ndata 9\ngenerated\n",
+            1_700_000_000 + i
+        ));
+        if i == 0 {
+            stream.push_str(&format!("from refs/heads/{branch}^0\n"));
+        }
+        stream.push_str(&format!(
+            "M 644 inline gen/{branch}/file-{i}.txt\ndata {}\n{content}\n",
+            content.len()
+        ));
+    }
+
+    let mut child = std::process::Command::new("git")
+        .args(["fast-import", "--quiet", "--force"])
+        .current_dir(env.repo_dir.path())
+        .stdin(std::process::Stdio::piped())
+        .spawn()
+        .expect("failed to run fast-import");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stream.as_bytes())
+        .unwrap();
+    assert!(child.wait().unwrap().success());
+}
+
+/// the most git processes a single `party hook` run may spawn, regardless of push size
+const MAX_GIT_INVOCATIONS: usize = 8;
+
+#[test]
+fn large_push_uses_fixed_number_of_git_invocations() {
+    const BRANCHES: usize = 40;
+    const COMMITS: usize = 300;
+
+    let env = git_env();
+    env.party(&["init"]);
+
+    env.vcs.commit_file("README.md", "# Test", "initial commit");
+    env.vcs.ensure_main();
+    env.vcs.push();
+
+    // lots of remote branches, which used to be checked one by one for every commit
+    for i in 0..BRANCHES {
+        env.vcs.cmd(&["branch", &format!("branch-{i}"), "main"]);
+    }
+    env.vcs.cmd(&["push", "origin", "--all"]);
+    let points_before = env.get_points();
+
+    // push without the hook, then run it by hand so its git calls can be traced
+    env.party(&["uninit"]);
+    env.vcs.cmd(&["branch", "big-feature", "main"]);
+    generate_commits(&env, "main", COMMITS);
+    generate_commits(&env, "big-feature", COMMITS);
+    env.vcs.cmd(&["push", "origin", "main", "big-feature"]);
+
+    let trace_dir = tempfile::TempDir::new().unwrap();
+    let trace_file = trace_dir.path().join("trace.log");
+    let started = std::time::Instant::now();
+    env.party_with_env(&["hook"], &[("GIT_TRACE", trace_file.to_str().unwrap())]);
+    let elapsed = started.elapsed();
+
+    let trace = std::fs::read_to_string(&trace_file).unwrap_or_default();
+    let invocations = trace
+        .lines()
+        .filter(|l| l.contains("trace: built-in: git "))
+        .count();
+    assert_eq!(env.get_points(), points_before + 2 * COMMITS as u64);
+    assert!(
+        invocations <= MAX_GIT_INVOCATIONS,
+        "expected at most {MAX_GIT_INVOCATIONS} git invocations for {COMMITS} x 2 commits and {BRANCHES} branches, got {invocations}:\n{trace}"
+    );
+    assert!(
+        elapsed < std::time::Duration::from_secs(10),
+        "hook took {elapsed:?} ({invocations} git invocations)"
+    );
+}
+
+#[test]
+fn pushing_several_branches_at_once_awards_points_for_all() {
+    let env = git_env();
+    env.party(&["init"]);
+
+    env.vcs.commit_file("README.md", "# Test", "initial commit");
+    env.vcs.ensure_main();
+    env.vcs.push();
+    let points_after_main = env.get_points();
+
+    for branch in ["feature-a", "feature-b", "feature-c"] {
+        env.vcs.checkout("main");
+        env.vcs
+            .commit_file_on_branch(&format!("{branch}.rs"), branch, branch, branch);
+    }
+    env.vcs.cmd(&["push", "origin", "--all"]);
+
+    assert_eq!(
+        env.get_points(),
+        points_after_main + 3,
+        "every branch in a multi-branch push should be credited"
+    );
+}
+
+#[test]
+fn commits_by_other_authors_are_not_credited() {
+    let env = git_env();
+    env.party(&["init"]);
+
+    env.vcs.commit_file("README.md", "# Test", "initial commit");
+    env.vcs.ensure_main();
+    env.vcs.push();
+    let points_after_main = env.get_points();
+
+    // a teammate's commit that ends up on a branch I push
+    std::fs::write(env.repo_dir.path().join("pal.rs"), "// pal").unwrap();
+    env.vcs.cmd(&["add", "pal.rs"]);
+    env.vcs.cmd(&[
+        "commit",
+        "--author",
+        "Pal <pal@example.com>",
+        "-m",
+        "pal's work",
+    ]);
+    env.vcs.commit_file("mine.rs", "// mine", "my work");
+    env.vcs.push();
+
+    assert_eq!(
+        env.get_points(),
+        points_after_main + 1,
+        "only my own commit should be credited"
+    );
+
+    // adding the teammate's email as an alias later doesn't retroactively credit their commit,
+    // but new commits from that email now count
+    env.vcs
+        .cmd(&["config", "--add", "party.email", "pal@example.com"]);
+    env.vcs.cmd(&["checkout", "-b", "rebased", "HEAD~2"]);
+    env.vcs.cmd(&["cherry-pick", "main~1"]);
+    env.vcs.push_branch("rebased");
+    assert_eq!(env.get_points(), points_after_main + 1);
+
+    std::fs::write(env.repo_dir.path().join("pal2.rs"), "// pal 2").unwrap();
+    env.vcs.cmd(&["add", "pal2.rs"]);
+    env.vcs.cmd(&[
+        "commit",
+        "--author",
+        "Pal <pal@example.com>",
+        "-m",
+        "more of my work, under my other email",
+    ]);
+    env.vcs.push_branch("rebased");
+    assert_eq!(env.get_points(), points_after_main + 2);
+}
+
+/// writes an executable reference-transaction hook into the test repo
+fn write_existing_hook(env: &common::TestEnv<common::Git<'static>>, content: &str) -> PathBuf {
+    write_existing_hook_named(env, "reference-transaction", content)
+}
+
+/// writes an executable hook with the given name into the test repo
+fn write_existing_hook_named(
+    env: &common::TestEnv<common::Git<'static>>,
+    name: &str,
+    content: &str,
+) -> PathBuf {
+    let path = env.repo_dir.path().join(".git/hooks").join(name);
+    std::fs::write(&path, content).unwrap();
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    path
+}
+
+#[test]
+fn init_chains_with_existing_shell_hook() {
+    let env = git_env();
+
+    // something like what lefthook or husky would install, which also reads the ref updates
+    let hook = "#!/bin/sh\ncat >> \"$(git rev-parse --git-dir)/hook.log\"\n";
+    let hook_path = write_existing_hook(&env, hook);
+
+    env.party(&["init"]);
+    env.party(&["init"]);
+    let installed = std::fs::read_to_string(&hook_path).unwrap();
+    assert_eq!(
+        installed.matches("party hook").count(),
+        1,
+        "running init twice should only add party once"
+    );
+
+    env.vcs.commit_file("README.md", "# Test", "initial commit");
+    env.vcs.ensure_main();
+    env.vcs.push();
+
+    assert_eq!(env.get_points(), 11, "party should still run");
+    let log = std::fs::read_to_string(env.repo_dir.path().join(".git/hook.log")).unwrap();
+    assert!(
+        log.contains("refs/remotes/origin/main"),
+        "existing hook should still run and see its stdin"
+    );
+
+    env.party(&["uninit"]);
+    assert_eq!(std::fs::read_to_string(&hook_path).unwrap(), hook);
+}
+
+#[test]
+fn init_chains_with_existing_pre_push_hook() {
+    let env = git_env();
+
+    let hook = "#!/bin/sh\ncat >> \"$(git rev-parse --git-dir)/pre-push.log\"\n";
+    let hook_path = write_existing_hook_named(&env, "pre-push", hook);
+
+    env.party(&["init"]);
+
+    env.vcs.commit_file("README.md", "# Test", "initial commit");
+    env.vcs.ensure_main();
+    env.vcs.push();
+    env.vcs.cmd(&["tag", "-a", "v1.0.0", "-m", "first release"]);
+    env.vcs.cmd(&["push", "origin", "v1.0.0"]);
+
+    let log = std::fs::read_to_string(env.repo_dir.path().join(".git/pre-push.log")).unwrap();
+    assert!(
+        log.contains("refs/heads/main") && log.contains("refs/tags/v1.0.0"),
+        "existing hook should still run and see its stdin"
+    );
+
+    env.party(&["uninit"]);
+    assert_eq!(std::fs::read_to_string(&hook_path).unwrap(), hook);
+}
+
+#[test]
+fn init_wraps_existing_non_shell_hook() {
+    let env = git_env();
+
+    let hook =
+        "#!/usr/bin/env perl\nopen(my $f, '>>', '.git/hook.log'); print $f $_ while <STDIN>;\n";
+    let hook_path = write_existing_hook(&env, hook);
+
+    env.party(&["init"]);
+    assert!(
+        env.repo_dir
+            .path()
+            .join(".git/hooks/reference-transaction.d")
+            .is_dir()
+    );
+
+    env.vcs.commit_file("README.md", "# Test", "initial commit");
+    env.vcs.ensure_main();
+    env.vcs.push();
+
+    assert_eq!(env.get_points(), 11, "party should still run");
+    let log = std::fs::read_to_string(env.repo_dir.path().join(".git/hook.log")).unwrap();
+    assert!(
+        log.contains("refs/remotes/origin/main"),
+        "wrapped hook should still run and see its stdin"
+    );
+
+    env.party(&["uninit"]);
+    assert_eq!(std::fs::read_to_string(&hook_path).unwrap(), hook);
+    assert!(
+        std::fs::metadata(&hook_path).unwrap().permissions().mode() & 0o111 != 0,
+        "restored hook should still be executable"
+    );
+    assert!(
+        !env.repo_dir
+            .path()
+            .join(".git/hooks/reference-transaction.d")
+            .exists()
+    );
+}
+
+#[test]
+fn global_init_seeds_unseen_repos_and_keeps_local_hooks() {
+    let env = git_env();
+
+    // history pushed before party was ever installed
+    env.vcs.commit_file("README.md", "# Test", "initial commit");
+    env.vcs.ensure_main();
+    env.vcs.push();
+
+    // a repo-local hook that should keep running
+    std::fs::write(
+        env.repo_dir.path().join(".git/hooks/post-commit"),
+        "#!/bin/sh\ntouch \"$(git rev-parse --git-dir)/post-commit-ran\"\n",
+    )
+    .unwrap();
+    std::fs::set_permissions(
+        env.repo_dir.path().join(".git/hooks/post-commit"),
+        std::fs::Permissions::from_mode(0o755),
+    )
+    .unwrap();
+
+    env.party(&["init", "--global"]);
+    let hooks_path = env.vcs.cmd(&["config", "--global", "core.hooksPath"]);
+    assert!(!hooks_path.trim().is_empty());
+    assert!(
+        !env.repo_dir
+            .path()
+            .join(".git/hooks/reference-transaction")
+            .exists(),
+        "a global install shouldn't touch the repo's hooks"
+    );
+
+    env.vcs
+        .commit_file("feature.rs", "// feature", "feature work");
+    env.vcs.push();
+
+    assert_eq!(
+        env.get_points(),
+        11,
+        "only the commit made after the global install should count"
+    );
+    assert!(
+        env.repo_dir.path().join(".git/post-commit-ran").exists(),
+        "the repo's own hooks should still run"
+    );
+
+    env.party(&["uninit", "--global"]);
+    assert!(
+        env.vcs
+            .try_cmd(&["config", "--global", "core.hooksPath"])
+            .is_err(),
+        "core.hooksPath should be unset"
+    );
+    assert!(!std::path::Path::new(hooks_path.trim()).exists());
+
+    // no more points once uninstalled
+    env.vcs.commit_file("more.rs", "// more", "more work");
+    env.vcs.push();
+    assert_eq!(env.get_points(), 11);
+}
+
+#[test]
+fn global_init_with_local_init_awards_points_once() {
+    let env = git_env();
+    env.party(&["init"]);
+    env.party(&["init", "--global"]);
+
+    env.vcs.commit_file("README.md", "# Test", "initial commit");
+    env.vcs.ensure_main();
+    env.vcs.push();
+
+    assert_eq!(env.get_points(), 11);
+}
+
+#[test]
+fn global_init_refuses_to_replace_existing_hooks_path() {
+    let env = git_env();
+    env.vcs
+        .cmd(&["config", "--global", "core.hooksPath", "/somewhere/else"]);
+
+    assert!(env.try_party(&["init", "--global"]).is_err());
+    assert_eq!(
+        env.vcs
+            .cmd(&["config", "--global", "core.hooksPath"])
+            .trim(),
+        "/somewhere/else"
+    );
+}
+
+#[test]
+fn init_from_subdirectory_installs_at_repo_root() {
+    let env = git_env();
+    let subdir = env.repo_dir.path().join("src/nested");
+    std::fs::create_dir_all(&subdir).unwrap();
+
+    env.party_in(&subdir, &["init"]);
+    assert!(
+        env.repo_dir
+            .path()
+            .join(".git/hooks/reference-transaction")
+            .exists()
+    );
+
+    env.vcs.commit_file("README.md", "# Test", "initial commit");
+    env.vcs.ensure_main();
+    env.vcs.push();
+    assert_eq!(env.get_points(), 11);
+
+    env.party_in(&subdir, &["uninit"]);
+    assert!(
+        !env.repo_dir
+            .path()
+            .join(".git/hooks/reference-transaction")
+            .exists()
+    );
+}
+
+#[test]
+fn init_and_push_from_linked_worktree() {
+    let env = git_env();
+    env.vcs.commit_file("README.md", "# Test", "initial commit");
+    env.vcs.ensure_main();
+    env.vcs.push();
+
+    let worktree = tempfile::TempDir::new().unwrap();
+    let wt = worktree.path().join("wt");
+    let wt_str = wt.to_str().unwrap();
+    env.vcs.cmd(&["worktree", "add", "-b", "feature", wt_str]);
+
+    env.party_in(&wt, &["init"]);
+    assert!(
+        env.repo_dir
+            .path()
+            .join(".git/hooks/reference-transaction")
+            .exists(),
+        "hooks are shared by all worktrees, so they go in the main git dir"
+    );
+
+    std::fs::write(wt.join("feature.rs"), "// feature").unwrap();
+    env.vcs.cmd(&["-C", wt_str, "add", "feature.rs"]);
+    env.vcs.cmd(&["-C", wt_str, "commit", "-m", "feature work"]);
+    env.vcs
+        .cmd(&["-C", wt_str, "push", "-u", "origin", "feature"]);
+
+    assert_eq!(env.get_points(), 11);
+}
+
+#[test]
+fn init_and_push_from_submodule() {
+    let env = git_env();
+    env.vcs
+        .cmd(&["config", "--global", "protocol.file.allow", "always"]);
+    env.vcs
+        .cmd(&["config", "--global", "user.email", "test@example.com"]);
+    env.vcs
+        .cmd(&["config", "--global", "user.name", "Test User"]);
+    env.vcs.commit_file("README.md", "# Test", "initial commit");
+    env.vcs.ensure_main();
+
+    // a separate repo with some history, to be used as a submodule
+    let sub_remote = tempfile::TempDir::new().unwrap();
+    let sub_remote_str = sub_remote.path().to_str().unwrap();
+    env.vcs.cmd(&["init", "--bare", sub_remote_str]);
+    env.vcs.cmd(&[
+        "-C",
+        sub_remote_str,
+        "symbolic-ref",
+        "HEAD",
+        "refs/heads/main",
+    ]);
+    let seed = tempfile::TempDir::new().unwrap();
+    let seed_str = seed.path().to_str().unwrap();
+    env.vcs.cmd(&["clone", sub_remote_str, seed_str]);
+    std::fs::write(seed.path().join("lib.rs"), "// lib").unwrap();
+    env.vcs.cmd(&["-C", seed_str, "add", "lib.rs"]);
+    env.vcs.cmd(&["-C", seed_str, "commit", "-m", "lib"]);
+    env.vcs
+        .cmd(&["-C", seed_str, "push", "origin", "HEAD:refs/heads/main"]);
+
+    env.vcs
+        .cmd(&["submodule", "add", "-b", "main", sub_remote_str, "sub"]);
+    let sub = env.repo_dir.path().join("sub");
+    let sub_str = sub.to_str().unwrap();
+
+    env.party_in(&sub, &["init"]);
+    assert!(
+        env.repo_dir
+            .path()
+            .join(".git/modules/sub/hooks/reference-transaction")
+            .exists(),
+        "a submodule's hooks live in the superproject's git dir"
+    );
+
+    std::fs::write(sub.join("more.rs"), "// more").unwrap();
+    env.vcs.cmd(&["-C", sub_str, "add", "more.rs"]);
+    env.vcs.cmd(&["-C", sub_str, "commit", "-m", "more lib"]);
+    env.vcs.cmd(&["-C", sub_str, "push", "origin", "main"]);
+
+    assert_eq!(
+        env.get_points(),
+        11,
+        "only the commit pushed after init should count"
+    );
+}
+
+#[test]
+fn changing_how_the_remote_url_is_written_keeps_the_same_repo() {
+    let env = git_env();
+    env.party(&["init"]);
+
+    env.vcs.commit_file("README.md", "# Test", "initial commit");
+    env.vcs.ensure_main();
+    env.vcs.push();
+    assert_eq!(env.get_points(), 11);
+
+    // same remote, spelled differently
+    let url = format!("file://{}/", env.remote_dir.path().display());
+    env.vcs.cmd(&["remote", "set-url", "origin", &url]);
+
+    env.vcs
+        .commit_file("feature.rs", "// feature", "feature work");
+    env.vcs.push();
+    assert_eq!(
+        env.get_points(),
+        12,
+        "the new url should not look like a repo party has never seen"
+    );
+}
+
+/// pushes some history before party is installed: 3 commits on main and 1 on a branch
+fn push_history_before_init(env: &common::TestEnv<common::Git<'static>>) {
+    env.vcs.commit_file("README.md", "# Test", "initial commit");
+    env.vcs.ensure_main();
+    env.vcs.push();
+    env.vcs.commit_file("a.rs", "// a", "a");
+    env.vcs.commit_file("b.rs", "// b", "b");
+    env.vcs.push();
+
+    env.vcs
+        .commit_file_on_branch("feature.rs", "// feature", "feature work", "feature");
+    env.vcs.push_branch("feature");
+    env.vcs.checkout("main");
+
+    // someone else's work that only arrives by fetching
+    env.simulate_external_push_to_main("theirs.rs", "// theirs", "their work");
+    env.vcs.fetch();
+}
+
+#[test]
+fn backfill_imports_past_pushes_without_points() {
+    let env = git_env();
+    push_history_before_init(&env);
+    env.party(&["init"]);
+
+    let output = env.party(&["backfill"]);
+    assert!(output.contains("with 4 commits"), "{output}");
+    assert_eq!(env.get_points(), 10, "backfill shouldn't award points");
+
+    let output = env.party(&["backfill"]);
+    assert!(
+        output.contains("no new pushes"),
+        "running again shouldn't import anything: {output}"
+    );
+
+    // pushing afterwards still works as usual
+    env.vcs.pull_and_rebase();
+    env.vcs.commit_file("c.rs", "// c", "c");
+    env.vcs.push();
+    assert_eq!(env.get_points(), 11);
+}
+
+#[test]
+fn backfill_awards_points_when_asked() {
+    let env = git_env();
+    push_history_before_init(&env);
+    env.party(&["init"]);
+
+    env.party(&["backfill", "--award-points"]);
+    assert_eq!(env.get_points(), 14);
+
+    env.party(&["backfill", "--award-points"]);
+    assert_eq!(env.get_points(), 14, "points should only be awarded once");
+}
+
+#[test]
+fn watch_queues_pushes_made_without_hooks() {
+    let env = git_env();
+
+    // no hooks installed, so only the watcher sees these pushes
+    env.vcs.commit_file("README.md", "# Test", "initial commit");
+    env.vcs.ensure_main();
+    env.vcs.push();
+    let output = env.party(&["watch", "--add"]);
+    assert!(output.contains("now watching"), "{output}");
+
+    env.vcs.commit_file("a.rs", "// a", "a");
+    env.vcs.commit_file("b.rs", "// b", "b");
+    env.vcs.push();
+    let output = env.party(&["watch", "--once"]);
+    assert!(output.contains("queued a party for 2 commits"), "{output}");
+    assert_eq!(env.get_points(), 0, "queued pushes aren't scored yet");
+
+    env.party(&["pending"]);
+    assert_eq!(env.get_points(), 2);
+    env.party(&["pending"]);
+    assert_eq!(env.get_points(), 2, "a queued party is only thrown once");
+}
+
+#[test]
+fn watch_does_not_double_credit_hooked_pushes() {
+    let env = git_env();
+    env.party(&["init"]);
+    env.party(&["watch", "--add"]);
+
+    env.vcs.commit_file("README.md", "# Test", "initial commit");
+    env.vcs.ensure_main();
+    env.vcs.push();
+    assert_eq!(env.get_points(), 11);
+
+    let output = env.party(&["watch", "--once"]);
+    assert!(!output.contains("queued"), "{output}");
+    env.party(&["pending"]);
+    assert_eq!(
+        env.get_points(),
+        11,
+        "the hook already credited this push, so the watcher shouldn't"
+    );
+}
+
+/// installs a `post-receive` hook in the test's bare remote
+fn write_post_receive_hook(env: &common::TestEnv<common::Git<'static>>, content: &str) {
+    let path = env.remote_dir.path().join("hooks/post-receive");
+    std::fs::write(&path, content).unwrap();
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+}
+
+#[test]
+fn receive_credits_each_author_in_their_own_ledger() {
+    let env = git_env();
+    write_post_receive_hook(&env, "#!/bin/sh\nparty receive\n");
+
+    env.vcs.commit_file("README.md", "# Test", "initial commit");
+    env.vcs.ensure_main();
+    std::fs::write(env.repo_dir.path().join("ada.rs"), "// ada").unwrap();
+    env.vcs.cmd(&["add", "ada.rs"]);
+    env.vcs.cmd(&[
+        "commit",
+        "--author",
+        "Ada <Ada@example.com>",
+        "-m",
+        "ada's work",
+    ]);
+
+    let output = env.vcs.cmd(&["push", "-u", "origin", "main"]);
+    assert!(
+        output.contains("test@example.com earned") && output.contains("ada@example.com earned"),
+        "{output}"
+    );
+    let ledgers = env.state_dir.path().join("ledgers");
+    assert!(ledgers.join("test@example.com.db").exists());
+    assert!(ledgers.join("ada@example.com.db").exists());
+    assert_eq!(
+        env.get_points(),
+        0,
+        "the server's ledgers aren't the client's"
+    );
+
+    // the same commits on another branch aren't credited again
+    let output = env.vcs.cmd(&["push", "origin", "main:copy"]);
+    assert!(!output.contains("earned"), "{output}");
+}
+
+#[test]
+fn receive_credits_the_pusher_when_asked() {
+    let env = git_env();
+    write_post_receive_hook(
+        &env,
+        "#!/bin/sh\nPARTY_PUSHER=ada party receive --by pusher\n",
+    );
+
+    env.vcs.commit_file("README.md", "# Test", "initial commit");
+    env.vcs.ensure_main();
+    env.vcs.commit_file("a.rs", "// a", "a");
+
+    let output = env.vcs.cmd(&["push", "-u", "origin", "main"]);
+    assert!(output.contains("ada earned"), "{output}");
+    assert!(output.contains("for 2 commits"), "{output}");
+    assert!(env.state_dir.path().join("ledgers/ada.db").exists());
+}
+
+#[test]
+fn repos_lists_prunes_and_uninstalls_registered_repos() {
+    let env = git_env();
+    env.party(&["init"]);
+
+    let other = tempfile::tempdir().unwrap();
+    let other_path = other.path().canonicalize().unwrap();
+    let status = std::process::Command::new("git")
+        .args(["init", "-q"])
+        .current_dir(&other_path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+    env.party_in(&other_path, &["init"]);
+
+    // watched repos are registered too, with or without the hook
+    let watched = tempfile::tempdir().unwrap();
+    let watched_path = watched.path().canonicalize().unwrap();
+    let status = std::process::Command::new("git")
+        .args(["init", "-q"])
+        .current_dir(&watched_path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+    let repo_path = env.repo_dir.path().canonicalize().unwrap();
+    env.party(&["watch", "--add", &*watched_path.to_string_lossy()]);
+    env.party(&["watch", "--add", &*repo_path.to_string_lossy()]);
+
+    let listed = env.party(&["repos"]);
+    let line_for = |path: &std::path::Path| {
+        listed
+            .lines()
+            .find(|l| l.ends_with(&*path.to_string_lossy()))
+            .unwrap_or_else(|| panic!("{} should be listed:\n{listed}", path.display()))
+            .to_string()
+    };
+    for path in [&repo_path, &other_path] {
+        let line = line_for(path);
+        assert!(line.starts_with("ok"), "{line}");
+    }
+    assert!(line_for(&repo_path).contains("watched"));
+    let line = line_for(&watched_path);
+    assert!(line.starts_with('-') && line.contains("watched"), "{line}");
+
+    // repos that were deleted get pruned, whether hooked or watched
+    drop(other);
+    drop(watched);
+    assert!(env.party(&["repos"]).contains("gone"));
+    let output = env.party(&["repos", "--prune"]);
+    assert!(output.contains("removed 2 stale repos"), "{output}");
+    let listed = env.party(&["repos"]);
+    assert!(!listed.contains(&*other_path.to_string_lossy()));
+    assert!(!listed.contains(&*watched_path.to_string_lossy()));
+
+    let output = env.party(&["repos", "--uninstall"]);
+    assert!(output.contains("removed party from 1 repos"), "{output}");
+    assert!(
+        env.party(&["watch", "--list"])
+            .contains("no repos are being watched")
+    );
+    assert!(
+        !env.repo_dir
+            .path()
+            .join(".git/hooks/reference-transaction")
+            .exists()
+    );
+    assert!(env.party(&["repos"]).contains("no repos registered"));
+}
+
+#[test]
+fn simulate_previews_unpushed_commits_without_saving_anything() {
+    let env = git_env();
+    env.party(&["init"]);
+
+    env.vcs.commit_file("README.md", "# Test", "initial commit");
+    env.vcs.ensure_main();
+    env.vcs.push();
+
+    env.vcs.commit_file("a.rs", "fn a() {}\n", "add a");
+    env.vcs.commit_file("b.rs", "fn b() {}\n", "add b");
+    let dump_before = env.party(&["dump"]);
+
+    let output = env.party(&["simulate"]);
+    assert!(output.contains("pushing 2 commits to main"), "{output}");
+    let preview: u64 = output
+        .lines()
+        .find_map(|l| l.strip_prefix("would earn "))
+        .and_then(|l| l.split_whitespace().next())
+        .and_then(|n| n.parse().ok())
+        .unwrap_or_else(|| panic!("no total in:\n{output}"));
+    assert_eq!(env.party(&["dump"]), dump_before, "nothing should change");
+
+    let points_before = env.get_points();
+    env.vcs.push();
+    assert_eq!(env.get_points(), points_before + preview);
+
+    // synthetic pushes work outside a repo too
+    let elsewhere = tempfile::tempdir().unwrap();
+    let output = env.party_in(elsewhere.path(), &["simulate", "--commits", "3"]);
+    assert!(output.contains("would earn 3 party points"), "{output}");
+}
+
+#[test]
+fn balance_file_overrides_built_in_numbers() {
+    let env = git_env();
+    let balance_path = env.state_dir.path().join("balance.json");
+    let elsewhere = tempfile::tempdir().unwrap();
+    let commit_value = r#"{
+        "version": 1,
+        "bonus_tracks": {
+            "commit_value": [
+                { "cost": 0, "points": 2 },
+                { "cost": 50, "points": 3 },
+                { "cost": 500, "points": 4 },
+                { "cost": 5000, "points": 5 },
+                { "cost": 50000, "points": 6 }
+            ]
+        }
+    }"#;
+
+    std::fs::write(&balance_path, commit_value).unwrap();
+    let output = env.party_in(elsewhere.path(), &["simulate", "--commits", "3"]);
+    assert!(output.contains("would earn 6 party points"), "{output}");
+
+    // tiers that get cheaper are rejected, and the built-in ones are used
+    std::fs::write(&balance_path, commit_value.replace("5000,", "5,")).unwrap();
+    let output = env.party_in(elsewhere.path(), &["simulate", "--commits", "3"]);
+    assert!(output.contains("ignoring balance file"), "{output}");
+    assert!(output.contains("would earn 3 party points"), "{output}");
+}
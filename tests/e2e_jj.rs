@@ -103,3 +103,52 @@ fn duplicate_feature_onto_fetched_trunk_only_awards_for_my_work() {
         "duplicating feature onto fetched trunk should not award points for fetched commits"
     );
 }
+
+#[test]
+fn plain_jj_git_push_is_credited_next_time_party_runs() {
+    let env = jj_env();
+    env.party(&["init"]);
+
+    env.vcs.commit_file("README.md", "# Test", "initial commit");
+    env.vcs.raw_push();
+    assert_eq!(
+        env.get_points(),
+        10,
+        "nothing runs party after a plain push"
+    );
+
+    let output = env.party(&["points"]);
+    assert!(
+        output.contains("credited 1 jj push from"),
+        "party should find the push in the operation log: {output}"
+    );
+    assert_eq!(env.get_points(), 11);
+
+    // and only once
+    env.party(&["points"]);
+    assert_eq!(env.get_points(), 11);
+}
+
+#[test]
+fn plain_git_push_in_colocated_repo_awards_points() {
+    let env = jj_env();
+    env.vcs
+        .cmd(&["config", "set", "--repo", "user.email", "test@example.com"]);
+    env.git(&["config", "user.email", "test@example.com"]);
+    env.party(&["init"]);
+    // git's own hooks, which see pushes that don't go through jj
+    env.party(&["init", "--global"]);
+
+    env.vcs.commit_file("README.md", "# Test", "initial commit");
+    env.git(&["push", "origin", "main"]);
+    assert_eq!(
+        env.get_points(),
+        11,
+        "a git push isn't in jj's operation log, but should still count"
+    );
+
+    // jj picking up the pushed ref afterwards doesn't count it again
+    env.vcs.fetch();
+    env.party(&["points"]);
+    assert_eq!(env.get_points(), 11);
+}